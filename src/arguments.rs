@@ -0,0 +1,527 @@
+//
+// Typed statement arguments
+//
+// The parser only distinguishes strings, numbers, dates and "other" values. This module parses the
+// argument of each statement into the type described by its ABNF rule, such as "status-arg" or
+// "max-value-arg", with errors that point at the offending value.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexing::Span;
+use crate::parsing::{Node, StatementKeyword, IDENTIFIER_PATTERN};
+
+/// An argument that doesn't match the ABNF rule of its statement
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgumentError {
+    pub keyword: String,
+    pub argument: String,
+    pub expected: &'static str,
+    pub span: Span,
+}
+
+impl Display for ArgumentError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Invalid argument {:?} for {:?}, expected {}",
+            self.argument, self.keyword, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ArgumentError {}
+
+/// A type that a statement argument can be parsed into
+pub trait ArgumentType: Sized {
+    /// A description of valid arguments, used in error messages
+    const EXPECTED: &'static str;
+
+    /// Parses the argument, or returns None if it's invalid
+    fn parse(text: &str) -> Option<Self>;
+}
+
+/// See "yang-version-arg" from ABNF, which also allows "1" for YANG 1.0 modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YangVersion {
+    V1,
+    V1_1,
+}
+
+/// See "status-arg" from ABNF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Current,
+    Deprecated,
+    Obsolete,
+}
+
+/// See "ordered-by-arg" from ABNF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderedBy {
+    User,
+    System,
+}
+
+/// See "max-value-arg" from ABNF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaxValue {
+    Unbounded,
+    Value(u64),
+}
+
+/// See "fraction-digits-arg" from ABNF, a number from 1 to 18
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FractionDigits(pub u8);
+
+/// See "modifier-arg" from ABNF
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    InvertMatch,
+}
+
+/// The argument of the "deviate" statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviateKind {
+    NotSupported,
+    Add,
+    Replace,
+    Delete,
+}
+
+/// See "date-arg" from ABNF, which must also be a valid calendar date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RevisionDate {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+}
+
+/// See "identifier-ref" from ABNF
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IdentifierRef {
+    pub prefix: Option<String>,
+    pub name: String,
+}
+
+/// See "identifier" from ABNF, also used for "prefix-arg"
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Identifier(pub String);
+
+impl ArgumentType for YangVersion {
+    const EXPECTED: &'static str = "\"1\" or \"1.1\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "1" => Some(YangVersion::V1),
+            "1.1" => Some(YangVersion::V1_1),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for Status {
+    const EXPECTED: &'static str = "\"current\", \"deprecated\" or \"obsolete\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "current" => Some(Status::Current),
+            "deprecated" => Some(Status::Deprecated),
+            "obsolete" => Some(Status::Obsolete),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for bool {
+    const EXPECTED: &'static str = "\"true\" or \"false\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for OrderedBy {
+    const EXPECTED: &'static str = "\"user\" or \"system\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "user" => Some(OrderedBy::User),
+            "system" => Some(OrderedBy::System),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for MaxValue {
+    const EXPECTED: &'static str = "\"unbounded\" or a positive integer";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "unbounded" => Some(MaxValue::Unbounded),
+            text => match parse_non_negative(text)? {
+                0 => None,
+                value => Some(MaxValue::Value(value)),
+            },
+        }
+    }
+}
+
+impl ArgumentType for u64 {
+    const EXPECTED: &'static str = "a non-negative integer";
+
+    fn parse(text: &str) -> Option<Self> {
+        parse_non_negative(text)
+    }
+}
+
+impl ArgumentType for u32 {
+    const EXPECTED: &'static str = "an integer from 0 to 4294967295";
+
+    fn parse(text: &str) -> Option<Self> {
+        parse_non_negative(text)?.try_into().ok()
+    }
+}
+
+impl ArgumentType for i32 {
+    const EXPECTED: &'static str = "an integer from -2147483648 to 2147483647";
+
+    fn parse(text: &str) -> Option<Self> {
+        // See "integer-value" from ABNF, which doesn't allow "+", "-0" or leading zeros
+        match text.strip_prefix('-') {
+            Some("0") => None,
+            Some(digits) => {
+                parse_non_negative(digits)?;
+                text.parse().ok()
+            }
+            None => parse_non_negative(text)?.try_into().ok(),
+        }
+    }
+}
+
+impl ArgumentType for FractionDigits {
+    const EXPECTED: &'static str = "an integer from 1 to 18";
+
+    fn parse(text: &str) -> Option<Self> {
+        match parse_non_negative(text)? {
+            digits @ 1..=18 => Some(FractionDigits(digits as u8)),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for Modifier {
+    const EXPECTED: &'static str = "\"invert-match\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        (text == "invert-match").then_some(Modifier::InvertMatch)
+    }
+}
+
+impl ArgumentType for DeviateKind {
+    const EXPECTED: &'static str = "\"not-supported\", \"add\", \"replace\" or \"delete\"";
+
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "not-supported" => Some(DeviateKind::NotSupported),
+            "add" => Some(DeviateKind::Add),
+            "replace" => Some(DeviateKind::Replace),
+            "delete" => Some(DeviateKind::Delete),
+            _ => None,
+        }
+    }
+}
+
+impl ArgumentType for RevisionDate {
+    const EXPECTED: &'static str = "a date on the form YYYY-MM-DD";
+
+    fn parse(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+
+        if !text.is_ascii() || bytes.len() != 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+            return None;
+        }
+
+        let number = |range: std::ops::Range<usize>| -> Option<u16> {
+            let digits = &text[range];
+            digits
+                .bytes()
+                .all(|c| c.is_ascii_digit())
+                .then(|| digits.parse().ok())?
+        };
+
+        let year = number(0..4)?;
+        let month = number(5..7)? as u8;
+        let day = number(8..10)? as u8;
+
+        let leap_year = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+        let days_in_month = match month {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if leap_year => 29,
+            2 => 28,
+            _ => return None,
+        };
+
+        (1..=days_in_month)
+            .contains(&day)
+            .then_some(RevisionDate { year, month, day })
+    }
+}
+
+impl ArgumentType for Identifier {
+    const EXPECTED: &'static str = "an identifier";
+
+    fn parse(text: &str) -> Option<Self> {
+        IDENTIFIER_PATTERN
+            .is_match(text)
+            .then(|| Identifier(text.to_string()))
+    }
+}
+
+impl ArgumentType for IdentifierRef {
+    const EXPECTED: &'static str = "an identifier with an optional prefix";
+
+    fn parse(text: &str) -> Option<Self> {
+        let (prefix, name) = match text.split_once(':') {
+            Some((prefix, name)) => (Some(prefix), name),
+            None => (None, text),
+        };
+
+        if !prefix.is_none_or(|prefix| IDENTIFIER_PATTERN.is_match(prefix))
+            || !IDENTIFIER_PATTERN.is_match(name)
+        {
+            return None;
+        }
+
+        Some(IdentifierRef {
+            prefix: prefix.map(str::to_string),
+            name: name.to_string(),
+        })
+    }
+}
+
+/// Parses a "non-negative-integer-value", which doesn't allow leading zeros
+fn parse_non_negative(text: &str) -> Option<u64> {
+    let valid = match text.as_bytes() {
+        [] => false,
+        [b'0'] => true,
+        [b'0', ..] => false,
+        bytes => bytes.iter().all(u8::is_ascii_digit),
+    };
+
+    valid.then(|| text.parse().ok())?
+}
+
+impl Display for RevisionDate {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+impl Display for IdentifierRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.prefix {
+            Some(prefix) => write!(f, "{}:{}", prefix, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// The argument of a statement, parsed according to its keyword
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Argument {
+    YangVersion(YangVersion),
+    Status(Status),
+    /// The argument of "config", "mandatory", "require-instance" and "yin-element"
+    Boolean(bool),
+    OrderedBy(OrderedBy),
+    MaxValue(MaxValue),
+    /// The argument of "min-elements"
+    MinValue(u64),
+    /// The argument of "position"
+    Position(u32),
+    /// The argument of "value"
+    Value(i32),
+    FractionDigits(FractionDigits),
+    Modifier(Modifier),
+    DeviateKind(DeviateKind),
+    /// The argument of "revision" and "revision-date"
+    Date(RevisionDate),
+    /// The name of a module, a prefix, or the name of anything defined by a statement
+    Identifier(Identifier),
+    /// The argument of "type", "uses" and "base"
+    IdentifierRef(IdentifierRef),
+    /// Any other argument, such as descriptions, XPath expressions and schema node identifiers
+    String(String),
+}
+
+/// Parses the argument of a statement according to its keyword
+///
+/// Returns None if the statement has no argument. Arguments of extension statements and
+/// statements without a more specific type are returned as plain strings.
+///
+pub fn parse_argument(node: &Node) -> Result<Option<Argument>, ArgumentError> {
+    let (argument, span) = match (node.argument(), node.value_span()) {
+        (Some(argument), Some(span)) => (argument, span),
+        _ => return Ok(None),
+    };
+
+    let keyword = match node.keyword() {
+        Some(StatementKeyword::Keyword(keyword)) => keyword.as_str(),
+        _ => return Ok(Some(Argument::String(argument.into_owned()))),
+    };
+
+    fn typed<T: ArgumentType>(
+        keyword: &str,
+        argument: &str,
+        span: Span,
+    ) -> Result<T, ArgumentError> {
+        T::parse(argument).ok_or_else(|| ArgumentError {
+            keyword: keyword.to_string(),
+            argument: argument.to_string(),
+            expected: T::EXPECTED,
+            span,
+        })
+    }
+
+    let parsed = match keyword {
+        "yang-version" => Argument::YangVersion(typed(keyword, &argument, span)?),
+        "status" => Argument::Status(typed(keyword, &argument, span)?),
+        "config" | "mandatory" | "require-instance" | "yin-element" => {
+            Argument::Boolean(typed(keyword, &argument, span)?)
+        }
+        "ordered-by" => Argument::OrderedBy(typed(keyword, &argument, span)?),
+        "max-elements" => Argument::MaxValue(typed(keyword, &argument, span)?),
+        "min-elements" => Argument::MinValue(typed(keyword, &argument, span)?),
+        "position" => Argument::Position(typed(keyword, &argument, span)?),
+        "value" => Argument::Value(typed(keyword, &argument, span)?),
+        "fraction-digits" => Argument::FractionDigits(typed(keyword, &argument, span)?),
+        "modifier" => Argument::Modifier(typed(keyword, &argument, span)?),
+        "deviate" => Argument::DeviateKind(typed(keyword, &argument, span)?),
+        "revision" | "revision-date" => Argument::Date(typed(keyword, &argument, span)?),
+        "action" | "anydata" | "anyxml" | "argument" | "belongs-to" | "bit" | "case" | "choice"
+        | "container" | "extension" | "feature" | "grouping" | "identity" | "import"
+        | "include" | "leaf" | "leaf-list" | "list" | "module" | "notification" | "prefix"
+        | "rpc" | "submodule" | "typedef" => Argument::Identifier(typed(keyword, &argument, span)?),
+        "type" | "uses" | "base" => Argument::IdentifierRef(typed(keyword, &argument, span)?),
+        _ => Argument::String(argument.into_owned()),
+    };
+
+    Ok(Some(parsed))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsing::parse;
+    use pretty_assertions::assert_eq;
+
+    fn argument(statement: &str) -> Result<Option<Argument>, ArgumentError> {
+        let tree = parse(statement.as_bytes()).expect("Failed to parse YANG");
+        parse_argument(&tree.children[0])
+    }
+
+    #[test]
+    fn arguments() {
+        assert_eq!(
+            Ok(Some(Argument::YangVersion(YangVersion::V1_1))),
+            argument("yang-version 1.1;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::Status(Status::Deprecated))),
+            argument("status \"deprecated\";")
+        );
+        assert_eq!(
+            Ok(Some(Argument::Boolean(false))),
+            argument("config false;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::OrderedBy(OrderedBy::User))),
+            argument("ordered-by user;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::MaxValue(MaxValue::Unbounded))),
+            argument("max-elements unbounded;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::MaxValue(MaxValue::Value(10)))),
+            argument("max-elements 10;")
+        );
+        assert_eq!(Ok(Some(Argument::MinValue(0))), argument("min-elements 0;"));
+        assert_eq!(Ok(Some(Argument::Value(-42))), argument("value -42;"));
+        assert_eq!(
+            Ok(Some(Argument::FractionDigits(FractionDigits(18)))),
+            argument("fraction-digits 18;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::Date(RevisionDate {
+                year: 2024,
+                month: 2,
+                day: 29,
+            }))),
+            argument("revision 2024-02-29;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::Identifier(Identifier("if".to_string())))),
+            argument("prefix if;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::IdentifierRef(IdentifierRef {
+                prefix: Some("inet".to_string()),
+                name: "ipv4-address".to_string(),
+            }))),
+            argument("type inet:ipv4-address;")
+        );
+        assert_eq!(
+            Ok(Some(Argument::String("/if:interfaces".to_string()))),
+            argument("augment /if:interfaces {}")
+        );
+        assert_eq!(
+            Ok(Some(Argument::String("maybe".to_string()))),
+            argument("ex:mandatory maybe;")
+        );
+        assert_eq!(Ok(None), argument("input {}"));
+    }
+
+    #[test]
+    fn errors() {
+        let error = argument("mandatory maybe;").unwrap_err();
+        assert_eq!(
+            ArgumentError {
+                keyword: "mandatory".to_string(),
+                argument: "maybe".to_string(),
+                expected: "\"true\" or \"false\"",
+                span: (10, 14),
+            },
+            error
+        );
+        assert_eq!(
+            "Invalid argument \"maybe\" for \"mandatory\", expected \"true\" or \"false\"",
+            error.to_string()
+        );
+
+        let invalid = |statement| argument(statement).is_err();
+
+        assert!(invalid("yang-version 2;"));
+        assert!(invalid("status active;"));
+        assert!(invalid("max-elements 0;"));
+        assert!(invalid("min-elements 01;"));
+        assert!(invalid("min-elements -1;"));
+        assert!(invalid("value -0;"));
+        assert!(invalid("value 2147483648;"));
+        assert!(invalid("position 4294967296;"));
+        assert!(invalid("fraction-digits 0;"));
+        assert!(invalid("fraction-digits 19;"));
+        assert!(invalid("revision 2023-02-29;"));
+        assert!(invalid("revision-date 2023-13-01;"));
+        assert!(invalid("revision-date 2023-1-01;"));
+        assert!(invalid("prefix 1abc;"));
+        assert!(invalid("type a:b:c;"));
+        assert!(invalid("deviate remove;"));
+    }
+}
@@ -0,0 +1,1070 @@
+//
+// Typed AST on top of the generic statement tree
+//
+// The parser produces an untyped tree where every statement is just a keyword and an optional
+// value. This module wraps the statements in types named after the YANG statements in the RFC 7950
+// ABNF grammar (see yang-abnf.txt), so code can use `module.containers()` rather than matching on
+// keyword strings.
+//
+// The wrappers borrow the nodes they were built from, which makes them cheap to create and keeps
+// the spans and the rest of the generic tree within reach.
+//
+
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexing::Span;
+use crate::parsing::{Node, RootNode, StatementKeyword, IDENTIFIER_PATTERN};
+
+/// Statements whose argument is an "identifier-arg"
+const IDENTIFIER_KEYWORDS: &[&str] = &[
+    "action",
+    "anydata",
+    "anyxml",
+    "argument",
+    "belongs-to",
+    "bit",
+    "case",
+    "choice",
+    "container",
+    "extension",
+    "feature",
+    "grouping",
+    "identity",
+    "import",
+    "include",
+    "leaf",
+    "leaf-list",
+    "list",
+    "module",
+    "notification",
+    "rpc",
+    "submodule",
+    "typedef",
+];
+
+/// Statements that don't take an argument
+const NO_ARGUMENT_KEYWORDS: &[&str] = &["input", "output"];
+
+/// An error for a statement that doesn't match the grammar
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AstError {
+    pub kind: AstErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AstErrorKind {
+    /// The document doesn't contain a module or submodule
+    MissingModule,
+
+    /// The document contains more than one module or submodule
+    MultipleModules,
+
+    /// A statement that requires an argument doesn't have one
+    MissingArgument { keyword: String },
+
+    /// A statement that doesn't take an argument has one
+    UnexpectedArgument { keyword: String },
+
+    /// The argument of a statement should be an identifier, but isn't
+    InvalidIdentifier { keyword: String, argument: String },
+
+    /// A statement that is neither a YANG statement nor an extension statement
+    UnknownStatement { keyword: String },
+
+    /// Part of the tree couldn't be parsed, see `parse_with_recovery`
+    Unparsable,
+}
+
+impl Display for AstError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            AstErrorKind::MissingModule => write!(f, "No module or submodule statement found"),
+            AstErrorKind::MultipleModules => {
+                write!(f, "Found more than one module or submodule statement")
+            }
+            AstErrorKind::MissingArgument { keyword } => {
+                write!(f, "The {:?} statement requires an argument", keyword)
+            }
+            AstErrorKind::UnexpectedArgument { keyword } => {
+                write!(f, "The {:?} statement doesn't take an argument", keyword)
+            }
+            AstErrorKind::InvalidIdentifier { keyword, argument } => write!(
+                f,
+                "Invalid identifier {:?} in {:?} statement",
+                argument, keyword
+            ),
+            AstErrorKind::UnknownStatement { keyword } => {
+                write!(f, "Unknown statement {:?}", keyword)
+            }
+            AstErrorKind::Unparsable => write!(f, "Unparsable statement"),
+        }
+    }
+}
+
+impl std::error::Error for AstError {}
+
+/// Conversion from a generic node into a typed node
+pub trait FromNode<'a>: Sized {
+    /// Wraps the node, or returns None if it's not the right kind of statement
+    fn from_node(node: &'a Node) -> Option<Self>;
+}
+
+/// Functionality shared by all typed statements
+pub trait Statement<'a>: FromNode<'a> {
+    /// The keyword of the statement
+    const KEYWORD: &'static str;
+
+    /// Returns the underlying node
+    fn node(&self) -> &'a Node;
+
+    fn span(&self) -> Span {
+        self.node().span()
+    }
+
+    fn argument(&self) -> Option<Cow<'a, str>> {
+        self.node().argument()
+    }
+
+    fn description(&self) -> Option<Cow<'a, str>> {
+        substatement_argument(self.node(), "description")
+    }
+
+    fn reference(&self) -> Option<Cow<'a, str>> {
+        substatement_argument(self.node(), "reference")
+    }
+
+    fn status(&self) -> Option<Cow<'a, str>> {
+        substatement_argument(self.node(), "status")
+    }
+
+    fn if_features(&self) -> Box<dyn Iterator<Item = Cow<'a, str>> + 'a> {
+        Box::new(
+            self.node()
+                .children()
+                .iter()
+                .filter(|node| node.is("if-feature"))
+                .filter_map(Node::argument),
+        )
+    }
+}
+
+/// Returns the sub-statements of the node that convert to the given type
+pub fn substatements<'a, T: FromNode<'a> + 'a>(node: &'a Node) -> impl Iterator<Item = T> + 'a {
+    node.children().iter().filter_map(T::from_node)
+}
+
+/// Returns the argument of the first sub-statement with the given keyword
+pub fn substatement_argument<'a>(node: &'a Node, keyword: &str) -> Option<Cow<'a, str>> {
+    node.children()
+        .iter()
+        .find(|node| node.is(keyword))
+        .and_then(Node::argument)
+}
+
+macro_rules! statements {
+    ($($(#[$doc:meta])* $name:ident = $keyword:literal;)*) => {$(
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy)]
+        pub struct $name<'a> {
+            node: &'a Node,
+        }
+
+        impl<'a> FromNode<'a> for $name<'a> {
+            fn from_node(node: &'a Node) -> Option<Self> {
+                node.is($keyword).then_some(Self { node })
+            }
+        }
+
+        impl<'a> Statement<'a> for $name<'a> {
+            const KEYWORD: &'static str = $keyword;
+
+            fn node(&self) -> &'a Node {
+                self.node
+            }
+        }
+    )*};
+}
+
+/// Generates accessors for sub-statements
+///
+/// - name: The argument of the statement itself, which is always present after validation
+/// - arguments: The argument of a sub-statement that may occur at most once
+/// - one: A typed sub-statement that may occur at most once
+/// - many: Typed sub-statements that may occur any number of times
+///
+macro_rules! accessors {
+    (
+        $name:ident {
+            $(name: $name_method:ident $(,)?)?
+            $(arguments { $($arg_method:ident: $arg_keyword:literal),* $(,)? })?
+            $(one { $($one_method:ident: $one_type:ident),* $(,)? })?
+            $(many { $($many_method:ident: $many_type:ident),* $(,)? })?
+        }
+    ) => {
+        impl<'a> $name<'a> {
+            $(
+                pub fn $name_method(&self) -> Cow<'a, str> {
+                    self.node.argument().unwrap_or_default()
+                }
+            )?
+
+            $($(
+                pub fn $arg_method(&self) -> Option<Cow<'a, str>> {
+                    substatement_argument(self.node, $arg_keyword)
+                }
+            )*)?
+
+            $($(
+                pub fn $one_method(&self) -> Option<$one_type<'a>> {
+                    substatements(self.node).next()
+                }
+            )*)?
+
+            $($(
+                pub fn $many_method(&self) -> impl Iterator<Item = $many_type<'a>> + 'a {
+                    substatements(self.node)
+                }
+            )*)?
+        }
+    };
+}
+
+statements! {
+    Module = "module";
+    Submodule = "submodule";
+    Import = "import";
+    Include = "include";
+    BelongsTo = "belongs-to";
+    Revision = "revision";
+    Extension = "extension";
+    Argument = "argument";
+    Feature = "feature";
+    Identity = "identity";
+    Typedef = "typedef";
+    Type = "type";
+    Enum = "enum";
+    Bit = "bit";
+    Range = "range";
+    Length = "length";
+    Pattern = "pattern";
+    Grouping = "grouping";
+    Container = "container";
+    Leaf = "leaf";
+    LeafList = "leaf-list";
+    List = "list";
+    Choice = "choice";
+    Case = "case";
+    Anydata = "anydata";
+    Anyxml = "anyxml";
+    Uses = "uses";
+    Refine = "refine";
+    Augment = "augment";
+    Rpc = "rpc";
+    Action = "action";
+    Input = "input";
+    Output = "output";
+    Notification = "notification";
+    Deviation = "deviation";
+    Deviate = "deviate";
+    Must = "must";
+    When = "when";
+}
+
+/// Any of the "data-def-stmt" statements
+#[derive(Debug, Clone, Copy)]
+pub enum DataDefinition<'a> {
+    Container(Container<'a>),
+    Leaf(Leaf<'a>),
+    LeafList(LeafList<'a>),
+    List(List<'a>),
+    Choice(Choice<'a>),
+    Anydata(Anydata<'a>),
+    Anyxml(Anyxml<'a>),
+    Uses(Uses<'a>),
+}
+
+impl<'a> FromNode<'a> for DataDefinition<'a> {
+    fn from_node(node: &'a Node) -> Option<Self> {
+        None.or_else(|| Container::from_node(node).map(Self::Container))
+            .or_else(|| Leaf::from_node(node).map(Self::Leaf))
+            .or_else(|| LeafList::from_node(node).map(Self::LeafList))
+            .or_else(|| List::from_node(node).map(Self::List))
+            .or_else(|| Choice::from_node(node).map(Self::Choice))
+            .or_else(|| Anydata::from_node(node).map(Self::Anydata))
+            .or_else(|| Anyxml::from_node(node).map(Self::Anyxml))
+            .or_else(|| Uses::from_node(node).map(Self::Uses))
+    }
+}
+
+impl<'a> DataDefinition<'a> {
+    pub fn node(&self) -> &'a Node {
+        match self {
+            DataDefinition::Container(statement) => statement.node(),
+            DataDefinition::Leaf(statement) => statement.node(),
+            DataDefinition::LeafList(statement) => statement.node(),
+            DataDefinition::List(statement) => statement.node(),
+            DataDefinition::Choice(statement) => statement.node(),
+            DataDefinition::Anydata(statement) => statement.node(),
+            DataDefinition::Anyxml(statement) => statement.node(),
+            DataDefinition::Uses(statement) => statement.node(),
+        }
+    }
+
+    /// Returns the name of the node, or the grouping name for "uses"
+    pub fn name(&self) -> Cow<'a, str> {
+        self.node().argument().unwrap_or_default()
+    }
+}
+
+accessors!(Module {
+    name: name,
+    arguments {
+        yang_version: "yang-version",
+        namespace: "namespace",
+        prefix: "prefix",
+        organization: "organization",
+        contact: "contact",
+    }
+    many {
+        imports: Import,
+        includes: Include,
+        revisions: Revision,
+        extensions: Extension,
+        features: Feature,
+        identities: Identity,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+        containers: Container,
+        leafs: Leaf,
+        leaf_lists: LeafList,
+        lists: List,
+        choices: Choice,
+        uses: Uses,
+        augments: Augment,
+        rpcs: Rpc,
+        notifications: Notification,
+        deviations: Deviation,
+    }
+});
+
+accessors!(Submodule {
+    name: name,
+    arguments {
+        yang_version: "yang-version",
+        organization: "organization",
+        contact: "contact",
+    }
+    one {
+        belongs_to: BelongsTo,
+    }
+    many {
+        imports: Import,
+        includes: Include,
+        revisions: Revision,
+        extensions: Extension,
+        features: Feature,
+        identities: Identity,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+        containers: Container,
+        leafs: Leaf,
+        leaf_lists: LeafList,
+        lists: List,
+        choices: Choice,
+        uses: Uses,
+        augments: Augment,
+        rpcs: Rpc,
+        notifications: Notification,
+        deviations: Deviation,
+    }
+});
+
+accessors!(Import {
+    name: module_name,
+    arguments {
+        prefix: "prefix",
+        revision_date: "revision-date",
+    }
+});
+
+accessors!(Include {
+    name: module_name,
+    arguments {
+        revision_date: "revision-date",
+    }
+});
+
+accessors!(BelongsTo {
+    name: module_name,
+    arguments {
+        prefix: "prefix",
+    }
+});
+
+accessors!(Revision { name: date });
+
+accessors!(Extension {
+    name: name,
+    one {
+        argument_statement: Argument,
+    }
+});
+
+accessors!(Argument {
+    name: name,
+    arguments {
+        yin_element: "yin-element",
+    }
+});
+
+accessors!(Feature { name: name });
+
+accessors!(Identity {
+    name: name,
+    arguments {
+        base: "base",
+    }
+});
+
+accessors!(Typedef {
+    name: name,
+    arguments {
+        units: "units",
+        default: "default",
+    }
+    one {
+        type_: Type,
+    }
+});
+
+accessors!(Type {
+    name: name,
+    arguments {
+        fraction_digits: "fraction-digits",
+        path: "path",
+        require_instance: "require-instance",
+    }
+    one {
+        range: Range,
+        length: Length,
+    }
+    many {
+        patterns: Pattern,
+        enums: Enum,
+        bits: Bit,
+        types: Type,
+    }
+});
+
+accessors!(Enum {
+    name: name,
+    arguments {
+        value: "value",
+    }
+});
+
+accessors!(Bit {
+    name: name,
+    arguments {
+        position: "position",
+    }
+});
+
+accessors!(Range {
+    name: expression,
+    arguments {
+        error_message: "error-message",
+        error_app_tag: "error-app-tag",
+    }
+});
+
+accessors!(Length {
+    name: expression,
+    arguments {
+        error_message: "error-message",
+        error_app_tag: "error-app-tag",
+    }
+});
+
+accessors!(Pattern {
+    name: expression,
+    arguments {
+        modifier: "modifier",
+        error_message: "error-message",
+        error_app_tag: "error-app-tag",
+    }
+});
+
+accessors!(Grouping {
+    name: name,
+    many {
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+        actions: Action,
+        notifications: Notification,
+    }
+});
+
+accessors!(Container {
+    name: name,
+    arguments {
+        presence: "presence",
+        config: "config",
+    }
+    one {
+        when: When,
+    }
+    many {
+        musts: Must,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+        containers: Container,
+        leafs: Leaf,
+        leaf_lists: LeafList,
+        lists: List,
+        choices: Choice,
+        uses: Uses,
+        actions: Action,
+        notifications: Notification,
+    }
+});
+
+accessors!(Leaf {
+    name: name,
+    arguments {
+        units: "units",
+        default: "default",
+        config: "config",
+        mandatory: "mandatory",
+    }
+    one {
+        when: When,
+        type_: Type,
+    }
+    many {
+        musts: Must,
+    }
+});
+
+accessors!(LeafList {
+    name: name,
+    arguments {
+        units: "units",
+        config: "config",
+        min_elements: "min-elements",
+        max_elements: "max-elements",
+        ordered_by: "ordered-by",
+    }
+    one {
+        when: When,
+        type_: Type,
+    }
+    many {
+        musts: Must,
+    }
+});
+
+impl<'a> LeafList<'a> {
+    /// A leaf-list can have multiple default values
+    pub fn defaults(&self) -> impl Iterator<Item = Cow<'a, str>> + 'a {
+        self.node
+            .children()
+            .iter()
+            .filter(|node| node.is("default"))
+            .filter_map(Node::argument)
+    }
+}
+
+accessors!(List {
+    name: name,
+    arguments {
+        key: "key",
+        config: "config",
+        min_elements: "min-elements",
+        max_elements: "max-elements",
+        ordered_by: "ordered-by",
+    }
+    one {
+        when: When,
+    }
+    many {
+        musts: Must,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+        containers: Container,
+        leafs: Leaf,
+        leaf_lists: LeafList,
+        lists: List,
+        choices: Choice,
+        uses: Uses,
+        actions: Action,
+        notifications: Notification,
+    }
+});
+
+impl<'a> List<'a> {
+    /// Returns the arguments of the "unique" statements
+    pub fn uniques(&self) -> impl Iterator<Item = Cow<'a, str>> + 'a {
+        self.node
+            .children()
+            .iter()
+            .filter(|node| node.is("unique"))
+            .filter_map(Node::argument)
+    }
+}
+
+accessors!(Choice {
+    name: name,
+    arguments {
+        default: "default",
+        config: "config",
+        mandatory: "mandatory",
+    }
+    one {
+        when: When,
+    }
+    many {
+        cases: Case,
+        data_definitions: DataDefinition,
+    }
+});
+
+accessors!(Case {
+    name: name,
+    one {
+        when: When,
+    }
+    many {
+        data_definitions: DataDefinition,
+    }
+});
+
+accessors!(Anydata {
+    name: name,
+    arguments {
+        config: "config",
+        mandatory: "mandatory",
+    }
+    one {
+        when: When,
+    }
+    many {
+        musts: Must,
+    }
+});
+
+accessors!(Anyxml {
+    name: name,
+    arguments {
+        config: "config",
+        mandatory: "mandatory",
+    }
+    one {
+        when: When,
+    }
+    many {
+        musts: Must,
+    }
+});
+
+accessors!(Uses {
+    name: grouping_name,
+    one {
+        when: When,
+    }
+    many {
+        refines: Refine,
+        augments: Augment,
+    }
+});
+
+accessors!(Refine {
+    name: target,
+    arguments {
+        presence: "presence",
+        default: "default",
+        config: "config",
+        mandatory: "mandatory",
+        min_elements: "min-elements",
+        max_elements: "max-elements",
+    }
+    many {
+        musts: Must,
+    }
+});
+
+accessors!(Augment {
+    name: target,
+    one {
+        when: When,
+    }
+    many {
+        data_definitions: DataDefinition,
+        cases: Case,
+        actions: Action,
+        notifications: Notification,
+    }
+});
+
+accessors!(Rpc {
+    name: name,
+    one {
+        input: Input,
+        output: Output,
+    }
+    many {
+        typedefs: Typedef,
+        groupings: Grouping,
+    }
+});
+
+accessors!(Action {
+    name: name,
+    one {
+        input: Input,
+        output: Output,
+    }
+    many {
+        typedefs: Typedef,
+        groupings: Grouping,
+    }
+});
+
+accessors!(Input {
+    many {
+        musts: Must,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+    }
+});
+
+accessors!(Output {
+    many {
+        musts: Must,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+    }
+});
+
+accessors!(Notification {
+    name: name,
+    many {
+        musts: Must,
+        typedefs: Typedef,
+        groupings: Grouping,
+        data_definitions: DataDefinition,
+    }
+});
+
+accessors!(Deviation {
+    name: target,
+    many {
+        deviates: Deviate,
+    }
+});
+
+accessors!(Deviate { name: kind });
+
+accessors!(Must {
+    name: expression,
+    arguments {
+        error_message: "error-message",
+        error_app_tag: "error-app-tag",
+    }
+});
+
+accessors!(When { name: expression });
+
+/// A parsed YANG document, which is either a module or a submodule
+#[derive(Debug, Clone, Copy)]
+pub enum Document<'a> {
+    Module(Module<'a>),
+    Submodule(Submodule<'a>),
+}
+
+impl<'a> Document<'a> {
+    pub fn node(&self) -> &'a Node {
+        match self {
+            Document::Module(module) => module.node(),
+            Document::Submodule(submodule) => submodule.node(),
+        }
+    }
+
+    pub fn name(&self) -> Cow<'a, str> {
+        self.node().argument().unwrap_or_default()
+    }
+}
+
+impl<'a> TryFrom<&'a RootNode> for Document<'a> {
+    type Error = AstError;
+
+    /// Finds the module or submodule in the tree and validates the statements in it
+    ///
+    /// Every statement must have an argument, except for the few statements that don't take one,
+    /// and statements that define something must be named by a valid identifier. Unknown
+    /// statements and unparsable parts of the tree are also errors.
+    ///
+    fn try_from(root: &'a RootNode) -> Result<Self, Self::Error> {
+        let mut document = None;
+
+        for node in root.children.iter() {
+            let candidate = match node {
+                Node::CommentNode(_) => continue,
+                node => None
+                    .or_else(|| Module::from_node(node).map(Document::Module))
+                    .or_else(|| Submodule::from_node(node).map(Document::Submodule)),
+            };
+
+            match (candidate, &document) {
+                (Some(candidate), None) => document = Some(candidate),
+                (Some(_), Some(_)) => {
+                    return Err(AstError {
+                        kind: AstErrorKind::MultipleModules,
+                        span: node.span(),
+                    })
+                }
+                (None, _) => validate_node(node)?,
+            }
+        }
+
+        let document = document.ok_or(AstError {
+            kind: AstErrorKind::MissingModule,
+            span: (0, 0),
+        })?;
+
+        validate_node(document.node())?;
+
+        Ok(document)
+    }
+}
+
+fn validate_node(node: &Node) -> Result<(), AstError> {
+    let error = |kind| {
+        Err(AstError {
+            kind,
+            span: node.span(),
+        })
+    };
+
+    let keyword = match node {
+        Node::CommentNode(_) => return Ok(()),
+        Node::ErrorNode(_) => return error(AstErrorKind::Unparsable),
+        Node::BlockNode(_) | Node::LeafNode(_) => node.keyword().unwrap(),
+    };
+
+    match keyword {
+        StatementKeyword::Keyword(keyword) => {
+            let argument = node.argument();
+
+            if NO_ARGUMENT_KEYWORDS.contains(&keyword.as_str()) {
+                if argument.is_some() {
+                    return error(AstErrorKind::UnexpectedArgument {
+                        keyword: keyword.clone(),
+                    });
+                }
+            } else if let Some(argument) = argument {
+                if IDENTIFIER_KEYWORDS.contains(&keyword.as_str())
+                    && !IDENTIFIER_PATTERN.is_match(&argument)
+                {
+                    return error(AstErrorKind::InvalidIdentifier {
+                        keyword: keyword.clone(),
+                        argument: argument.to_string(),
+                    });
+                }
+            } else {
+                return error(AstErrorKind::MissingArgument {
+                    keyword: keyword.clone(),
+                });
+            }
+        }
+
+        // The arguments of extensions are defined by the extension, so there's nothing to check
+        StatementKeyword::ExtensionKeyword(_) => {}
+
+        StatementKeyword::Invalid(keyword) => {
+            return error(AstErrorKind::UnknownStatement {
+                keyword: keyword.clone(),
+            })
+        }
+    }
+
+    for child in node.children() {
+        validate_node(child)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsing::parse;
+    use pretty_assertions::assert_eq;
+
+    fn dedent(text: &str) -> String {
+        let mut text = textwrap::dedent(text).trim().to_string();
+        text.push('\n');
+        text
+    }
+
+    #[test]
+    fn smoke_test() {
+        let buffer: Vec<u8> = dedent(
+            r#"
+            // Comment
+            module test {
+                yang-version 1.1;
+                namespace "urn:test";
+                prefix t;
+
+                import ietf-inet-types {
+                    prefix inet;
+                    revision-date 2013-07-15;
+                }
+
+                typedef port {
+                    type uint16 {
+                        range "1..65535";
+                    }
+                }
+
+                container interfaces {
+                    description "All interfaces";
+
+                    list interface {
+                        key name;
+
+                        leaf name {
+                            type string;
+                        }
+                        leaf address {
+                            type inet:ip-address;
+                        }
+                        uses stats;
+                    }
+                }
+
+                rpc reset {
+                    input {
+                        leaf name { type string; }
+                    }
+                }
+            }
+            "#,
+        )
+        .bytes()
+        .collect();
+
+        let tree = parse(&buffer).expect("Failed to parse YANG");
+
+        let module = match Document::try_from(&tree).expect("Invalid YANG") {
+            Document::Module(module) => module,
+            Document::Submodule(_) => panic!("Expected a module"),
+        };
+
+        assert_eq!("test", module.name());
+        assert_eq!(Some("urn:test".into()), module.namespace());
+        assert_eq!(Some("t".into()), module.prefix());
+
+        let import = module.imports().next().unwrap();
+        assert_eq!("ietf-inet-types", import.module_name());
+        assert_eq!(Some("inet".into()), import.prefix());
+        assert_eq!(Some("2013-07-15".into()), import.revision_date());
+
+        let typedef = module.typedefs().next().unwrap();
+        assert_eq!(
+            Some("1..65535".into()),
+            typedef
+                .type_()
+                .and_then(|type_| type_.range())
+                .map(|range| range.expression())
+        );
+
+        let container = module.containers().next().unwrap();
+        assert_eq!("interfaces", container.name());
+        assert_eq!(Some("All interfaces".into()), container.description());
+
+        let list = container.lists().next().unwrap();
+        assert_eq!(Some("name".into()), list.key());
+        assert_eq!(
+            vec!["name", "address", "stats"],
+            list.data_definitions()
+                .map(|definition| definition.name())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![Some("string".into()), Some("inet:ip-address".into())],
+            list.leafs()
+                .map(|leaf| leaf.type_().map(|type_| type_.name()))
+                .collect::<Vec<_>>()
+        );
+
+        let rpc = module.rpcs().next().unwrap();
+        let input = rpc.input().unwrap();
+        assert_eq!(1, input.data_definitions().count());
+        assert!(rpc.output().is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let error = |text: &str| {
+            let tree = parse(text.as_bytes()).expect("Failed to parse YANG");
+            Document::try_from(&tree).unwrap_err()
+        };
+
+        assert_eq!(AstErrorKind::MissingModule, error("// Nothing here\n").kind);
+
+        assert_eq!(
+            AstError {
+                kind: AstErrorKind::MultipleModules,
+                span: (12, 22),
+            },
+            error("module a {}\nmodule b {}\n")
+        );
+
+        assert_eq!(
+            AstError {
+                kind: AstErrorKind::InvalidIdentifier {
+                    keyword: "leaf".to_string(),
+                    argument: "1st".to_string(),
+                },
+                span: (11, 19),
+            },
+            error("module a {\nleaf 1st;\n}\n")
+        );
+
+        assert_eq!(
+            AstErrorKind::MissingArgument {
+                keyword: "container".to_string()
+            },
+            error("module a {\ncontainer {}\n}\n").kind
+        );
+
+        assert_eq!(
+            AstErrorKind::UnexpectedArgument {
+                keyword: "input".to_string()
+            },
+            error("module a {\nrpc r { input i {} }\n}\n").kind
+        );
+
+        assert_eq!(
+            AstErrorKind::UnknownStatement {
+                keyword: "foo".to_string()
+            },
+            error("module a {\nfoo bar;\n}\n").kind
+        );
+
+        // Extension statements can have any argument, or none
+        let tree = parse(b"module a {\nex:foo {}\n}\n").unwrap();
+        assert!(Document::try_from(&tree).is_ok());
+    }
+}
@@ -15,6 +15,13 @@ fn main() {
     let mut lock = stdout().lock();
 
     for token in scan(&buffer) {
-        write!(lock, "{}", token.human_readable_string()).expect("Failed to write to STDOUT");
+        match token {
+            Ok(token) => write!(lock, "{}", token.human_readable_string())
+                .expect("Failed to write to STDOUT"),
+            Err(error) => {
+                eprintln!("Failed to lex input: {}", error);
+                std::process::exit(1);
+            }
+        }
     }
 }
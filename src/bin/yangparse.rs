@@ -1,12 +1,111 @@
+use std::process::exit;
+
+use yangparse::formatting::{format_yang, FormatOptions};
 use yangparse::parsing::parse;
 
+const USAGE: &str = "\
+Usage:
+    yangparse FILE                              Print the syntax tree of a YANG file
+    yangparse fmt [--check | --in-place] FILE...  Format YANG files";
+
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
+    let args: Vec<String> = std::env::args().skip(1).collect();
 
-    let filepath = args.get(1).expect("Expected file path as first argument");
-    let buffer = std::fs::read(filepath).expect("Failed to read input file");
+    match args.first().map(String::as_str) {
+        Some("fmt") => fmt(&args[1..]),
+        Some(filepath) if args.len() == 1 && !filepath.starts_with('-') => print_tree(filepath),
+        _ => usage(),
+    }
+}
 
-    let tree = parse(&buffer).expect("Failed to parse input");
+/// Prints the usage text and exits with status 2
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    exit(2);
+}
+
+/// Reads a file, exiting with status 1 if it can't be read
+fn read(filepath: &str) -> Vec<u8> {
+    match std::fs::read(filepath) {
+        Ok(buffer) => buffer,
+        Err(error) => {
+            eprintln!("Failed to read {}: {}", filepath, error);
+            exit(1);
+        }
+    }
+}
+
+fn print_tree(filepath: &str) {
+    let buffer = read(filepath);
+
+    let tree = match parse(&buffer) {
+        Ok(tree) => tree,
+        Err(error) => {
+            eprintln!("Failed to parse input: {}", error);
+            exit(1);
+        }
+    };
 
     println!("{}", tree);
 }
+
+/// Formats the given files
+///
+/// By default the formatted files are written to STDOUT. With --in-place the files are rewritten,
+/// and with --check nothing is written, but the exit code is 1 if any file isn't formatted.
+///
+fn fmt(args: &[String]) {
+    let mut check = false;
+    let mut in_place = false;
+    let mut filepaths: Vec<&str> = vec![];
+
+    for arg in args {
+        match arg.as_str() {
+            "--check" => check = true,
+            "--in-place" | "-i" => in_place = true,
+            flag if flag.starts_with('-') => usage(),
+            filepath => filepaths.push(filepath),
+        }
+    }
+
+    if filepaths.is_empty() || (check && in_place) {
+        usage();
+    }
+
+    let options = FormatOptions::default();
+    let mut unformatted = false;
+
+    for filepath in filepaths {
+        let buffer = read(filepath);
+
+        let tree = match parse(&buffer) {
+            Ok(tree) => tree,
+            Err(error) => {
+                eprintln!("Failed to parse {}: {}", filepath, error);
+                exit(1);
+            }
+        };
+
+        let formatted = format_yang(&tree, &options);
+
+        if check {
+            if formatted.as_bytes() != buffer {
+                println!("Would reformat: {}", filepath);
+                unformatted = true;
+            }
+        } else if in_place {
+            if formatted.as_bytes() != buffer {
+                if let Err(error) = std::fs::write(filepath, formatted) {
+                    eprintln!("Failed to write {}: {}", filepath, error);
+                    exit(1);
+                }
+            }
+        } else {
+            print!("{}", formatted);
+        }
+    }
+
+    if unformatted {
+        exit(1);
+    }
+}
@@ -1,3 +1,5 @@
+use Cardinality::*;
+
 pub const STATEMENT_KEYWORDS: &[&str] = &[
     "action",
     "anydata",
@@ -68,3 +70,593 @@ pub const STATEMENT_KEYWORDS: &[&str] = &[
     "yang-version",
     "yin-element",
 ];
+
+/// How many times a sub-statement may occur in its parent statement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    /// 0..1, written as "[stmt]" in the ABNF
+    Optional,
+    /// 1, written as "stmt" in the ABNF
+    Required,
+    /// 0..n, written as "*stmt" in the ABNF
+    ZeroOrMore,
+    /// 1..n, written as "1*stmt" in the ABNF
+    OneOrMore,
+}
+
+/// The sub-statements allowed in each of the STATEMENT_KEYWORDS, along with their cardinality
+///
+/// Based on the ABNF grammar in RFC 7950 section 14 (see yang-abnf.txt), with a few deviations
+/// where the grammar depends on more than just the parent keyword:
+///
+/// - "yang-version" is required in YANG 1.1 modules, but optional in YANG 1.0 modules
+/// - "type" allows the union of all the type restrictions, which ones are actually valid depends
+///   on the base type
+/// - "deviate" allows the union of what "add", "replace" and "delete" allow
+/// - Requirements for at least one statement out of a group, such as the data definitions in a
+///   "list", are not expressed here
+///
+/// Extension statements may appear anywhere and are not listed.
+///
+pub const SUBSTATEMENTS: &[(&str, &[(&str, Cardinality)])] = &[
+    (
+        "action",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("input", Optional),
+            ("output", Optional),
+        ],
+    ),
+    (
+        "anydata",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "anyxml",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("argument", &[("yin-element", Optional)]),
+    (
+        "augment",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("case", ZeroOrMore),
+            ("action", ZeroOrMore),
+            ("notification", ZeroOrMore),
+        ],
+    ),
+    ("base", &[]),
+    ("belongs-to", &[("prefix", Required)]),
+    (
+        "bit",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("position", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "case",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+        ],
+    ),
+    (
+        "choice",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("default", Optional),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("case", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+        ],
+    ),
+    ("config", &[]),
+    ("contact", &[]),
+    (
+        "container",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("presence", Optional),
+            ("config", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("action", ZeroOrMore),
+            ("notification", ZeroOrMore),
+        ],
+    ),
+    ("default", &[]),
+    ("description", &[]),
+    (
+        "deviate",
+        &[
+            ("type", Optional),
+            ("units", Optional),
+            ("must", ZeroOrMore),
+            ("unique", ZeroOrMore),
+            ("default", ZeroOrMore),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("min-elements", Optional),
+            ("max-elements", Optional),
+        ],
+    ),
+    (
+        "deviation",
+        &[
+            ("description", Optional),
+            ("reference", Optional),
+            ("deviate", OneOrMore),
+        ],
+    ),
+    (
+        "enum",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("value", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("error-app-tag", &[]),
+    ("error-message", &[]),
+    (
+        "extension",
+        &[
+            ("argument", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "feature",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("fraction-digits", &[]),
+    (
+        "grouping",
+        &[
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("action", ZeroOrMore),
+            ("notification", ZeroOrMore),
+        ],
+    ),
+    (
+        "identity",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("base", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("if-feature", &[]),
+    (
+        "import",
+        &[
+            ("prefix", Required),
+            ("revision-date", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "include",
+        &[
+            ("revision-date", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "input",
+        &[
+            ("must", ZeroOrMore),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+        ],
+    ),
+    ("key", &[]),
+    (
+        "leaf",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("type", Required),
+            ("units", Optional),
+            ("must", ZeroOrMore),
+            ("default", Optional),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "leaf-list",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("type", Required),
+            ("units", Optional),
+            ("must", ZeroOrMore),
+            ("default", ZeroOrMore),
+            ("config", Optional),
+            ("min-elements", Optional),
+            ("max-elements", Optional),
+            ("ordered-by", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "length",
+        &[
+            ("error-message", Optional),
+            ("error-app-tag", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    (
+        "list",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("key", Optional),
+            ("unique", ZeroOrMore),
+            ("config", Optional),
+            ("min-elements", Optional),
+            ("max-elements", Optional),
+            ("ordered-by", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("action", ZeroOrMore),
+            ("notification", ZeroOrMore),
+        ],
+    ),
+    ("mandatory", &[]),
+    ("max-elements", &[]),
+    ("min-elements", &[]),
+    ("modifier", &[]),
+    (
+        "module",
+        &[
+            ("yang-version", Optional),
+            ("namespace", Required),
+            ("prefix", Required),
+            ("import", ZeroOrMore),
+            ("include", ZeroOrMore),
+            ("organization", Optional),
+            ("contact", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("revision", ZeroOrMore),
+            ("extension", ZeroOrMore),
+            ("feature", ZeroOrMore),
+            ("identity", ZeroOrMore),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("augment", ZeroOrMore),
+            ("rpc", ZeroOrMore),
+            ("notification", ZeroOrMore),
+            ("deviation", ZeroOrMore),
+        ],
+    ),
+    (
+        "must",
+        &[
+            ("error-message", Optional),
+            ("error-app-tag", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("namespace", &[]),
+    (
+        "notification",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+        ],
+    ),
+    ("ordered-by", &[]),
+    ("organization", &[]),
+    (
+        "output",
+        &[
+            ("must", ZeroOrMore),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+        ],
+    ),
+    ("path", &[]),
+    (
+        "pattern",
+        &[
+            ("modifier", Optional),
+            ("error-message", Optional),
+            ("error-app-tag", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("position", &[]),
+    ("prefix", &[]),
+    ("presence", &[]),
+    (
+        "range",
+        &[
+            ("error-message", Optional),
+            ("error-app-tag", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("reference", &[]),
+    (
+        "refine",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("must", ZeroOrMore),
+            ("presence", Optional),
+            ("default", ZeroOrMore),
+            ("config", Optional),
+            ("mandatory", Optional),
+            ("min-elements", Optional),
+            ("max-elements", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("require-instance", &[]),
+    (
+        "revision",
+        &[("description", Optional), ("reference", Optional)],
+    ),
+    ("revision-date", &[]),
+    (
+        "rpc",
+        &[
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("input", Optional),
+            ("output", Optional),
+        ],
+    ),
+    ("status", &[]),
+    (
+        "submodule",
+        &[
+            ("yang-version", Optional),
+            ("belongs-to", Required),
+            ("import", ZeroOrMore),
+            ("include", ZeroOrMore),
+            ("organization", Optional),
+            ("contact", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("revision", ZeroOrMore),
+            ("extension", ZeroOrMore),
+            ("feature", ZeroOrMore),
+            ("identity", ZeroOrMore),
+            ("typedef", ZeroOrMore),
+            ("grouping", ZeroOrMore),
+            ("container", ZeroOrMore),
+            ("leaf", ZeroOrMore),
+            ("leaf-list", ZeroOrMore),
+            ("list", ZeroOrMore),
+            ("choice", ZeroOrMore),
+            ("anydata", ZeroOrMore),
+            ("anyxml", ZeroOrMore),
+            ("uses", ZeroOrMore),
+            ("augment", ZeroOrMore),
+            ("rpc", ZeroOrMore),
+            ("notification", ZeroOrMore),
+            ("deviation", ZeroOrMore),
+        ],
+    ),
+    (
+        "type",
+        &[
+            ("range", Optional),
+            ("fraction-digits", Optional),
+            ("length", Optional),
+            ("pattern", ZeroOrMore),
+            ("enum", ZeroOrMore),
+            ("path", Optional),
+            ("require-instance", Optional),
+            ("base", ZeroOrMore),
+            ("bit", ZeroOrMore),
+            ("type", ZeroOrMore),
+        ],
+    ),
+    (
+        "typedef",
+        &[
+            ("type", Required),
+            ("units", Optional),
+            ("default", Optional),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+        ],
+    ),
+    ("unique", &[]),
+    ("units", &[]),
+    (
+        "uses",
+        &[
+            ("when", Optional),
+            ("if-feature", ZeroOrMore),
+            ("status", Optional),
+            ("description", Optional),
+            ("reference", Optional),
+            ("refine", ZeroOrMore),
+            ("augment", ZeroOrMore),
+        ],
+    ),
+    ("value", &[]),
+    (
+        "when",
+        &[("description", Optional), ("reference", Optional)],
+    ),
+    ("yang-version", &[]),
+    ("yin-element", &[]),
+];
+
+/// Returns the allowed sub-statements of a statement, or None if the keyword is unknown
+pub fn substatements(keyword: &str) -> Option<&'static [(&'static str, Cardinality)]> {
+    SUBSTATEMENTS
+        .iter()
+        .find(|(k, _)| *k == keyword)
+        .map(|(_, substatements)| *substatements)
+}
@@ -0,0 +1,364 @@
+//
+// Lossless concrete syntax tree
+//
+// Unlike the syntax tree built by the parsing module, the concrete syntax tree keeps every single
+// token from the input, including whitespace, line breaks and comments. Printing the tree
+// reproduces the input byte-for-byte, which makes it possible to edit a YANG file
+// programmatically without reformatting the whole thing.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::errors::{ParseError, ParseErrorKind};
+use crate::lexing::{ScanIterator, Span, Token, TokenType};
+
+#[derive(Debug)]
+pub struct CstRoot<'a> {
+    pub elements: Vec<CstElement<'a>>,
+}
+
+#[derive(Debug)]
+pub enum CstElement<'a> {
+    Token(Token<'a>),
+    Statement(CstStatement<'a>),
+}
+
+/// A statement with all of its tokens
+///
+/// The elements start with the keyword and end with either the terminating semicolon or the
+/// closing curly brace of the block. Everything in between is retained, including trivia between
+/// the keyword and the value, and sub-statements along with the trivia surrounding them.
+///
+#[derive(Debug)]
+pub struct CstStatement<'a> {
+    pub elements: Vec<CstElement<'a>>,
+}
+
+impl<'a> CstRoot<'a> {
+    /// Returns the top level statements
+    pub fn statements(&self) -> impl Iterator<Item = &CstStatement<'a>> {
+        statements(&self.elements)
+    }
+}
+
+impl<'a> CstStatement<'a> {
+    /// Returns the keyword token
+    pub fn keyword(&self) -> &Token<'a> {
+        match self.elements.first() {
+            Some(CstElement::Token(token)) => token,
+            _ => unreachable!("A statement always starts with a keyword token"),
+        }
+    }
+
+    /// Returns the value tokens, if any
+    ///
+    /// This is usually a single token, but concatenated strings are made up of several strings
+    /// and "+" tokens.
+    ///
+    pub fn value(&self) -> impl Iterator<Item = &Token<'a>> {
+        self.elements
+            .iter()
+            .skip(1)
+            .map_while(|element| match element {
+                CstElement::Token(token) => Some(token),
+                CstElement::Statement(_) => None,
+            })
+            .take_while(|token| {
+                !matches!(
+                    token.token_type,
+                    TokenType::SemiColon | TokenType::OpenCurlyBrace
+                )
+            })
+            .filter(|token| !is_trivia(token))
+    }
+
+    /// Returns the sub-statements
+    pub fn children(&self) -> impl Iterator<Item = &CstStatement<'a>> {
+        statements(&self.elements)
+    }
+
+    /// Returns the span of the whole statement
+    pub fn span(&self) -> Span {
+        (self.keyword().span.0, last_token(&self.elements).span.1)
+    }
+}
+
+fn statements<'a, 'b>(
+    elements: &'b [CstElement<'a>],
+) -> impl Iterator<Item = &'b CstStatement<'a>> {
+    elements.iter().filter_map(|element| match element {
+        CstElement::Statement(statement) => Some(statement),
+        CstElement::Token(_) => None,
+    })
+}
+
+fn last_token<'a, 'b>(elements: &'b [CstElement<'a>]) -> &'b Token<'a> {
+    match elements.last() {
+        Some(CstElement::Token(token)) => token,
+        Some(CstElement::Statement(statement)) => last_token(&statement.elements),
+        None => unreachable!("A statement always has at least one token"),
+    }
+}
+
+fn is_trivia(token: &Token) -> bool {
+    matches!(
+        token.token_type,
+        TokenType::WhiteSpace | TokenType::LineBreak | TokenType::Comment
+    )
+}
+
+impl Display for CstRoot<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        print_elements(f, &self.elements)
+    }
+}
+
+impl Display for CstStatement<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        print_elements(f, &self.elements)
+    }
+}
+
+fn print_elements(f: &mut Formatter, elements: &[CstElement]) -> fmt::Result {
+    for element in elements {
+        match element {
+            CstElement::Token(token) => write!(f, "{}", token.text)?,
+            CstElement::Statement(statement) => print_elements(f, &statement.elements)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the input bytes as a YANG document and returns a lossless concrete syntax tree
+///
+/// Printing the returned tree reproduces the input exactly.
+///
+pub fn parse_cst(buffer: &[u8]) -> Result<CstRoot<'_>, ParseError> {
+    let mut tokens = crate::lexing::scan(buffer);
+    let mut elements = vec![];
+
+    while let Some(token) = tokens.next().transpose()? {
+        match token.token_type {
+            TokenType::Other => {
+                elements.push(CstElement::Statement(parse_statement(
+                    buffer,
+                    &mut tokens,
+                    token,
+                )?));
+            }
+            TokenType::ClosingCurlyBrace => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnbalancedBrace,
+                    token.span,
+                    buffer,
+                ));
+            }
+            _ if is_trivia(&token) => elements.push(CstElement::Token(token)),
+            _ => return Err(unexpected(buffer, &token, "a statement keyword or comment")),
+        }
+    }
+
+    Ok(CstRoot { elements })
+}
+
+/// The last thing read in the header of a statement, meaning the keyword and the value
+enum HeaderState {
+    Keyword,
+    Value { is_string: bool },
+    Plus,
+}
+
+/// Parses the rest of a statement after its keyword
+fn parse_statement<'a>(
+    buffer: &'a [u8],
+    tokens: &mut ScanIterator<'a>,
+    keyword: Token<'a>,
+) -> Result<CstStatement<'a>, ParseError> {
+    let keyword_span = keyword.span;
+    let mut elements = vec![CstElement::Token(keyword)];
+    let mut state = HeaderState::Keyword;
+
+    while let Some(token) = tokens.next().transpose()? {
+        if is_trivia(&token) {
+            elements.push(CstElement::Token(token));
+            continue;
+        }
+
+        match (&state, &token.token_type) {
+            (HeaderState::Keyword, TokenType::SemiColon) => {
+                return Err(ParseError::new(
+                    ParseErrorKind::MissingValue,
+                    keyword_span,
+                    buffer,
+                ));
+            }
+
+            (HeaderState::Value { .. }, TokenType::SemiColon) => {
+                elements.push(CstElement::Token(token));
+                return Ok(CstStatement { elements });
+            }
+
+            (HeaderState::Keyword | HeaderState::Value { .. }, TokenType::OpenCurlyBrace) => {
+                let open_span = token.span;
+                elements.push(CstElement::Token(token));
+                parse_block(buffer, tokens, open_span, &mut elements)?;
+                return Ok(CstStatement { elements });
+            }
+
+            (HeaderState::Keyword, TokenType::ClosingCurlyBrace) => {
+                return Err(unexpected(buffer, &token, "a value, \";\" or \"{\""));
+            }
+
+            (HeaderState::Keyword, token_type) => {
+                state = HeaderState::Value {
+                    is_string: *token_type == TokenType::String,
+                };
+            }
+
+            (HeaderState::Value { is_string: true }, TokenType::Plus) => {
+                state = HeaderState::Plus;
+            }
+
+            (HeaderState::Value { .. }, _) => {
+                return Err(unexpected(buffer, &token, "\";\" or \"{\""));
+            }
+
+            (HeaderState::Plus, TokenType::String) => {
+                state = HeaderState::Value { is_string: true };
+            }
+
+            (HeaderState::Plus, _) => {
+                return Err(unexpected(buffer, &token, "a quoted string"));
+            }
+        }
+
+        elements.push(CstElement::Token(token));
+    }
+
+    Err(ParseError::new(
+        ParseErrorKind::UnexpectedEof,
+        (buffer.len(), buffer.len()),
+        buffer,
+    ))
+}
+
+/// Parses the sub-statements of a block up to and including the closing curly brace
+fn parse_block<'a>(
+    buffer: &'a [u8],
+    tokens: &mut ScanIterator<'a>,
+    open_span: Span,
+    elements: &mut Vec<CstElement<'a>>,
+) -> Result<(), ParseError> {
+    while let Some(token) = tokens.next().transpose()? {
+        match token.token_type {
+            TokenType::Other => {
+                let statement = parse_statement(buffer, tokens, token)?;
+                elements.push(CstElement::Statement(statement));
+            }
+            TokenType::ClosingCurlyBrace => {
+                elements.push(CstElement::Token(token));
+                return Ok(());
+            }
+            _ if is_trivia(&token) => elements.push(CstElement::Token(token)),
+            _ => {
+                return Err(unexpected(
+                    buffer,
+                    &token,
+                    "a statement keyword, comment or \"}\"",
+                ))
+            }
+        }
+    }
+
+    Err(ParseError::new(
+        ParseErrorKind::UnbalancedBrace,
+        open_span,
+        buffer,
+    ))
+}
+
+fn unexpected(buffer: &[u8], token: &Token, expected: &'static str) -> ParseError {
+    ParseError::new(
+        ParseErrorKind::UnexpectedToken {
+            text: token.text.to_string(),
+            expected,
+        },
+        token.span,
+        buffer,
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn round_trip() {
+        let input = concat!(
+            "// Leading comment\r\n",
+            "module /* odd */ test {\n",
+            "\tyang-version   1.1 ;\n",
+            "  description\n",
+            "    \"foo\" // between parts\n",
+            "    + 'bar'\n",
+            "    ;\n",
+            "\n",
+            "  container c { leaf l { type string; } }   \n",
+            "}\n",
+            "/* trailing */",
+        );
+
+        let cst = parse_cst(input.as_bytes()).expect("Failed to parse YANG");
+
+        assert_eq!(input, cst.to_string());
+
+        let module = cst.statements().next().unwrap();
+        assert_eq!("module", module.keyword().text);
+        assert_eq!(
+            vec!["test"],
+            module.value().map(|token| token.text).collect::<Vec<_>>()
+        );
+        assert_eq!((20, input.len() - 16), module.span());
+
+        let children: Vec<_> = module.children().collect();
+        assert_eq!(3, children.len());
+        assert_eq!("yang-version   1.1 ;", children[0].to_string());
+        assert_eq!(
+            vec!["\"foo\"", "+", "'bar'"],
+            children[1]
+                .value()
+                .map(|token| token.text)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            "container c { leaf l { type string; } }",
+            children[2].to_string()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = parse_cst(b"module test {\n  prefix;\n}\n").unwrap_err();
+        assert_eq!(ParseErrorKind::MissingValue, error.kind);
+        assert_eq!((16, 21), error.span);
+
+        let error = parse_cst(b"module test {\n  prefix \"a\" + b;\n}\n").unwrap_err();
+        assert_eq!(
+            ParseErrorKind::UnexpectedToken {
+                text: "b".to_string(),
+                expected: "a quoted string",
+            },
+            error.kind
+        );
+
+        let error = parse_cst(b"module test {\n  prefix t;\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnbalancedBrace, error.kind);
+        assert_eq!((12, 12), error.span);
+
+        let error = parse_cst(b"module test;\n}\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnbalancedBrace, error.kind);
+        assert_eq!((13, 13), error.span);
+    }
+}
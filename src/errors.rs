@@ -0,0 +1,82 @@
+//
+// Error types shared by the lexer and the parser
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexing::{Span, TextPosition};
+
+/// An error encountered while lexing or parsing a YANG document
+///
+/// Carries the byte span of the offending input (inclusive, like token spans) as well as the
+/// line/column position of the start of that span, so callers can point at the exact location
+/// without scraping the error message.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+    pub position: TextPosition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A quoted string was never closed
+    UnterminatedString,
+
+    /// A "/*" comment was never closed
+    UnterminatedBlockComment,
+
+    /// A token that isn't valid at this position, along with a description of what was expected
+    UnexpectedToken {
+        text: String,
+        expected: &'static str,
+    },
+
+    /// A statement keyword was followed directly by a semicolon
+    MissingValue,
+
+    /// The input ended in the middle of a statement
+    UnexpectedEof,
+
+    /// A block was never closed, or a closing brace has no matching block
+    UnbalancedBrace,
+
+    /// The input is not valid UTF-8
+    InvalidUtf8,
+}
+
+impl ParseError {
+    /// Creates a new error, computing the line/column position from the span and the buffer
+    pub fn new(kind: ParseErrorKind, span: Span, buffer: &[u8]) -> Self {
+        Self {
+            kind,
+            span,
+            position: TextPosition::from_buffer_index(buffer, span.0),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnterminatedString => write!(f, "Unterminated string"),
+            ParseErrorKind::UnterminatedBlockComment => write!(f, "Unterminated block comment"),
+            ParseErrorKind::UnexpectedToken { text, expected } => {
+                write!(f, "Unexpected {:?}, expected {}", text, expected)
+            }
+            ParseErrorKind::MissingValue => write!(f, "Expected to find a value, not \";\""),
+            ParseErrorKind::UnexpectedEof => write!(f, "Unexpected end of input"),
+            ParseErrorKind::UnbalancedBrace => write!(f, "Unbalanced curly brace"),
+            ParseErrorKind::InvalidUtf8 => write!(f, "Invalid UTF-8"),
+        }
+    }
+}
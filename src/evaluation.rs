@@ -0,0 +1,1611 @@
+//
+// XPath evaluation
+//
+// The "must" and "when" constraints of a schema are evaluated against instance data, which is
+// given as a plain tree of data nodes. Before evaluation the tree is linked to the compiled
+// schema, so that every data node knows its schema node, and flattened into a document where the
+// nodes are numbered in document order.
+//
+// Expressions see the accessible tree of RFC 7950 section 6.4.1. For constraints on configuration
+// nodes this is the configuration only, so state data is invisible to them, while constraints on
+// state nodes see all data. Data nodes are never rpc or notification contents here, so their
+// special accessible trees are not supported.
+//
+// Following RFC 7950 section 6.4.1, unprefixed names in an expression belong to the module of the
+// context node, while prefixes are resolved through the imports of the module the expression is
+// written in.
+//
+// Values of identityref and instance-identifier leafs use the JSON encoding of RFC 7951, where
+// names are prefixed with the module name rather than an XML namespace prefix.
+//
+
+use std::collections::{HashSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+
+use crate::arguments::{ArgumentType, IdentifierRef};
+use crate::patterns::Pattern;
+use crate::repository::{ModuleId, ModuleRepository};
+use crate::schema::{
+    find_definition, find_top_level, main_module, resolve_prefix, Property, Schema, SchemaNode,
+    SchemaNodeKind, SourceRef,
+};
+use crate::types::{resolve_node_type, BuiltinType};
+use crate::xpath::{
+    parse_xpath, Axis, BinaryOperator, Expr, ExprKind, NodeTest, PathStart, Step, XPathError,
+};
+
+/// A node in an instance data tree
+///
+/// Leafs have a value and no children. Each entry of a list or leaf-list is a separate node with
+/// the name of the list or leaf-list. Choices and cases don't appear in the data tree.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataNode {
+    /// The module whose namespace the node belongs to
+    pub module: ModuleId,
+    pub name: String,
+    pub value: Option<String>,
+    pub children: Vec<DataNode>,
+}
+
+impl DataNode {
+    /// Creates a container or list entry
+    pub fn container(module: ModuleId, name: &str, children: Vec<DataNode>) -> Self {
+        Self {
+            module,
+            name: name.to_string(),
+            value: None,
+            children,
+        }
+    }
+
+    /// Creates a leaf or leaf-list entry
+    pub fn leaf(module: ModuleId, name: &str, value: &str) -> Self {
+        Self {
+            module,
+            name: name.to_string(),
+            value: Some(value.to_string()),
+            children: vec![],
+        }
+    }
+}
+
+/// The index of a node in a document, where the root is 0
+pub type NodeId = usize;
+
+struct Entry<'a> {
+    /// The data node, or None for the root
+    data: Option<&'a DataNode>,
+    schema: Option<&'a SchemaNode>,
+
+    /// The choice and case nodes between the schema node of the parent and this one
+    via: Vec<&'a SchemaNode>,
+
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+
+    /// The last descendant of the node, or the node itself
+    end: NodeId,
+    config: bool,
+}
+
+/// An instance data tree linked to a schema, with its nodes in document order
+pub struct Document<'a> {
+    entries: Vec<Entry<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Links a data tree to the schema
+    ///
+    /// When `config_only` is set the document only contains configuration, which is the
+    /// accessible tree for constraints on configuration nodes.
+    ///
+    pub fn new(schema: &'a Schema, tree: &'a [DataNode], config_only: bool) -> Self {
+        let mut document = Self {
+            entries: vec![Entry {
+                data: None,
+                schema: None,
+                via: vec![],
+                parent: None,
+                children: vec![],
+                end: 0,
+                config: true,
+            }],
+        };
+
+        for node in tree {
+            document.add(node, 0, Some(&schema.nodes), config_only);
+        }
+
+        document.entries[0].end = document.entries.len() - 1;
+        document
+    }
+
+    fn add(
+        &mut self,
+        node: &'a DataNode,
+        parent: NodeId,
+        siblings: Option<&'a [SchemaNode]>,
+        config_only: bool,
+    ) {
+        let mut via = vec![];
+        let schema = siblings.and_then(|siblings| find_schema_node(siblings, node, &mut via));
+
+        let config = self.entries[parent].config
+            && via
+                .iter()
+                .copied()
+                .chain(schema)
+                .all(|schema| schema.argument("config").as_deref() != Some("false"));
+
+        if config_only && !config {
+            return;
+        }
+
+        let id = self.entries.len();
+
+        self.entries.push(Entry {
+            data: Some(node),
+            schema,
+            via,
+            parent: Some(parent),
+            children: vec![],
+            end: id,
+            config,
+        });
+
+        self.entries[parent].children.push(id);
+
+        for child in node.children.iter() {
+            let siblings = schema.map(|schema| schema.children.as_slice());
+            self.add(child, id, siblings, config_only);
+        }
+
+        self.entries[id].end = self.entries.len() - 1;
+    }
+
+    pub fn root(&self) -> NodeId {
+        0
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.len() == 1
+    }
+
+    pub fn data(&self, id: NodeId) -> Option<&'a DataNode> {
+        self.entries[id].data
+    }
+
+    pub fn schema(&self, id: NodeId) -> Option<&'a SchemaNode> {
+        self.entries[id].schema
+    }
+
+    /// Returns the value of a leaf, or the concatenated values of the leafs below other nodes
+    pub fn string_value(&self, id: NodeId) -> String {
+        (id..=self.entries[id].end)
+            .filter_map(|id| self.entries[id].data.and_then(|data| data.value.as_deref()))
+            .collect()
+    }
+
+    /// Returns a path to the node, like "/interfaces/interface[2]/mtu", where the position is
+    /// only given for nodes with siblings of the same name
+    pub fn path(&self, id: NodeId) -> String {
+        let mut segments = vec![];
+        let mut current = id;
+
+        while let (Some(parent), Some(data)) = (self.entries[current].parent, self.data(current)) {
+            let same_name: Vec<_> = self.entries[parent]
+                .children
+                .iter()
+                .filter(|sibling| {
+                    self.data(**sibling)
+                        .is_some_and(|other| other.module == data.module && other.name == data.name)
+                })
+                .collect();
+
+            match same_name.iter().position(|sibling| **sibling == current) {
+                Some(i) if same_name.len() > 1 => {
+                    segments.push(format!("{}[{}]", data.name, i + 1))
+                }
+                _ => segments.push(data.name.clone()),
+            }
+
+            current = parent;
+        }
+
+        segments.reverse();
+        format!("/{}", segments.join("/"))
+    }
+}
+
+/// Finds the schema node of a data node, looking through choices and cases
+fn find_schema_node<'a>(
+    siblings: &'a [SchemaNode],
+    node: &DataNode,
+    via: &mut Vec<&'a SchemaNode>,
+) -> Option<&'a SchemaNode> {
+    for sibling in siblings {
+        match sibling.kind {
+            SchemaNodeKind::Choice | SchemaNodeKind::Case => {
+                via.push(sibling);
+
+                if let Some(found) = find_schema_node(&sibling.children, node, via) {
+                    return Some(found);
+                }
+
+                via.pop();
+            }
+            _ if sibling.module == node.module && sibling.name == node.name => {
+                return Some(sibling)
+            }
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// The result of an XPath expression
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Nodes in document order, without duplicates
+    NodeSet(Vec<NodeId>),
+    Boolean(bool),
+    Number(f64),
+    String(String),
+}
+
+impl Value {
+    /// Converts the value as the boolean() function does
+    pub fn boolean(&self) -> bool {
+        match self {
+            Value::NodeSet(nodes) => !nodes.is_empty(),
+            Value::Boolean(value) => *value,
+            Value::Number(number) => *number != 0.0 && !number.is_nan(),
+            Value::String(text) => !text.is_empty(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvaluationError {
+    Syntax(XPathError),
+    UnknownPrefix {
+        prefix: String,
+    },
+    UnknownVariable {
+        name: String,
+    },
+    UnknownFunction {
+        name: String,
+    },
+    InvalidPattern {
+        pattern: String,
+    },
+
+    /// An operation that requires a node set was given another type of value
+    NotANodeSet,
+}
+
+impl Display for EvaluationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            EvaluationError::Syntax(error) => write!(f, "{}", error),
+            EvaluationError::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            EvaluationError::UnknownVariable { name } => write!(f, "Unknown variable {:?}", name),
+            EvaluationError::UnknownFunction { name } => write!(f, "Unknown function {:?}", name),
+            EvaluationError::InvalidPattern { pattern } => {
+                write!(f, "Invalid pattern {:?}", pattern)
+            }
+            EvaluationError::NotANodeSet => write!(f, "Expected a node set"),
+        }
+    }
+}
+
+impl std::error::Error for EvaluationError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViolationKind {
+    /// A "must" expression that evaluated to false
+    Must,
+
+    /// A node that exists even though one of its "when" expressions evaluated to false
+    When,
+
+    /// An expression that couldn't be evaluated
+    Error(EvaluationError),
+}
+
+/// A constraint that isn't satisfied by the instance data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub kind: ViolationKind,
+
+    /// The path of the data node the constraint applies to, see `Document::path`
+    pub path: String,
+    pub expression: String,
+
+    /// The "error-message" of a "must" statement
+    pub error_message: Option<String>,
+
+    /// The "error-app-tag" of a "must" statement, which defaults to "must-violation"
+    pub error_app_tag: Option<String>,
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match (&self.kind, &self.error_message) {
+            (ViolationKind::Error(error), _) => write!(f, "{}: {}", self.path, error),
+            (_, Some(message)) => write!(f, "{}: {}", self.path, message),
+            (ViolationKind::Must, None) => {
+                write!(
+                    f,
+                    "{}: Must {:?} is not satisfied",
+                    self.path, self.expression
+                )
+            }
+            (ViolationKind::When, None) => {
+                write!(
+                    f,
+                    "{}: When {:?} is not satisfied",
+                    self.path, self.expression
+                )
+            }
+        }
+    }
+}
+
+/// Evaluates an expression of a module with the given node as both context and current node
+///
+/// Prefixes are resolved through the imports of the module, while unprefixed names belong to the
+/// module of the context node, or the module itself at the root.
+///
+pub fn evaluate(
+    repository: &ModuleRepository,
+    document: &Document,
+    expr: &Expr,
+    module: ModuleId,
+    context: NodeId,
+) -> Result<Value, EvaluationError> {
+    let evaluator = Evaluator {
+        repository,
+        document,
+        scope: Scope::Prefixes {
+            module,
+            default: document
+                .schema(context)
+                .map_or_else(|| main_module(repository, module), |schema| schema.module),
+        },
+        current: context,
+        dummy: None,
+    };
+
+    evaluator.evaluate(expr, &Context::single(context))
+}
+
+/// Checks the "must" and "when" constraints of every node in an instance data tree
+pub fn validate(
+    repository: &ModuleRepository,
+    schema: &Schema,
+    tree: &[DataNode],
+) -> Vec<Violation> {
+    let mut violations = vec![];
+
+    for config in [true, false] {
+        let document = Document::new(schema, tree, config);
+        let mut validator = Validator {
+            repository,
+            document: &document,
+            checked: HashSet::new(),
+            violations: vec![],
+        };
+
+        for id in 1..document.len() {
+            if document.entries[id].config == config {
+                validator.validate(id);
+            }
+        }
+
+        violations.extend(validator.violations);
+    }
+
+    violations
+}
+
+struct Validator<'a, 'd> {
+    repository: &'a ModuleRepository,
+    document: &'d Document<'a>,
+
+    /// The choices and cases whose conditions have been checked, along with the parent node
+    checked: HashSet<(NodeId, *const SchemaNode)>,
+    violations: Vec<Violation>,
+}
+
+impl Validator<'_, '_> {
+    fn validate(&mut self, id: NodeId) {
+        let entry = &self.document.entries[id];
+        let (schema, parent) = match (entry.schema, entry.parent) {
+            (Some(schema), Some(parent)) => (schema, parent),
+            _ => return,
+        };
+
+        // See RFC 7950 section 7.21.5 for the context node of each kind of "when"
+        for node in entry.via.iter() {
+            if self.checked.insert((parent, *node as *const SchemaNode)) {
+                for when in node.properties("when").chain(node.conditions.iter()) {
+                    self.check(when, ViolationKind::When, id, parent, None);
+                }
+            }
+        }
+
+        for condition in schema.conditions.iter() {
+            self.check(condition, ViolationKind::When, id, parent, None);
+        }
+
+        for when in schema.properties("when") {
+            self.check(when, ViolationKind::When, id, id, Some(id));
+        }
+
+        for must in schema.properties("must") {
+            self.check(must, ViolationKind::Must, id, id, None);
+        }
+    }
+
+    fn check(
+        &mut self,
+        property: &Property,
+        kind: ViolationKind,
+        id: NodeId,
+        context: NodeId,
+        dummy: Option<NodeId>,
+    ) {
+        let expression = property.argument().unwrap_or_default().into_owned();
+
+        // For a grouping this is the module that uses it rather than the one that defines it
+        let default = self
+            .document
+            .schema(context)
+            .or(self.document.schema(id))
+            .map_or(property.source.module, |schema| schema.module);
+
+        let evaluator = Evaluator {
+            repository: self.repository,
+            document: self.document,
+            scope: Scope::Prefixes {
+                module: property.source.module,
+                default,
+            },
+            current: context,
+            dummy,
+        };
+
+        let result = parse_xpath(&expression)
+            .map_err(EvaluationError::Syntax)
+            .and_then(|expr| evaluator.evaluate(&expr, &Context::single(context)));
+
+        let kind = match result {
+            Ok(value) if value.boolean() => return,
+            Ok(_) => kind,
+            Err(error) => ViolationKind::Error(error),
+        };
+
+        let argument = |keyword| {
+            property
+                .node
+                .children()
+                .iter()
+                .find(|child| child.is(keyword))
+                .and_then(|child| child.argument())
+                .map(|argument| argument.into_owned())
+        };
+
+        let (error_message, error_app_tag) = match kind {
+            ViolationKind::Must => (
+                argument("error-message"),
+                argument("error-app-tag").or_else(|| Some("must-violation".to_string())),
+            ),
+            _ => (None, None),
+        };
+
+        self.violations.push(Violation {
+            kind,
+            path: self.document.path(id),
+            expression,
+            error_message,
+            error_app_tag,
+        });
+    }
+}
+
+/// How the prefixes of names in an expression are resolved
+#[derive(Debug, Clone, Copy)]
+enum Scope {
+    /// Through the prefix and imports of a module, with unprefixed names in the default module
+    Prefixes { module: ModuleId, default: ModuleId },
+
+    /// As module names, as in JSON encoded values, with unprefixed names in the given module
+    ModuleNames(ModuleId),
+}
+
+struct Context {
+    node: NodeId,
+    position: usize,
+    size: usize,
+}
+
+impl Context {
+    fn single(node: NodeId) -> Self {
+        Self {
+            node,
+            position: 1,
+            size: 1,
+        }
+    }
+}
+
+type EvaluationResult<T> = Result<T, EvaluationError>;
+
+#[derive(Clone, Copy)]
+struct Evaluator<'a, 'd> {
+    repository: &'a ModuleRepository,
+    document: &'d Document<'a>,
+    scope: Scope,
+
+    /// The node returned by current()
+    current: NodeId,
+
+    /// A node that is treated as having no value and no children, and stands in for every
+    /// instance of its schema node, see RFC 7950 section 7.21.5
+    dummy: Option<NodeId>,
+}
+
+impl Evaluator<'_, '_> {
+    fn evaluate(&self, expr: &Expr, context: &Context) -> EvaluationResult<Value> {
+        match &expr.kind {
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => self.binary(*operator, left, right, context),
+            ExprKind::Negate(expr) => {
+                let value = self.evaluate(expr, context)?;
+                Ok(Value::Number(-self.number(&value)))
+            }
+            ExprKind::Literal(text) => Ok(Value::String(text.clone())),
+            ExprKind::Number(number) => Ok(Value::Number(*number)),
+            ExprKind::Variable(name) => {
+                let name = name.clone();
+                Err(EvaluationError::UnknownVariable { name })
+            }
+            ExprKind::Function { name, arguments } => self.function(name, arguments, context),
+            ExprKind::Filter { expr, predicates } => {
+                let nodes = self.node_set(expr, context)?;
+                Ok(Value::NodeSet(self.filter(nodes, predicates)?))
+            }
+            ExprKind::Path { start, steps } => {
+                let mut nodes = match start {
+                    PathStart::Context => vec![context.node],
+                    PathStart::Root => vec![self.document.root()],
+                    PathStart::Expr(expr) => self.node_set(expr, context)?,
+                };
+
+                for step in steps {
+                    nodes = self.step(&nodes, step)?;
+                }
+
+                Ok(Value::NodeSet(nodes))
+            }
+        }
+    }
+
+    fn node_set(&self, expr: &Expr, context: &Context) -> EvaluationResult<Vec<NodeId>> {
+        match self.evaluate(expr, context)? {
+            Value::NodeSet(nodes) => Ok(nodes),
+            _ => Err(EvaluationError::NotANodeSet),
+        }
+    }
+
+    fn binary(
+        &self,
+        operator: BinaryOperator,
+        left: &Expr,
+        right: &Expr,
+        context: &Context,
+    ) -> EvaluationResult<Value> {
+        let left = self.evaluate(left, context)?;
+
+        // The right operand of "and" and "or" is only evaluated when needed
+        match operator {
+            BinaryOperator::Or if left.boolean() => return Ok(Value::Boolean(true)),
+            BinaryOperator::And if !left.boolean() => return Ok(Value::Boolean(false)),
+            _ => {}
+        }
+
+        let right = self.evaluate(right, context)?;
+
+        Ok(match operator {
+            BinaryOperator::Or | BinaryOperator::And => Value::Boolean(right.boolean()),
+            BinaryOperator::Equal
+            | BinaryOperator::NotEqual
+            | BinaryOperator::Less
+            | BinaryOperator::LessOrEqual
+            | BinaryOperator::Greater
+            | BinaryOperator::GreaterOrEqual => {
+                Value::Boolean(self.compare(operator, &left, &right))
+            }
+            BinaryOperator::Add => Value::Number(self.number(&left) + self.number(&right)),
+            BinaryOperator::Subtract => Value::Number(self.number(&left) - self.number(&right)),
+            BinaryOperator::Multiply => Value::Number(self.number(&left) * self.number(&right)),
+            BinaryOperator::Divide => Value::Number(self.number(&left) / self.number(&right)),
+            BinaryOperator::Modulo => Value::Number(self.number(&left) % self.number(&right)),
+            BinaryOperator::Union => match (left, right) {
+                (Value::NodeSet(mut left), Value::NodeSet(right)) => {
+                    left.extend(right);
+                    left.sort_unstable();
+                    left.dedup();
+                    Value::NodeSet(left)
+                }
+                _ => return Err(EvaluationError::NotANodeSet),
+            },
+        })
+    }
+
+    /// Compares two values, following the rules of XPath section 3.4
+    fn compare(&self, operator: BinaryOperator, left: &Value, right: &Value) -> bool {
+        let strings = |nodes: &[NodeId]| -> Vec<Value> {
+            nodes
+                .iter()
+                .map(|node| Value::String(self.string_value(*node)))
+                .collect()
+        };
+
+        match (left, right) {
+            (Value::NodeSet(nodes), Value::Boolean(_)) => {
+                self.compare(operator, &Value::Boolean(!nodes.is_empty()), right)
+            }
+            (Value::Boolean(_), Value::NodeSet(nodes)) => {
+                self.compare(operator, left, &Value::Boolean(!nodes.is_empty()))
+            }
+            (Value::NodeSet(left), Value::NodeSet(right)) => {
+                let right = strings(right);
+                strings(left).iter().any(|left| {
+                    right
+                        .iter()
+                        .any(|right| self.compare(operator, left, right))
+                })
+            }
+            (Value::NodeSet(nodes), _) => strings(nodes)
+                .iter()
+                .any(|left| self.compare(operator, left, right)),
+            (_, Value::NodeSet(nodes)) => strings(nodes)
+                .iter()
+                .any(|right| self.compare(operator, left, right)),
+            _ => {
+                let equality = matches!(operator, BinaryOperator::Equal | BinaryOperator::NotEqual);
+
+                let ordering = if equality
+                    && matches!(
+                        (left, right),
+                        (Value::Boolean(_), _) | (_, Value::Boolean(_))
+                    ) {
+                    left.boolean().partial_cmp(&right.boolean())
+                } else if equality
+                    && !matches!((left, right), (Value::Number(_), _) | (_, Value::Number(_)))
+                {
+                    Some(self.string(left).cmp(&self.string(right)))
+                } else {
+                    self.number(left).partial_cmp(&self.number(right))
+                };
+
+                match (operator, ordering) {
+                    (BinaryOperator::NotEqual, ordering) => {
+                        ordering != Some(std::cmp::Ordering::Equal)
+                    }
+                    (_, None) => false,
+                    (BinaryOperator::Equal, Some(ordering)) => ordering.is_eq(),
+                    (BinaryOperator::Less, Some(ordering)) => ordering.is_lt(),
+                    (BinaryOperator::LessOrEqual, Some(ordering)) => ordering.is_le(),
+                    (BinaryOperator::Greater, Some(ordering)) => ordering.is_gt(),
+                    (BinaryOperator::GreaterOrEqual, Some(ordering)) => ordering.is_ge(),
+                    _ => false,
+                }
+            }
+        }
+    }
+
+    fn string_value(&self, id: NodeId) -> String {
+        match self.dummy {
+            Some(dummy) if dummy == id => String::new(),
+            _ => self.document.string_value(id),
+        }
+    }
+
+    /// Converts a value as the string() function does
+    fn string(&self, value: &Value) -> String {
+        match value {
+            Value::NodeSet(nodes) => nodes
+                .first()
+                .map_or(String::new(), |node| self.string_value(*node)),
+            Value::Boolean(value) => value.to_string(),
+            Value::Number(number) => format_number(*number),
+            Value::String(text) => text.clone(),
+        }
+    }
+
+    /// Converts a value as the number() function does
+    fn number(&self, value: &Value) -> f64 {
+        match value {
+            Value::Boolean(value) => *value as u8 as f64,
+            Value::Number(number) => *number,
+            value => parse_number(&self.string(value)),
+        }
+    }
+
+    fn filter(&self, nodes: Vec<NodeId>, predicates: &[Expr]) -> EvaluationResult<Vec<NodeId>> {
+        let mut nodes = nodes;
+
+        for predicate in predicates {
+            let size = nodes.len();
+            let mut selected = vec![];
+
+            for (i, node) in nodes.iter().enumerate() {
+                let context = Context {
+                    node: *node,
+                    position: i + 1,
+                    size,
+                };
+
+                let keep = match self.evaluate(predicate, &context)? {
+                    Value::Number(number) => number == context.position as f64,
+                    value => value.boolean(),
+                };
+
+                if keep {
+                    selected.push(*node);
+                }
+            }
+
+            nodes = selected;
+        }
+
+        Ok(nodes)
+    }
+
+    fn step(&self, nodes: &[NodeId], step: &Step) -> EvaluationResult<Vec<NodeId>> {
+        let module = match &step.test {
+            NodeTest::Name { prefix, .. } | NodeTest::Wildcard { prefix } => {
+                Some(self.resolve(prefix.as_deref())?)
+            }
+            _ => None,
+        };
+
+        let mut selected = vec![];
+
+        for node in nodes {
+            let candidates = self
+                .axis(*node, step.axis)
+                .into_iter()
+                .filter(|candidate| self.matches(*candidate, &step.test, module))
+                .collect();
+
+            selected.extend(self.filter(candidates, &step.predicates)?);
+        }
+
+        selected.sort_unstable();
+        selected.dedup();
+        Ok(selected)
+    }
+
+    fn resolve(&self, prefix: Option<&str>) -> EvaluationResult<ModuleId> {
+        let module = match self.scope {
+            Scope::Prefixes { module, default } => match prefix {
+                Some(_) => resolve_prefix(self.repository, module, prefix),
+                None => Some(default),
+            },
+            Scope::ModuleNames(module) => match prefix {
+                Some(prefix) => self.repository.find(prefix, None),
+                None => Some(module),
+            },
+        };
+
+        module.ok_or_else(|| EvaluationError::UnknownPrefix {
+            prefix: prefix.unwrap_or_default().to_string(),
+        })
+    }
+
+    fn matches(&self, id: NodeId, test: &NodeTest, module: Option<ModuleId>) -> bool {
+        let data = self.document.data(id);
+
+        match test {
+            NodeTest::Node => true,
+            NodeTest::Text | NodeTest::Comment | NodeTest::ProcessingInstruction(_) => false,
+            NodeTest::Name { name, .. } => {
+                data.is_some_and(|data| data.name == *name && Some(data.module) == module)
+            }
+            NodeTest::Wildcard { prefix: None } => data.is_some(),
+            NodeTest::Wildcard { prefix: Some(_) } => {
+                data.is_some_and(|data| Some(data.module) == module)
+            }
+        }
+    }
+
+    /// Returns true for the descendants of the dummy node, and for the other instances of its
+    /// schema node under the same parent along with their descendants, which are all hidden
+    fn is_hidden(&self, id: NodeId) -> bool {
+        let dummy = match self.dummy {
+            Some(dummy) if dummy != id => dummy,
+            _ => return false,
+        };
+
+        let entries = &self.document.entries;
+        let schema = entries[dummy].schema;
+        let siblings = entries[dummy]
+            .parent
+            .map_or(&[][..], |parent| &entries[parent].children);
+
+        siblings.iter().any(|sibling| {
+            let instance = *sibling == dummy
+                || schema.is_some_and(|schema| {
+                    entries[*sibling]
+                        .schema
+                        .is_some_and(|other| std::ptr::eq(schema, other))
+                });
+
+            instance && *sibling <= id && id <= entries[*sibling].end
+        })
+    }
+
+    /// Returns the nodes on an axis, in the order of the axis
+    fn axis(&self, id: NodeId, axis: Axis) -> Vec<NodeId> {
+        let entries = &self.document.entries;
+        let entry = &entries[id];
+
+        let mut ancestors = vec![];
+        let mut current = entry.parent;
+
+        while let Some(parent) = current {
+            ancestors.push(parent);
+            current = entries[parent].parent;
+        }
+
+        let siblings = entry
+            .parent
+            .map_or(&[][..], |parent| &entries[parent].children);
+        let index = siblings.iter().position(|sibling| *sibling == id);
+
+        let nodes: Vec<NodeId> = match axis {
+            Axis::Child => entry.children.clone(),
+            Axis::Descendant => (id + 1..=entry.end).collect(),
+            Axis::DescendantOrSelf => (id..=entry.end).collect(),
+            Axis::Parent => entry.parent.into_iter().collect(),
+            Axis::Ancestor => ancestors,
+            Axis::AncestorOrSelf => [id].into_iter().chain(ancestors).collect(),
+            Axis::FollowingSibling => index.map_or(vec![], |i| siblings[i + 1..].to_vec()),
+            Axis::PrecedingSibling => {
+                index.map_or(vec![], |i| siblings[..i].iter().rev().copied().collect())
+            }
+            Axis::Following => (entry.end + 1..entries.len()).collect(),
+            Axis::Preceding => (0..id)
+                .rev()
+                .filter(|node| !ancestors.contains(node))
+                .collect(),
+            Axis::Self_ => vec![id],
+            Axis::Attribute | Axis::Namespace => vec![],
+        };
+
+        let descends = matches!(
+            axis,
+            Axis::Child | Axis::Descendant | Axis::DescendantOrSelf
+        );
+
+        nodes
+            .into_iter()
+            .filter(|node| !self.is_hidden(*node))
+            .filter(|node| !(descends && self.dummy == Some(id) && *node != id))
+            .collect()
+    }
+
+    fn function(
+        &self,
+        name: &str,
+        arguments: &[Expr],
+        context: &Context,
+    ) -> EvaluationResult<Value> {
+        let argument = |i: usize| self.evaluate(&arguments[i], context);
+        let string = |i: usize| argument(i).map(|value| self.string(&value));
+        let number = |i: usize| argument(i).map(|value| self.number(&value));
+        let nodes = |i: usize| self.node_set(&arguments[i], context);
+
+        // The string of the first argument, or of the context node without arguments
+        let string_or_context = || match arguments.is_empty() {
+            true => Ok(self.string_value(context.node)),
+            false => string(0),
+        };
+
+        // The first node of the first argument, or the context node without arguments
+        let first_or_context = || match arguments.is_empty() {
+            true => Ok(self.document.data(context.node)),
+            false => Ok(nodes(0)?.first().and_then(|node| self.document.data(*node))),
+        };
+
+        Ok(match name {
+            "last" => Value::Number(context.size as f64),
+            "position" => Value::Number(context.position as f64),
+            "count" => Value::Number(nodes(0)?.len() as f64),
+            "id" => Value::NodeSet(vec![]),
+            "local-name" => {
+                Value::String(first_or_context()?.map_or(String::new(), |data| data.name.clone()))
+            }
+            "namespace-uri" => Value::String(first_or_context()?.map_or(String::new(), |data| {
+                let module = self.repository.get(data.module);
+                module.namespace().unwrap_or_default().into_owned()
+            })),
+            "name" => Value::String(first_or_context()?.map_or(String::new(), |data| {
+                format!("{}:{}", self.repository.get(data.module).prefix, data.name)
+            })),
+            "string" => Value::String(string_or_context()?),
+            "concat" => Value::String(
+                (0..arguments.len())
+                    .map(string)
+                    .collect::<EvaluationResult<String>>()?,
+            ),
+            "starts-with" => Value::Boolean(string(0)?.starts_with(&string(1)?)),
+            "contains" => Value::Boolean(string(0)?.contains(&string(1)?)),
+            "substring-before" => {
+                let (text, separator) = (string(0)?, string(1)?);
+                let end = text.find(&separator).unwrap_or(0);
+                Value::String(text[..end].to_string())
+            }
+            "substring-after" => {
+                let (text, separator) = (string(0)?, string(1)?);
+                let start = text
+                    .find(&separator)
+                    .map_or(text.len(), |i| i + separator.len());
+                Value::String(text[start..].to_string())
+            }
+            "substring" => {
+                let text = string(0)?;
+                let start = round(number(1)?);
+                let end = match arguments.len() {
+                    3 => start + round(number(2)?),
+                    _ => f64::INFINITY,
+                };
+
+                Value::String(
+                    text.chars()
+                        .enumerate()
+                        .filter(|(i, _)| {
+                            let position = (i + 1) as f64;
+                            position >= start && position < end
+                        })
+                        .map(|(_, c)| c)
+                        .collect(),
+                )
+            }
+            "string-length" => Value::Number(string_or_context()?.chars().count() as f64),
+            "normalize-space" => Value::String(
+                string_or_context()?
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            "translate" => {
+                let (text, from, to) = (string(0)?, string(1)?, string(2)?);
+                let to: Vec<char> = to.chars().collect();
+
+                Value::String(
+                    text.chars()
+                        .filter_map(|c| match from.chars().position(|from| from == c) {
+                            Some(i) => to.get(i).copied(),
+                            None => Some(c),
+                        })
+                        .collect(),
+                )
+            }
+            "boolean" => Value::Boolean(argument(0)?.boolean()),
+            "not" => Value::Boolean(!argument(0)?.boolean()),
+            "true" => Value::Boolean(true),
+            "false" => Value::Boolean(false),
+            "lang" => Value::Boolean(false),
+            "number" => Value::Number(match arguments.is_empty() {
+                true => parse_number(&self.string_value(context.node)),
+                false => number(0)?,
+            }),
+            "sum" => Value::Number(
+                nodes(0)?
+                    .iter()
+                    .map(|node| parse_number(&self.string_value(*node)))
+                    .sum(),
+            ),
+            "floor" => Value::Number(number(0)?.floor()),
+            "ceiling" => Value::Number(number(0)?.ceil()),
+            "round" => Value::Number(round(number(0)?)),
+            "current" => Value::NodeSet(vec![self.current]),
+            "deref" => Value::NodeSet(self.deref(&nodes(0)?)?),
+            "derived-from" => Value::Boolean(self.derived_from(&nodes(0)?, &string(1)?, false)?),
+            "derived-from-or-self" => {
+                Value::Boolean(self.derived_from(&nodes(0)?, &string(1)?, true)?)
+            }
+            "re-match" => {
+                let pattern = string(1)?;
+
+                match Pattern::new(&pattern, false) {
+                    Ok(compiled) => Value::Boolean(compiled.is_match(&string(0)?)),
+                    Err(_) => return Err(EvaluationError::InvalidPattern { pattern }),
+                }
+            }
+            "enum-value" => Value::Number(self.enum_value(&nodes(0)?)),
+            "bit-is-set" => {
+                let bit = string(1)?;
+                let value = nodes(0)?
+                    .first()
+                    .map_or(String::new(), |node| self.string_value(*node));
+
+                Value::Boolean(value.split_whitespace().any(|set| set == bit))
+            }
+            _ => {
+                let name = name.to_string();
+                return Err(EvaluationError::UnknownFunction { name });
+            }
+        })
+    }
+
+    /// Follows the leafref or instance-identifier value of the first node
+    fn deref(&self, nodes: &[NodeId]) -> EvaluationResult<Vec<NodeId>> {
+        let id = match nodes.first() {
+            Some(id) => *id,
+            None => return Ok(vec![]),
+        };
+
+        let (data, schema) = match (self.document.data(id), self.document.schema(id)) {
+            (Some(data), Some(schema)) => (data, schema),
+            _ => return Ok(vec![]),
+        };
+
+        let resolved = match resolve_node_type(self.repository, schema).0 {
+            Some(resolved) => resolved,
+            None => return Ok(vec![]),
+        };
+
+        let value = self.string_value(id);
+
+        match resolved.builtin {
+            BuiltinType::Leafref => {
+                let path = match resolved.path {
+                    Some(path) => path,
+                    None => return Ok(vec![]),
+                };
+
+                let argument = path.argument().unwrap_or_default();
+                let expr = parse_xpath(&argument).map_err(EvaluationError::Syntax)?;
+                let evaluator = Evaluator {
+                    scope: Scope::Prefixes {
+                        module: path.source.module,
+                        default: schema.module,
+                    },
+                    current: id,
+                    ..*self
+                };
+
+                let targets = evaluator.node_set(&expr, &Context::single(id))?;
+
+                Ok(targets
+                    .into_iter()
+                    .filter(|target| self.string_value(*target) == value)
+                    .collect())
+            }
+            BuiltinType::InstanceIdentifier => {
+                let expr = parse_xpath(&value).map_err(EvaluationError::Syntax)?;
+                let evaluator = Evaluator {
+                    scope: Scope::ModuleNames(data.module),
+                    current: id,
+                    ..*self
+                };
+
+                evaluator.node_set(&expr, &Context::single(id))
+            }
+            _ => Ok(vec![]),
+        }
+    }
+
+    /// Returns true if the value of any of the nodes is an identity derived from the given one
+    fn derived_from(
+        &self,
+        nodes: &[NodeId],
+        identity: &str,
+        or_self: bool,
+    ) -> EvaluationResult<bool> {
+        let reference = match IdentifierRef::parse(identity.trim()) {
+            Some(reference) => reference,
+            None => return Ok(false),
+        };
+
+        let module = self.resolve(reference.prefix.as_deref())?;
+        let base = match find_top_level(self.repository, module, "identity", &reference.name) {
+            Some(base) => base,
+            None => return Ok(false),
+        };
+
+        for node in nodes {
+            let data = match self.document.data(*node) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let value = match IdentifierRef::parse(self.string_value(*node).trim()) {
+                Some(value) => value,
+                None => continue,
+            };
+
+            let module = match &value.prefix {
+                Some(prefix) => self.repository.find(prefix, None),
+                None => Some(data.module),
+            };
+
+            let identity = module.and_then(|module| {
+                find_top_level(self.repository, module, "identity", &value.name)
+            });
+
+            if let Some(identity) = identity {
+                if (or_self && identity == base) || self.is_derived(&identity, &base) {
+                    return Ok(true);
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Returns true if an identity is derived from the base, directly or indirectly
+    fn is_derived(&self, identity: &SourceRef, base: &SourceRef) -> bool {
+        let mut queue = VecDeque::from([identity.clone()]);
+        let mut visited = HashSet::new();
+
+        while let Some(current) = queue.pop_front() {
+            if !visited.insert(current.clone()) {
+                continue;
+            }
+
+            for (i, child) in current.node(self.repository).children().iter().enumerate() {
+                let argument = match child.argument().filter(|_| child.is("base")) {
+                    Some(argument) => argument,
+                    None => continue,
+                };
+
+                let parent =
+                    find_definition(self.repository, &current.child(i), "identity", &argument);
+
+                match parent {
+                    Ok(parent) if parent == *base => return true,
+                    Ok(parent) => queue.push_back(parent),
+                    Err(_) => {}
+                }
+            }
+        }
+
+        false
+    }
+
+    /// Returns the value of the enum of the first node, or NaN if it isn't an enumeration
+    fn enum_value(&self, nodes: &[NodeId]) -> f64 {
+        let id = match nodes.first() {
+            Some(id) => *id,
+            None => return f64::NAN,
+        };
+
+        let resolved = self
+            .document
+            .schema(id)
+            .and_then(|schema| resolve_node_type(self.repository, schema).0);
+
+        let value = self.string_value(id);
+
+        resolved
+            .and_then(|resolved| {
+                resolved
+                    .enums
+                    .iter()
+                    .find(|member| member.name == value)
+                    .map(|member| member.value as f64)
+            })
+            .unwrap_or(f64::NAN)
+    }
+}
+
+/// Rounds a number as the round() function does, towards positive infinity on ties
+fn round(number: f64) -> f64 {
+    (number + 0.5).floor()
+}
+
+/// Parses a number as the number() function does, returning NaN for anything else
+fn parse_number(text: &str) -> f64 {
+    let text = text.trim();
+    let digits = text.strip_prefix('-').unwrap_or(text);
+
+    let is_number = digits.chars().any(|c| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && digits.matches('.').count() <= 1;
+
+    match is_number {
+        true => text.parse().unwrap_or(f64::NAN),
+        false => f64::NAN,
+    }
+}
+
+/// Formats a number as the string() function does
+fn format_number(number: f64) -> String {
+    if number.is_nan() {
+        "NaN".to_string()
+    } else if number.is_infinite() {
+        match number > 0.0 {
+            true => "Infinity".to_string(),
+            false => "-Infinity".to_string(),
+        }
+    } else if number == 0.0 {
+        "0".to_string()
+    } else {
+        number.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        textwrap::dedent(text).trim_start().to_string()
+    }
+
+    fn repository() -> ModuleRepository {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "system.yang",
+            dedent(
+                r#"
+                module system {
+                    namespace "urn:system";
+                    prefix sys;
+
+                    identity interface-type;
+                    identity ethernet { base interface-type; }
+                    identity fast-ethernet { base ethernet; }
+                    identity loopback { base interface-type; }
+
+                    container interfaces {
+                        list interface {
+                            key name;
+                            leaf name { type string; }
+                            leaf type { type identityref { base interface-type; } }
+                            leaf mtu {
+                                type uint16;
+                                must ". <= 1500 or derived-from(../type, 'sys:ethernet')" {
+                                    error-message "The MTU is too large";
+                                    error-app-tag "mtu-too-large";
+                                }
+                            }
+                            leaf speed {
+                                when "derived-from-or-self(../type, 'ethernet')";
+                                type enumeration {
+                                    enum "10m";
+                                    enum "100m";
+                                    enum "1g" { value 10; }
+                                }
+                            }
+                            leaf oper-status {
+                                config false;
+                                type string;
+                                must "../name != 'down'";
+                            }
+                            must "count(oper-status) = 0";
+                        }
+                    }
+
+                    container routing {
+                        leaf interface {
+                            type leafref { path "/interfaces/interface/name"; }
+                            must "deref(.)/../mtu >= 1000";
+                        }
+                        choice protocol {
+                            case static {
+                                when "interface";
+                                leaf gateway { type string; }
+                            }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        repository
+    }
+
+    fn tree(system: ModuleId) -> Vec<DataNode> {
+        let interface = |name: &str, type_: &str, mtu: &str, extra: Vec<DataNode>| {
+            let mut children = vec![
+                DataNode::leaf(system, "name", name),
+                DataNode::leaf(system, "type", type_),
+                DataNode::leaf(system, "mtu", mtu),
+            ];
+
+            children.extend(extra);
+            DataNode::container(system, "interface", children)
+        };
+
+        vec![
+            DataNode::container(
+                system,
+                "interfaces",
+                vec![
+                    interface(
+                        "eth0",
+                        "system:fast-ethernet",
+                        "9000",
+                        vec![
+                            DataNode::leaf(system, "speed", "1g"),
+                            DataNode::leaf(system, "oper-status", "up"),
+                        ],
+                    ),
+                    interface(
+                        "lo",
+                        "loopback",
+                        "65535",
+                        vec![DataNode::leaf(system, "speed", "10m")],
+                    ),
+                    interface(
+                        "down",
+                        "loopback",
+                        "500",
+                        vec![DataNode::leaf(system, "oper-status", "down")],
+                    ),
+                ],
+            ),
+            DataNode::container(
+                system,
+                "routing",
+                vec![
+                    DataNode::leaf(system, "interface", "down"),
+                    DataNode::leaf(system, "gateway", "10.0.0.1"),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn expressions() {
+        let mut repository = repository();
+        let system = repository.load("system", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+        let tree = tree(system);
+        let document = Document::new(&schema, &tree, false);
+
+        let evaluate = |text: &str| {
+            let expr = parse_xpath(text).unwrap();
+            evaluate(&repository, &document, &expr, system, document.root())
+        };
+
+        let string = |text: &str| match evaluate(text).unwrap() {
+            Value::NodeSet(nodes) => nodes.iter().map(|n| document.path(*n)).collect(),
+            value => vec![format!("{:?}", value)],
+        };
+
+        let cases: &[(&str, &[&str])] = &[
+            (
+                "/interfaces/interface[mtu > 1000]/name",
+                &[
+                    "/interfaces/interface[1]/name",
+                    "/interfaces/interface[2]/name",
+                ],
+            ),
+            (
+                "//interface[last()]/preceding-sibling::*[1]",
+                &["/interfaces/interface[2]"],
+            ),
+            ("count(//oper-status)", &["Number(2.0)"]),
+            ("sum(//mtu) div 2", &["Number(37517.5)"]),
+            ("//interface[name = 'lo']/mtu = 65535", &["Boolean(true)"]),
+            ("//interface/name = 'missing'", &["Boolean(false)"]),
+            ("//interface/name != 'lo'", &["Boolean(true)"]),
+            (
+                "concat(substring('abcdef', 2, 3), '-', 7 mod 3)",
+                &["String(\"bcd-1\")"],
+            ),
+            (
+                "translate(normalize-space('  a  b '), 'ab', 'B')",
+                &["String(\"B \")"],
+            ),
+            ("string(1 div 0)", &["String(\"Infinity\")"]),
+            ("enum-value(//interface[1]/speed)", &["Number(10.0)"]),
+            (
+                "re-match(//interface[1]/name, 'eth[0-9]+')",
+                &["Boolean(true)"],
+            ),
+            (
+                "deref(/routing/interface)/../mtu",
+                &["/interfaces/interface[3]/mtu"],
+            ),
+            ("/sys:routing/ancestor::node()", &["/"]),
+            (
+                "not(/interfaces/interface[2]/speed/following::sys:*)",
+                &["Boolean(false)"],
+            ),
+        ];
+
+        for (text, expected) in cases {
+            assert_eq!(
+                expected.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+                string(text),
+                "{}",
+                text
+            );
+        }
+
+        assert_eq!(
+            Err(EvaluationError::UnknownPrefix {
+                prefix: "x".to_string()
+            }),
+            evaluate("/x:interfaces")
+        );
+    }
+
+    #[test]
+    fn constraints() {
+        let mut repository = repository();
+        let system = repository.load("system", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+        let tree = tree(system);
+
+        let violations = validate(&repository, &schema, &tree);
+
+        let summary: Vec<_> = violations
+            .iter()
+            .map(|v| (v.kind.clone(), v.path.as_str(), v.error_message.as_deref()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    ViolationKind::Must,
+                    "/interfaces/interface[2]/mtu",
+                    Some("The MTU is too large")
+                ),
+                (ViolationKind::When, "/interfaces/interface[2]/speed", None),
+                (ViolationKind::Must, "/routing/interface", None),
+                (
+                    ViolationKind::Must,
+                    "/interfaces/interface[3]/oper-status",
+                    None
+                ),
+            ],
+            summary
+        );
+
+        assert_eq!(
+            Some("mtu-too-large"),
+            violations[0].error_app_tag.as_deref()
+        );
+        assert_eq!(
+            Some("must-violation"),
+            violations[2].error_app_tag.as_deref()
+        );
+    }
+
+    #[test]
+    fn list_conditions() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    container servers {
+                        list server {
+                            key name;
+                            when "count(../server) = 1 and not(../server/name)";
+                            leaf name { type string; }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+
+        // Every entry of the list is replaced by a single dummy node without children
+        let server = |name: &str| {
+            DataNode::container(main, "server", vec![DataNode::leaf(main, "name", name)])
+        };
+        let tree = vec![DataNode::container(
+            main,
+            "servers",
+            vec![server("a"), server("b")],
+        )];
+
+        assert_eq!(
+            Vec::<Violation>::new(),
+            validate(&repository, &schema, &tree)
+        );
+    }
+
+    #[test]
+    fn groupings() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "limits.yang",
+            dedent(
+                r#"
+                module limits {
+                    namespace "urn:limits";
+                    prefix l;
+
+                    grouping range {
+                        leaf min { type uint8; }
+                        leaf max {
+                            type uint8;
+                            must ". >= ../min";
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    import limits { prefix l; }
+
+                    container sizes {
+                        uses l:range;
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+
+        let sizes = |min: &str, max: &str| {
+            vec![DataNode::container(
+                main,
+                "sizes",
+                vec![
+                    DataNode::leaf(main, "min", min),
+                    DataNode::leaf(main, "max", max),
+                ],
+            )]
+        };
+
+        let tree = sizes("5", "9");
+        assert_eq!(
+            Vec::<Violation>::new(),
+            validate(&repository, &schema, &tree)
+        );
+
+        let tree = sizes("10", "9");
+        let paths: Vec<_> = validate(&repository, &schema, &tree)
+            .into_iter()
+            .map(|violation| (violation.kind, violation.path))
+            .collect();
+        assert_eq!(vec![(ViolationKind::Must, "/sizes/max".to_string())], paths);
+    }
+}
@@ -0,0 +1,516 @@
+//
+// Features
+//
+// In YANG 1.1 the argument of "if-feature" is a boolean expression over feature names, such as
+// "foo and (bar or not baz)" (RFC 7950 section 7.20.2). This module parses those expressions,
+// resolves the feature names they contain, and evaluates them against a set of enabled features,
+// so that the parts of a compiled schema that a particular server doesn't support can be pruned.
+//
+// A feature is only considered enabled if its own "if-feature" statements are satisfied as well,
+// since a server can't support a feature without supporting the features it depends on.
+//
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+use crate::arguments::{ArgumentType, IdentifierRef};
+use crate::lexing::Span;
+use crate::parsing::Node;
+use crate::prefixes::argument_span;
+use crate::repository::{ModuleId, ModuleRepository};
+use crate::schema::{
+    find_top_level, resolve_prefix, top_level_statements, Schema, SchemaNode, SourceRef,
+};
+
+/// A parsed "if-feature-expr"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureExpr {
+    Feature {
+        reference: IdentifierRef,
+
+        /// The byte offset of the reference within the argument
+        offset: usize,
+    },
+    Not(Box<FeatureExpr>),
+    And(Box<FeatureExpr>, Box<FeatureExpr>),
+    Or(Box<FeatureExpr>, Box<FeatureExpr>),
+}
+
+/// The features that are enabled in each module
+#[derive(Debug, Clone, Default)]
+pub struct FeatureSet {
+    enabled: HashSet<(ModuleId, String)>,
+}
+
+impl FeatureSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a set with every feature defined in the repository enabled
+    pub fn all(repository: &ModuleRepository) -> Self {
+        let mut features = Self::new();
+
+        for (source, module) in top_level_statements(repository, "feature") {
+            if let Some(name) = source.node(repository).argument() {
+                features.enable(module, &name);
+            }
+        }
+
+        features
+    }
+
+    /// Enables a feature of a module, which must not be a submodule
+    pub fn enable(&mut self, module: ModuleId, name: &str) {
+        self.enabled.insert((module, name.to_string()));
+    }
+
+    pub fn is_enabled(&self, module: ModuleId, name: &str) -> bool {
+        self.enabled.contains(&(module, name.to_string()))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeatureError {
+    pub kind: FeatureErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeatureErrorKind {
+    /// An expression that doesn't follow the "if-feature-expr" syntax
+    InvalidExpression {
+        /// The byte offset of the unexpected token, or the length of the argument if it ended
+        /// too early
+        offset: usize,
+    },
+    UnknownPrefix {
+        prefix: String,
+    },
+    UnknownFeature {
+        name: String,
+    },
+}
+
+impl Display for FeatureError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            FeatureErrorKind::InvalidExpression { .. } => {
+                write!(f, "Invalid if-feature expression")
+            }
+            FeatureErrorKind::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            FeatureErrorKind::UnknownFeature { name } => write!(f, "Unknown feature {:?}", name),
+        }
+    }
+}
+
+impl std::error::Error for FeatureError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Token<'a> {
+    Open,
+    Close,
+    Word(&'a str),
+}
+
+/// Parses an "if-feature" argument
+pub fn parse_if_feature(text: &str) -> Result<FeatureExpr, FeatureErrorKind> {
+    let mut tokens = vec![];
+    let mut start = None;
+
+    for (i, c) in text.char_indices().chain([(text.len(), ' ')]) {
+        let separator = c.is_whitespace() || c == '(' || c == ')';
+
+        match (separator, start) {
+            (true, Some(s)) => {
+                tokens.push((s, Token::Word(&text[s..i])));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+
+        match c {
+            '(' => tokens.push((i, Token::Open)),
+            ')' => tokens.push((i, Token::Close)),
+            _ => {}
+        }
+    }
+
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        end: text.len(),
+    };
+
+    let expr = parser.expr()?;
+
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some((offset, _)) => Err(FeatureErrorKind::InvalidExpression { offset: *offset }),
+    }
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Result<(usize, Token<'_>), FeatureErrorKind> {
+        let token = self.tokens.get(self.position).copied();
+        self.position += 1;
+        token.ok_or(FeatureErrorKind::InvalidExpression { offset: self.end })
+    }
+
+    fn accept(&mut self, keyword: &str) -> bool {
+        let found =
+            self.tokens.get(self.position).map(|(_, token)| *token) == Some(Token::Word(keyword));
+        self.position += found as usize;
+        found
+    }
+
+    /// if-feature-expr = if-feature-term [sep or-keyword sep if-feature-expr]
+    fn expr(&mut self) -> Result<FeatureExpr, FeatureErrorKind> {
+        let term = self.term()?;
+
+        match self.accept("or") {
+            true => Ok(FeatureExpr::Or(Box::new(term), Box::new(self.expr()?))),
+            false => Ok(term),
+        }
+    }
+
+    /// if-feature-term = if-feature-factor [sep and-keyword sep if-feature-term]
+    fn term(&mut self) -> Result<FeatureExpr, FeatureErrorKind> {
+        let factor = self.factor()?;
+
+        match self.accept("and") {
+            true => Ok(FeatureExpr::And(Box::new(factor), Box::new(self.term()?))),
+            false => Ok(factor),
+        }
+    }
+
+    /// if-feature-factor = not-keyword sep if-feature-factor /
+    ///                     "(" optsep if-feature-expr optsep ")" /
+    ///                     identifier-ref-arg
+    fn factor(&mut self) -> Result<FeatureExpr, FeatureErrorKind> {
+        match self.next()? {
+            (_, Token::Word("not")) => Ok(FeatureExpr::Not(Box::new(self.factor()?))),
+            (_, Token::Open) => {
+                let expr = self.expr()?;
+
+                match self.next()? {
+                    (_, Token::Close) => Ok(expr),
+                    (offset, _) => Err(FeatureErrorKind::InvalidExpression { offset }),
+                }
+            }
+            (offset, Token::Word(word)) if !matches!(word, "and" | "or") => {
+                match IdentifierRef::parse(word) {
+                    Some(reference) => Ok(FeatureExpr::Feature { reference, offset }),
+                    None => Err(FeatureErrorKind::InvalidExpression { offset }),
+                }
+            }
+            (offset, _) => Err(FeatureErrorKind::InvalidExpression { offset }),
+        }
+    }
+}
+
+/// Removes the schema nodes whose "if-feature" statements aren't satisfied by the features
+///
+/// Nodes with an invalid "if-feature" statement, or one that refers to an unknown feature, are
+/// removed as well, and the problem is reported.
+///
+pub fn prune(
+    repository: &ModuleRepository,
+    schema: &mut Schema,
+    features: &FeatureSet,
+) -> Vec<FeatureError> {
+    let mut evaluator = Evaluator::new(repository, features);
+    evaluator.prune(&mut schema.nodes);
+    evaluator.errors
+}
+
+/// Evaluates an "if-feature" statement of a module against a set of features
+pub fn evaluate_if_feature(
+    repository: &ModuleRepository,
+    source: &SourceRef,
+    features: &FeatureSet,
+) -> (bool, Vec<FeatureError>) {
+    let mut evaluator = Evaluator::new(repository, features);
+    let enabled = evaluator.if_feature(source);
+    (enabled, evaluator.errors)
+}
+
+struct Evaluator<'r> {
+    repository: &'r ModuleRepository,
+    features: &'r FeatureSet,
+    errors: Vec<FeatureError>,
+
+    /// Features that have been evaluated, or None while they are being evaluated
+    resolved: HashMap<SourceRef, Option<bool>>,
+}
+
+impl<'r> Evaluator<'r> {
+    fn new(repository: &'r ModuleRepository, features: &'r FeatureSet) -> Self {
+        Self {
+            repository,
+            features,
+            errors: vec![],
+            resolved: HashMap::new(),
+        }
+    }
+
+    fn error(&mut self, kind: FeatureErrorKind, module: ModuleId, span: Span) {
+        self.errors.push(FeatureError { kind, module, span });
+    }
+
+    fn prune(&mut self, nodes: &mut Vec<SchemaNode>) {
+        nodes.retain(|node| self.is_enabled(node));
+
+        for node in nodes.iter_mut() {
+            self.prune(&mut node.children);
+        }
+    }
+
+    fn is_enabled(&mut self, node: &SchemaNode) -> bool {
+        let sources: Vec<_> = node
+            .properties("if-feature")
+            .map(|property| property.source.clone())
+            .collect();
+
+        // Every statement is evaluated, so that all errors are reported
+        let mut enabled = true;
+
+        for source in sources.iter() {
+            enabled &= self.if_feature(source);
+        }
+
+        enabled
+    }
+
+    fn if_feature(&mut self, source: &SourceRef) -> bool {
+        let node = source.node(self.repository);
+        let argument = node.argument().unwrap_or_default();
+
+        match parse_if_feature(&argument) {
+            Ok(expr) => self.evaluate(&expr, node, source.module),
+            Err(kind) => {
+                let offset = match kind {
+                    FeatureErrorKind::InvalidExpression { offset } => offset,
+                    _ => 0,
+                };
+
+                let span = argument_span(node, offset, 1);
+                self.error(kind, source.module, span);
+                false
+            }
+        }
+    }
+
+    /// Evaluates all parts of an expression, so that every unknown feature is reported
+    fn evaluate(&mut self, expr: &FeatureExpr, node: &Node, module: ModuleId) -> bool {
+        match expr {
+            FeatureExpr::Feature { reference, offset } => {
+                let span = argument_span(node, *offset, reference.to_string().len());
+                self.feature(reference, module, span)
+            }
+            FeatureExpr::Not(expr) => !self.evaluate(expr, node, module),
+            FeatureExpr::And(left, right) => {
+                let left = self.evaluate(left, node, module);
+                self.evaluate(right, node, module) && left
+            }
+            FeatureExpr::Or(left, right) => {
+                let left = self.evaluate(left, node, module);
+                self.evaluate(right, node, module) || left
+            }
+        }
+    }
+
+    fn feature(&mut self, reference: &IdentifierRef, module: ModuleId, span: Span) -> bool {
+        let target = match resolve_prefix(self.repository, module, reference.prefix.as_deref()) {
+            Some(target) => target,
+            None => {
+                let prefix = reference.prefix.clone().unwrap_or_default();
+                self.error(FeatureErrorKind::UnknownPrefix { prefix }, module, span);
+                return false;
+            }
+        };
+
+        let definition = match find_top_level(self.repository, target, "feature", &reference.name) {
+            Some(definition) => definition,
+            None => {
+                let name = reference.name.clone();
+                self.error(FeatureErrorKind::UnknownFeature { name }, module, span);
+                return false;
+            }
+        };
+
+        if !self.features.is_enabled(target, &reference.name) {
+            return false;
+        }
+
+        // A feature that depends on itself can't be satisfied
+        match self.resolved.get(&definition) {
+            Some(Some(enabled)) => return *enabled,
+            Some(None) => return false,
+            None => {}
+        }
+
+        self.resolved.insert(definition.clone(), None);
+
+        let dependencies: Vec<_> = definition
+            .node(self.repository)
+            .children()
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| child.is("if-feature"))
+            .map(|(i, _)| definition.child(i))
+            .collect();
+
+        let mut enabled = true;
+
+        for source in dependencies.iter() {
+            enabled &= self.if_feature(source);
+        }
+
+        self.resolved.insert(definition, Some(enabled));
+        enabled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        textwrap::dedent(text).trim_start().to_string()
+    }
+
+    #[test]
+    fn parsing() {
+        let feature = |name: &str, offset: usize| FeatureExpr::Feature {
+            reference: IdentifierRef::parse(name).unwrap(),
+            offset,
+        };
+
+        assert_eq!(
+            Ok(FeatureExpr::Or(
+                Box::new(FeatureExpr::And(
+                    Box::new(feature("a", 0)),
+                    Box::new(FeatureExpr::Not(Box::new(feature("x:b", 10))))
+                )),
+                Box::new(feature("c", 18))
+            )),
+            parse_if_feature("a and not x:b or (c)")
+        );
+
+        let offset = |offset| Err(FeatureErrorKind::InvalidExpression { offset });
+        assert_eq!(offset(2), parse_if_feature("a b"));
+        assert_eq!(offset(5), parse_if_feature("a and"));
+        assert_eq!(offset(3), parse_if_feature("(a b)"));
+        assert_eq!(offset(0), parse_if_feature("or"));
+        assert_eq!(offset(0), parse_if_feature(""));
+    }
+
+    #[test]
+    fn pruning() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "base.yang",
+            dedent(
+                r#"
+                module base {
+                    namespace "urn:base";
+                    prefix b;
+
+                    feature routing;
+                    feature ipv6 { if-feature routing; }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    import base { prefix b; }
+
+                    feature logging;
+
+                    container system {
+                        leaf log-level { if-feature logging; type string; }
+                        leaf ipv6 { if-feature "b:ipv6"; type string; }
+                        leaf no-ipv6 { if-feature "not b:ipv6"; type string; }
+                        container routing {
+                            if-feature "b:routing and (logging or m:logging)";
+                            leaf metric { type uint32; }
+                        }
+                        leaf broken { if-feature "b:missing or"; type string; }
+                        leaf unknown { if-feature "b:missing"; type string; }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let base = repository.load("base", None).unwrap();
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+
+        let mut features = FeatureSet::new();
+        features.enable(base, "ipv6");
+        features.enable(main, "logging");
+
+        let mut pruned = schema.clone();
+        let errors = prune(&repository, &mut pruned, &features);
+
+        assert_eq!(
+            dedent(
+                r#"
+                container system
+                  leaf log-level
+                  leaf no-ipv6
+                "#
+            ),
+            pruned.to_string()
+        );
+
+        let kinds: Vec<_> = errors.into_iter().map(|error| error.kind).collect();
+        assert_eq!(
+            vec![
+                FeatureErrorKind::InvalidExpression { offset: 12 },
+                FeatureErrorKind::UnknownFeature {
+                    name: "missing".to_string()
+                },
+            ],
+            kinds
+        );
+
+        let mut pruned = schema.clone();
+        prune(&repository, &mut pruned, &FeatureSet::all(&repository));
+
+        assert_eq!(
+            dedent(
+                r#"
+                container system
+                  leaf log-level
+                  leaf ipv6
+                  container routing
+                    leaf metric
+                "#
+            ),
+            pruned.to_string()
+        );
+    }
+}
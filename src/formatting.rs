@@ -1,6 +1,9 @@
 use std::fmt::{self, Display, Formatter};
 
-use crate::parsing::{Node, NodeValue, RootNode, StatementKeyword};
+use crate::parsing::{Node, NodeValue, RootNode, StatementKeyword, StringPart};
+
+/// Statements with free-form prose arguments, which are reflowed to fit the line width
+const PROSE_KEYWORDS: &[&str] = &["contact", "description", "organization", "reference"];
 
 impl Display for RootNode {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -51,6 +54,15 @@ fn print_node(out: &mut Formatter, node: &Node, depth: u8) -> Result<(), fmt::Er
         Node::CommentNode(_) => {
             write!(out, "(comment)")?;
         }
+        Node::ErrorNode(node) => {
+            write!(out, "(error")?;
+
+            for node in node.children.iter() {
+                print_node(out, node, depth + 1)?;
+            }
+
+            write!(out, ")")?;
+        }
     }
 
     Ok(())
@@ -82,3 +94,358 @@ impl Display for NodeValue {
         Ok(())
     }
 }
+
+/// Options for formatting a tree as YANG source
+pub struct FormatOptions {
+    /// Number of spaces per indentation level
+    pub indent: usize,
+
+    /// The line width to stay within where possible, used for placing and reflowing strings
+    pub max_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: 2,
+            max_width: 80,
+        }
+    }
+}
+
+/// Formats the tree as canonical YANG source
+///
+/// Every statement is put on its own line and indented according to its depth. Quoted strings are
+/// normalized to double quotes where that doesn't require escaping, and prose strings such as
+/// descriptions are reflowed to fit the line width. Everything at the top level is separated by
+/// blank lines, as are block statements directly inside the module.
+///
+/// Comments are preserved, but always put on their own line above the statement that follows
+/// them. Comments written inside a statement, such as between the keyword and the value, are put
+/// above that statement.
+///
+pub fn format_yang(root: &RootNode, options: &FormatOptions) -> String {
+    let mut output = String::new();
+
+    format_nodes(&mut output, &root.children, 0, options);
+
+    output
+}
+
+fn format_nodes(out: &mut String, nodes: &[Node], depth: usize, options: &FormatOptions) {
+    for (i, node) in nodes.iter().enumerate() {
+        if i > 0 && (depth == 0 || depth == 1 && needs_blank_line(&nodes[i - 1], &nodes[i..])) {
+            out.push('\n');
+        }
+
+        format_node(out, node, depth, options);
+    }
+}
+
+/// Returns true if a blank line should be put between the previous node and the rest
+///
+/// Comments stick to the statement following them, so there's never a blank line after a comment,
+/// and a blank line before a comment is decided by the statement it belongs to.
+///
+fn needs_blank_line(previous: &Node, rest: &[Node]) -> bool {
+    let next = rest
+        .iter()
+        .find(|node| !matches!(node, Node::CommentNode(_)));
+
+    match previous {
+        Node::CommentNode(_) => false,
+        Node::BlockNode(_) => true,
+        Node::LeafNode(_) | Node::ErrorNode(_) => matches!(next, Some(Node::BlockNode(_))),
+    }
+}
+
+fn format_node(out: &mut String, node: &Node, depth: usize, options: &FormatOptions) {
+    let indent = " ".repeat(depth * options.indent);
+
+    match node {
+        Node::CommentNode(node) => {
+            out.push_str(&indent);
+            out.push_str(&node.text);
+            out.push('\n');
+        }
+        Node::LeafNode(node) => {
+            format_header(out, &indent, &node.keyword, Some(&node.value), options);
+            out.push_str(";\n");
+        }
+        Node::BlockNode(node) => {
+            format_header(out, &indent, &node.keyword, node.value.as_ref(), options);
+            out.push_str(" {\n");
+            format_nodes(out, &node.children, depth + 1, options);
+            out.push_str(&indent);
+            out.push_str("}\n");
+        }
+        Node::ErrorNode(node) => {
+            // The broken statement itself can't be reproduced, but whatever was parsed inside it
+            // is kept
+            format_nodes(out, &node.children, depth, options);
+        }
+    }
+}
+
+/// Writes the keyword and value of a statement, without the terminating ";" or "{"
+fn format_header(
+    out: &mut String,
+    indent: &str,
+    keyword: &StatementKeyword,
+    value: Option<&NodeValue>,
+    options: &FormatOptions,
+) {
+    out.push_str(indent);
+    out.push_str(keyword.text());
+
+    let parts = match value {
+        Some(NodeValue::String(parts)) => parts,
+        Some(value) => {
+            out.push(' ');
+            out.push_str(&value.argument());
+            return;
+        }
+        None => return,
+    };
+
+    let inline_column = indent.len() + keyword.text().len() + 1;
+    let next_line_indent = format!("{}{}", indent, " ".repeat(options.indent));
+
+    if let [part] = parts.as_slice() {
+        let value = if PROSE_KEYWORDS.contains(&keyword.text()) {
+            let width = options.max_width.saturating_sub(next_line_indent.len() + 3);
+            reflow(&part.value, width.max(20))
+        } else {
+            part.value.clone()
+        };
+
+        let inline = quote(&value, inline_column);
+
+        // The +2 is for the terminating ";" or " {"
+        if !inline.contains('\n') && inline_column + inline.len() + 2 <= options.max_width {
+            out.push(' ');
+            out.push_str(&inline);
+        } else {
+            out.push('\n');
+            out.push_str(&next_line_indent);
+            out.push_str(&quote(&value, next_line_indent.len()));
+        }
+    } else {
+        format_concatenation(out, parts, inline_column, &next_line_indent);
+    }
+}
+
+/// Writes concatenated strings with the first part inline and the rest on their own lines
+fn format_concatenation(
+    out: &mut String,
+    parts: &[StringPart],
+    inline_column: usize,
+    next_line_indent: &str,
+) {
+    for (i, part) in parts.iter().enumerate() {
+        if i == 0 {
+            out.push(' ');
+            out.push_str(&quote(&part.value, inline_column));
+        } else {
+            out.push('\n');
+            out.push_str(next_line_indent);
+            out.push_str("+ ");
+            out.push_str(&quote(&part.value, next_line_indent.len() + 2));
+        }
+    }
+}
+
+/// Quotes a string value so that it decodes back to the same value
+///
+/// The column is the column of the opening quote, which continuation lines of double quoted
+/// strings are indented relative to. Double quotes are preferred, single quotes are used if that
+/// avoids escaping, and as a last resort the value is double quoted with escapes.
+///
+fn quote(value: &str, column: usize) -> String {
+    let has_trailing_whitespace = value
+        .split('\n')
+        .rev()
+        .skip(1)
+        .any(|line| line.ends_with([' ', '\t']));
+
+    if !value.contains(['"', '\\']) && !has_trailing_whitespace {
+        double_quote(value, column)
+    } else if !value.contains('\'') {
+        // Single quoted strings are taken literally, so continuation lines can't be indented
+        format!("'{}'", value)
+    } else {
+        // Trailing whitespace before a line break can't be represented in this case, but tabs
+        // can at least be escaped
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\t', "\\t");
+
+        double_quote(&escaped, column)
+    }
+}
+
+/// Double quotes an already escaped value, indenting continuation lines past the opening quote
+fn double_quote(value: &str, column: usize) -> String {
+    let continuation_indent = " ".repeat(column + 1);
+    let mut quoted = String::from("\"");
+
+    for (i, line) in value.split('\n').enumerate() {
+        if i > 0 {
+            quoted.push('\n');
+
+            if !line.is_empty() {
+                quoted.push_str(&continuation_indent);
+            }
+        }
+
+        quoted.push_str(line);
+    }
+
+    quoted.push('"');
+    quoted
+}
+
+/// Reflows prose text to fit within the given width
+///
+/// Paragraphs are separated by blank lines. Paragraphs that look preformatted, such as lists or
+/// indented blocks, are left as they are.
+///
+fn reflow(text: &str, width: usize) -> String {
+    let options = textwrap::Options::new(width).break_words(false);
+
+    text.split("\n\n")
+        .map(|paragraph| {
+            if paragraph.lines().any(is_preformatted_line) {
+                paragraph.to_string()
+            } else {
+                let words: Vec<&str> = paragraph.split_whitespace().collect();
+                textwrap::fill(&words.join(" "), &options)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn is_preformatted_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+
+    line.starts_with([' ', '\t'])
+        || trimmed.starts_with(['-', '*', '+', '|'])
+        || trimmed.split_once(['.', ')']).is_some_and(|(number, _)| {
+            !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parsing::parse;
+    use pretty_assertions::assert_eq;
+
+    fn dedent(text: &str) -> String {
+        let mut text = textwrap::dedent(text).trim().to_string();
+        text.push('\n');
+        text
+    }
+
+    #[test]
+    fn format() {
+        let buffer: Vec<u8> = dedent(
+            r#"
+            // Header comment
+            module test {
+              yang-version 1.1; namespace 'urn:test';
+                prefix t;
+              description 'A very long description that definitely does not fit on a single line of eighty characters, so it must be reflowed.
+
+                Preformatted paragraph
+                that is left alone';
+              // Comment about the container
+              container c {
+                leaf l { type string { pattern '[a-z]\d+' + "[0-9]"; } default 'it''s'; }
+              }
+              revision 2020-01-01 { description "Initial"; }
+            }
+            "#,
+        )
+        .replace("'it''s'", r#""it's \"quoted\"""#)
+        .bytes()
+        .collect();
+
+        let tree = parse(&buffer).expect("Failed to parse YANG");
+        let formatted = format_yang(&tree, &FormatOptions::default());
+
+        assert_eq!(
+            dedent(
+                r#"
+                // Header comment
+
+                module test {
+                  yang-version 1.1;
+                  namespace "urn:test";
+                  prefix t;
+                  description
+                    "A very long description that definitely does not fit on a single line of
+                     eighty characters, so it must be reflowed.
+
+                         Preformatted paragraph
+                         that is left alone";
+
+                  // Comment about the container
+                  container c {
+                    leaf l {
+                      type string {
+                        pattern '[a-z]\d+'
+                          + "[0-9]";
+                      }
+                      default "it's \"quoted\"";
+                    }
+                  }
+
+                  revision 2020-01-01 {
+                    description "Initial";
+                  }
+                }
+                "#
+            ),
+            formatted
+        );
+
+        // Formatting is idempotent
+        let tree = parse(formatted.as_bytes()).expect("Failed to parse formatted YANG");
+        assert_eq!(formatted, format_yang(&tree, &FormatOptions::default()));
+    }
+
+    #[test]
+    fn comments() {
+        let buffer = dedent(
+            r#"
+            module test { // after the brace
+              leaf /* keep me */ x { type string; }
+              description "a" // trailing
+                + "b" /* before the semicolon */;
+              /* last */
+            }
+            "#,
+        );
+
+        let tree = parse(buffer.as_bytes()).expect("Failed to parse YANG");
+        let formatted = format_yang(&tree, &FormatOptions::default());
+
+        let comments = [
+            "// after the brace",
+            "/* keep me */",
+            "// trailing",
+            "/* before the semicolon */",
+            "/* last */",
+        ];
+
+        for comment in comments {
+            assert!(formatted.contains(comment), "{:?} is missing", comment);
+        }
+
+        let tree = parse(formatted.as_bytes()).expect("Failed to parse formatted YANG");
+        assert_eq!(formatted, format_yang(&tree, &FormatOptions::default()));
+    }
+}
@@ -0,0 +1,901 @@
+//
+// Leafref paths
+//
+// The "path" of a leafref type is a restricted XPath expression (the "path-arg" rule of RFC 7950
+// section 14), such as "/interfaces/interface[name = current()/../ifname]/mtu". It's either an
+// absolute path from the root, or goes up a number of levels with ".." before going down again.
+// Predicates compare a key of a list with a leaf that's found relative to the leafref itself.
+//
+// Paths are resolved against the compiled schema, so that nodes added by augments and groupings
+// are found wherever they ended up. Following RFC 7950 section 6.4.1, unprefixed names belong to
+// the module of the leaf that has the leafref type, which for a grouping is the module that uses
+// it rather than the one that defines it.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexing::Span;
+use crate::prefixes::argument_span;
+use crate::repository::{ModuleId, ModuleRepository};
+use crate::schema::{resolve_prefix, Property, Schema, SchemaNode, SchemaNodeKind};
+use crate::types::{resolve_node_type, BuiltinType, ResolvedType};
+
+/// A possibly prefixed node name in a path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeName {
+    pub prefix: Option<String>,
+    pub name: String,
+
+    /// The byte offset of the name, including any prefix, within the argument
+    pub offset: usize,
+}
+
+/// A "path-predicate" such as "[name = current()/../ifname]"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPredicate {
+    /// The key leaf of the list
+    pub key: NodeName,
+
+    /// The number of ".." steps after "current()"
+    pub up: usize,
+
+    /// The names after the ".." steps, ending with the leaf the key is compared to
+    pub steps: Vec<NodeName>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathStep {
+    pub name: NodeName,
+    pub predicates: Vec<PathPredicate>,
+}
+
+/// A parsed "path-arg"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafrefPath {
+    pub absolute: bool,
+
+    /// The number of ".." steps at the start of a relative path
+    pub up: usize,
+    pub steps: Vec<PathStep>,
+}
+
+/// A leafref type whose path was resolved to a leaf in the schema
+#[derive(Debug, Clone)]
+pub struct Leafref {
+    /// The module and name of each node from the root to the leaf or leaf-list with the type,
+    /// including choices and cases, as taken by `Schema::node`
+    pub node: Vec<(ModuleId, String)>,
+
+    /// The "path" statement
+    pub path: Property,
+
+    /// The module and name of each node from the root to the target
+    pub target: Vec<(ModuleId, String)>,
+
+    /// The type of the target, which may be a leafref itself
+    pub target_type: Option<ResolvedType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LeafrefError {
+    pub kind: LeafrefErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LeafrefErrorKind {
+    /// A path that doesn't follow the "path-arg" syntax
+    InvalidPath {
+        /// The byte offset of the unexpected character, or the length of the argument if it
+        /// ended too early
+        offset: usize,
+    },
+    UnknownPrefix {
+        prefix: String,
+    },
+
+    /// A ".." step above the top level of the schema
+    AboveRoot,
+    TargetNotFound {
+        name: String,
+    },
+
+    /// A path that ends at a node other than a leaf or leaf-list, or a predicate that compares
+    /// a key with such a node
+    NotALeaf {
+        name: String,
+    },
+
+    /// A configuration leafref that refers to a state leaf, without "require-instance false"
+    ConfigMismatch {
+        name: String,
+    },
+}
+
+impl Display for LeafrefError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LeafrefErrorKind::InvalidPath { .. } => write!(f, "Invalid leafref path"),
+            LeafrefErrorKind::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            LeafrefErrorKind::AboveRoot => write!(f, "The path goes above the top level"),
+            LeafrefErrorKind::TargetNotFound { name } => write!(f, "Node {:?} not found", name),
+            LeafrefErrorKind::NotALeaf { name } => {
+                write!(f, "Node {:?} is not a leaf or leaf-list", name)
+            }
+            LeafrefErrorKind::ConfigMismatch { name } => write!(
+                f,
+                "A configuration leafref can't refer to the state leaf {:?}",
+                name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for LeafrefError {}
+
+/// Parses a leafref "path" argument
+pub fn parse_path(text: &str) -> Result<LeafrefPath, LeafrefErrorKind> {
+    let mut parser = Parser { text, position: 0 };
+    let path = parser.path()?;
+
+    match parser.position == text.len() {
+        true => Ok(path),
+        false => Err(parser.error()),
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self) -> LeafrefErrorKind {
+        LeafrefErrorKind::InvalidPath {
+            offset: self.position,
+        }
+    }
+
+    fn rest(&self) -> &str {
+        &self.text[self.position..]
+    }
+
+    /// Consumes the given text if the rest of the argument starts with it
+    fn eat(&mut self, expected: &str) -> bool {
+        let found = self.rest().starts_with(expected);
+
+        if found {
+            self.position += expected.len();
+        }
+
+        found
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), LeafrefErrorKind> {
+        match self.eat(expected) {
+            true => Ok(()),
+            false => Err(self.error()),
+        }
+    }
+
+    fn whitespace(&mut self) {
+        while self.eat(" ") || self.eat("\t") {}
+    }
+
+    fn identifier(&mut self) -> Result<&str, LeafrefErrorKind> {
+        let start = self.position;
+        let length = self
+            .rest()
+            .char_indices()
+            .find(|(i, c)| match i {
+                0 => !(c.is_ascii_alphabetic() || *c == '_'),
+                _ => !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')),
+            })
+            .map_or(self.rest().len(), |(i, _)| i);
+
+        match length {
+            0 => Err(self.error()),
+            _ => {
+                self.position += length;
+                Ok(&self.text[start..self.position])
+            }
+        }
+    }
+
+    fn node_name(&mut self) -> Result<NodeName, LeafrefErrorKind> {
+        let offset = self.position;
+        let first = self.identifier()?.to_string();
+
+        let (prefix, name) = match self.eat(":") {
+            true => (Some(first), self.identifier()?.to_string()),
+            false => (None, first),
+        };
+
+        Ok(NodeName {
+            prefix,
+            name,
+            offset,
+        })
+    }
+
+    fn path(&mut self) -> Result<LeafrefPath, LeafrefErrorKind> {
+        let mut up = 0;
+
+        while self.eat("../") {
+            up += 1;
+        }
+
+        let absolute = up == 0;
+        let mut steps = vec![];
+
+        if !absolute {
+            let name = self.node_name()?;
+            let predicates = self.predicates()?;
+
+            // A relative path can only have predicates on its first step if more steps follow
+            if !predicates.is_empty() && !self.rest().starts_with('/') {
+                return Err(self.error());
+            }
+
+            steps.push(PathStep { name, predicates });
+        }
+
+        if absolute && !self.rest().starts_with('/') {
+            return Err(self.error());
+        }
+
+        while self.eat("/") {
+            let name = self.node_name()?;
+            let predicates = self.predicates()?;
+            steps.push(PathStep { name, predicates });
+        }
+
+        Ok(LeafrefPath {
+            absolute,
+            up,
+            steps,
+        })
+    }
+
+    fn predicates(&mut self) -> Result<Vec<PathPredicate>, LeafrefErrorKind> {
+        let mut predicates = vec![];
+
+        while self.eat("[") {
+            self.whitespace();
+            let key = self.node_name()?;
+            self.whitespace();
+            self.expect("=")?;
+            self.whitespace();
+            self.expect("current")?;
+            self.whitespace();
+            self.expect("(")?;
+            self.whitespace();
+            self.expect(")")?;
+            self.whitespace();
+            self.expect("/")?;
+            self.whitespace();
+
+            let mut up = 0;
+
+            while self.eat("..") {
+                up += 1;
+                self.whitespace();
+                self.expect("/")?;
+                self.whitespace();
+            }
+
+            if up == 0 {
+                return Err(self.error());
+            }
+
+            let mut steps = vec![self.node_name()?];
+            self.whitespace();
+
+            while self.eat("/") {
+                self.whitespace();
+                steps.push(self.node_name()?);
+                self.whitespace();
+            }
+
+            self.expect("]")?;
+            predicates.push(PathPredicate { key, up, steps });
+        }
+
+        Ok(predicates)
+    }
+}
+
+/// Resolves the path of every leafref type in the schema, including the members of unions
+///
+/// Returns the leafrefs that could be resolved and the errors of those that couldn't.
+///
+pub fn resolve_leafrefs(
+    repository: &ModuleRepository,
+    schema: &Schema,
+) -> (Vec<Leafref>, Vec<LeafrefError>) {
+    let mut resolver = Resolver {
+        repository,
+        schema,
+        leafrefs: vec![],
+        errors: vec![],
+    };
+
+    let mut ancestors = vec![];
+    resolver.visit(&schema.nodes, &mut ancestors);
+
+    (resolver.leafrefs, resolver.errors)
+}
+
+struct Resolver<'r, 's> {
+    repository: &'r ModuleRepository,
+    schema: &'s Schema,
+    leafrefs: Vec<Leafref>,
+    errors: Vec<LeafrefError>,
+}
+
+impl<'s> Resolver<'_, 's> {
+    fn visit(&mut self, nodes: &'s [SchemaNode], ancestors: &mut Vec<&'s SchemaNode>) {
+        for node in nodes {
+            ancestors.push(node);
+
+            if matches!(node.kind, SchemaNodeKind::Leaf | SchemaNodeKind::LeafList) {
+                if let Some(resolved) = resolve_node_type(self.repository, node).0 {
+                    let mut paths = vec![];
+                    collect_paths(&resolved, &mut paths);
+
+                    for (path, require_instance) in paths {
+                        self.resolve(ancestors, path, require_instance);
+                    }
+                }
+            }
+
+            self.visit(&node.children, ancestors);
+            ancestors.pop();
+        }
+    }
+
+    fn resolve(&mut self, chain: &[&'s SchemaNode], path: &Property, require_instance: bool) {
+        let module = path.source.module;
+        let argument = path.argument().unwrap_or_default();
+
+        let error = |kind, offset, length| LeafrefError {
+            kind,
+            module,
+            span: argument_span(&path.node, offset, length),
+        };
+
+        let parsed = match parse_path(&argument) {
+            Ok(parsed) => parsed,
+            Err(kind) => {
+                let offset = match kind {
+                    LeafrefErrorKind::InvalidPath { offset } => offset,
+                    _ => 0,
+                };
+
+                return self.report(error(kind, offset, 1));
+            }
+        };
+
+        let leaf = chain[chain.len() - 1];
+        let navigator = Navigator {
+            repository: self.repository,
+            schema: self.schema,
+            leaf,
+            from: chain,
+            module,
+        };
+
+        let result = navigator
+            .start(parsed.absolute, parsed.up)
+            .and_then(|start| navigator.steps(start, &parsed.steps));
+
+        let target = match result {
+            Ok(target) => target,
+            Err((kind, name)) => {
+                let (offset, length) =
+                    name.map_or((0, argument.len()), |name| (name.offset, name_length(name)));
+
+                return self.report(error(kind, offset, length));
+            }
+        };
+
+        let last = parsed.steps.last().map(|step| &step.name);
+        let (offset, length) =
+            last.map_or((0, argument.len()), |last| (last.offset, name_length(last)));
+
+        let node = target[target.len() - 1];
+
+        if !matches!(node.kind, SchemaNodeKind::Leaf | SchemaNodeKind::LeafList) {
+            let name = node.name.clone();
+            return self.report(error(LeafrefErrorKind::NotALeaf { name }, offset, length));
+        }
+
+        if require_instance && is_config(chain) && !is_config(&target) {
+            let name = node.name.clone();
+            let kind = LeafrefErrorKind::ConfigMismatch { name };
+            return self.report(error(kind, offset, length));
+        }
+
+        self.leafrefs.push(Leafref {
+            node: names(chain),
+            path: path.clone(),
+            target: names(&target),
+            target_type: resolve_node_type(self.repository, node).0,
+        });
+    }
+
+    /// Adds an error, unless the same path gave the same error in another context
+    fn report(&mut self, error: LeafrefError) {
+        if !self.errors.contains(&error) {
+            self.errors.push(error);
+        }
+    }
+}
+
+/// The nodes from the root to the current position in the schema, including choices and cases
+type Chain<'s> = Vec<&'s SchemaNode>;
+
+/// An error, along with the name in the path that caused it
+type NavigationError<'p> = (LeafrefErrorKind, Option<&'p NodeName>);
+
+struct Navigator<'r, 's, 'c> {
+    repository: &'r ModuleRepository,
+    schema: &'s Schema,
+
+    /// The leaf with the leafref type, which is the current() node
+    leaf: &'s SchemaNode,
+
+    /// The chain of the leaf
+    from: &'c [&'s SchemaNode],
+
+    /// The module with the "path" statement, which its prefixes are resolved in
+    module: ModuleId,
+}
+
+impl<'s> Navigator<'_, 's, '_> {
+    fn start<'p>(&self, absolute: bool, up: usize) -> Result<Chain<'s>, NavigationError<'p>> {
+        match absolute {
+            true => Ok(vec![]),
+            false => self.up(self.from.to_vec(), up),
+        }
+    }
+
+    /// Goes up to the parent data node a number of times, skipping choices and cases
+    fn up<'p>(&self, mut chain: Chain<'s>, count: usize) -> Result<Chain<'s>, NavigationError<'p>> {
+        for _ in 0..count {
+            chain.pop().ok_or((LeafrefErrorKind::AboveRoot, None))?;
+
+            while chain.last().is_some_and(|node| {
+                matches!(node.kind, SchemaNodeKind::Choice | SchemaNodeKind::Case)
+            }) {
+                chain.pop();
+            }
+        }
+
+        Ok(chain)
+    }
+
+    /// Goes down to the child data node with the given name, looking through choices and cases
+    fn down<'p>(
+        &self,
+        mut chain: Chain<'s>,
+        name: &'p NodeName,
+    ) -> Result<Chain<'s>, NavigationError<'p>> {
+        let module = match &name.prefix {
+            Some(prefix) => resolve_prefix(self.repository, self.module, Some(prefix)),
+            None => Some(self.leaf.module),
+        };
+
+        let module = module.ok_or_else(|| {
+            let prefix = name.prefix.clone().unwrap_or_default();
+            (LeafrefErrorKind::UnknownPrefix { prefix }, Some(name))
+        })?;
+
+        let children = match chain.last() {
+            Some(node) => &node.children,
+            None => &self.schema.nodes,
+        };
+
+        match find_child(children, module, &name.name, &mut chain) {
+            true => Ok(chain),
+            false => {
+                let kind = LeafrefErrorKind::TargetNotFound {
+                    name: name.name.clone(),
+                };
+                Err((kind, Some(name)))
+            }
+        }
+    }
+
+    fn steps<'p>(
+        &self,
+        mut chain: Chain<'s>,
+        steps: &'p [PathStep],
+    ) -> Result<Chain<'s>, NavigationError<'p>> {
+        for step in steps {
+            chain = self.down(chain, &step.name)?;
+
+            for predicate in step.predicates.iter() {
+                self.predicate(&chain, predicate)?;
+            }
+        }
+
+        Ok(chain)
+    }
+
+    /// Checks that both sides of a predicate are leafs
+    fn predicate<'p>(
+        &self,
+        list: &Chain<'s>,
+        predicate: &'p PathPredicate,
+    ) -> Result<(), NavigationError<'p>> {
+        let key = self.down(list.clone(), &predicate.key)?;
+        self.check_leaf(&key, &predicate.key)?;
+
+        let mut chain = self.up(self.from.to_vec(), predicate.up)?;
+
+        for name in predicate.steps.iter() {
+            chain = self.down(chain, name)?;
+        }
+
+        match predicate.steps.last() {
+            Some(last) => self.check_leaf(&chain, last),
+            None => Ok(()),
+        }
+    }
+
+    fn check_leaf<'p>(
+        &self,
+        chain: &Chain<'s>,
+        name: &'p NodeName,
+    ) -> Result<(), NavigationError<'p>> {
+        match chain.last() {
+            Some(node) if matches!(node.kind, SchemaNodeKind::Leaf | SchemaNodeKind::LeafList) => {
+                Ok(())
+            }
+            _ => {
+                let kind = LeafrefErrorKind::NotALeaf {
+                    name: name.name.clone(),
+                };
+                Err((kind, Some(name)))
+            }
+        }
+    }
+}
+
+/// Finds a child data node, adding the choices and cases on the way and the node to the chain
+fn find_child<'s>(
+    children: &'s [SchemaNode],
+    module: ModuleId,
+    name: &str,
+    chain: &mut Chain<'s>,
+) -> bool {
+    for child in children {
+        match child.kind {
+            SchemaNodeKind::Choice | SchemaNodeKind::Case => {
+                chain.push(child);
+
+                if find_child(&child.children, module, name, chain) {
+                    return true;
+                }
+
+                chain.pop();
+            }
+            _ if child.module == module && child.name == name => {
+                chain.push(child);
+                return true;
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Collects the "path" statements of a leafref type or the leafref members of a union, along
+/// with their "require-instance" setting
+fn collect_paths<'t>(resolved: &'t ResolvedType, paths: &mut Vec<(&'t Property, bool)>) {
+    match (resolved.builtin, &resolved.path) {
+        (BuiltinType::Leafref, Some(path)) => {
+            paths.push((path, resolved.require_instance.unwrap_or(true)))
+        }
+        (BuiltinType::Union, _) => {
+            for member in resolved.members.iter() {
+                collect_paths(member, paths);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Returns true if a node is configuration, which nodes in operations and notifications never are
+fn is_config(chain: &[&SchemaNode]) -> bool {
+    chain.iter().all(|node| {
+        !matches!(
+            node.kind,
+            SchemaNodeKind::Rpc | SchemaNodeKind::Action | SchemaNodeKind::Notification
+        ) && node.argument("config").as_deref() != Some("false")
+    })
+}
+
+fn names(chain: &[&SchemaNode]) -> Vec<(ModuleId, String)> {
+    chain
+        .iter()
+        .map(|node| (node.module, node.name.clone()))
+        .collect()
+}
+
+fn name_length(name: &NodeName) -> usize {
+    name.prefix.as_ref().map_or(0, |prefix| prefix.len() + 1) + name.name.len()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        textwrap::dedent(text).trim_start().to_string()
+    }
+
+    #[test]
+    fn parsing() {
+        let name = |prefix: Option<&str>, name: &str, offset| NodeName {
+            prefix: prefix.map(str::to_string),
+            name: name.to_string(),
+            offset,
+        };
+
+        assert_eq!(
+            Ok(LeafrefPath {
+                absolute: true,
+                up: 0,
+                steps: vec![
+                    PathStep {
+                        name: name(Some("if"), "interfaces", 1),
+                        predicates: vec![],
+                    },
+                    PathStep {
+                        name: name(None, "interface", 15),
+                        predicates: vec![PathPredicate {
+                            key: name(None, "name", 26),
+                            up: 2,
+                            steps: vec![name(None, "config", 55), name(None, "ifname", 63)],
+                        }],
+                    },
+                    PathStep {
+                        name: name(None, "mtu", 71),
+                        predicates: vec![],
+                    },
+                ],
+            }),
+            parse_path(
+                "/if:interfaces/interface[ name = current ( ) / .. / ../config/ ifname]/mtu"
+            )
+        );
+
+        assert_eq!(
+            Ok(LeafrefPath {
+                absolute: false,
+                up: 2,
+                steps: vec![PathStep {
+                    name: name(None, "name", 6),
+                    predicates: vec![],
+                }],
+            }),
+            parse_path("../../name")
+        );
+
+        let errors = [
+            ("", 0),
+            ("/", 1),
+            ("../name[id = current()/../id]", 29),
+            ("/a[id = current()/id]", 18),
+            ("/a[id = ../id]", 8),
+            ("a/b", 0),
+            ("/a/", 3),
+            ("/a b", 2),
+        ];
+
+        for (text, offset) in errors {
+            assert_eq!(
+                Err(LeafrefErrorKind::InvalidPath { offset }),
+                parse_path(text),
+                "{}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn resolution() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "interfaces.yang",
+            dedent(
+                r#"
+                module interfaces {
+                    namespace "urn:interfaces";
+                    prefix if;
+
+                    typedef interface-ref {
+                        type leafref { path "/if:interfaces/if:interface/if:name"; }
+                    }
+
+                    container interfaces {
+                        list interface {
+                            key name;
+                            leaf name { type string; }
+                            leaf mtu { type uint16 { range "68..9000"; } }
+                            container statistics {
+                                config false;
+                                leaf packets { type uint64; }
+                            }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let source = dedent(
+            r#"
+                module routing {
+                    namespace "urn:routing";
+                    prefix rt;
+
+                    import interfaces { prefix if; }
+
+                    grouping next-hop {
+                        leaf ifname { type if:interface-ref; }
+                        leaf mtu {
+                            type leafref {
+                                path "/if:interfaces/if:interface[if:name = current()/../ifname]"
+                                   + "/if:mtu";
+                            }
+                        }
+                    }
+
+                    container routes {
+                        list route {
+                            key id;
+                            leaf id { type string; }
+                            choice target {
+                                case hop { uses next-hop; }
+                            }
+                            leaf previous { type union { type leafref { path "../id"; } type int8; } }
+                            leaf interfaces { type leafref { path "/if:interfaces"; } }
+                            leaf packets {
+                                type leafref { path "/if:interfaces/if:interface/if:statistics/if:packets"; }
+                            }
+                            leaf missing { type leafref { path "../../../routes/rt:unknown"; } }
+                            leaf above { type leafref { path "../../../../id"; } }
+                        }
+                    }
+
+                    augment "/if:interfaces/if:interface" {
+                        leaf default-route {
+                            type leafref {
+                                require-instance false;
+                                path "/routes/route/id";
+                            }
+                        }
+                        leaf sent { config false; type leafref { path "../if:statistics/if:packets"; } }
+                        leaf bad-prefix { type leafref { path "../x:name"; } }
+                    }
+                }
+                "#,
+        );
+
+        repository.add_source("routing.yang", source.clone());
+
+        let interfaces = repository.load("interfaces", None).unwrap();
+        repository.load("routing", None).unwrap();
+        let (schema, errors) = Schema::compile(&repository);
+        assert_eq!(
+            Vec::<String>::new(),
+            errors.iter().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+
+        let (leafrefs, errors) = resolve_leafrefs(&repository, &schema);
+
+        let path = |names: &[(ModuleId, String)]| {
+            names
+                .iter()
+                .map(|(module, name)| format!("/{}:{}", repository.get(*module).prefix, name))
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            vec![
+                (
+                    "/if:interfaces/if:interface/rt:default-route".to_string(),
+                    "/rt:routes/rt:route/rt:id".to_string(),
+                    Some(BuiltinType::String),
+                ),
+                (
+                    "/if:interfaces/if:interface/rt:sent".to_string(),
+                    "/if:interfaces/if:interface/if:statistics/if:packets".to_string(),
+                    Some(BuiltinType::Uint64),
+                ),
+                (
+                    "/rt:routes/rt:route/rt:target/rt:hop/rt:ifname".to_string(),
+                    "/if:interfaces/if:interface/if:name".to_string(),
+                    Some(BuiltinType::String),
+                ),
+                (
+                    "/rt:routes/rt:route/rt:target/rt:hop/rt:mtu".to_string(),
+                    "/if:interfaces/if:interface/if:mtu".to_string(),
+                    Some(BuiltinType::Uint16),
+                ),
+                (
+                    "/rt:routes/rt:route/rt:previous".to_string(),
+                    "/rt:routes/rt:route/rt:id".to_string(),
+                    Some(BuiltinType::String),
+                ),
+            ],
+            leafrefs
+                .iter()
+                .map(|leafref| (
+                    path(&leafref.node),
+                    path(&leafref.target),
+                    leafref.target_type.as_ref().map(|t| t.builtin)
+                ))
+                .collect::<Vec<_>>()
+        );
+
+        let mtu = &leafrefs[3];
+        let range = mtu.target_type.as_ref().unwrap().range.as_ref().unwrap();
+        assert_eq!(Some(false), range.contains_value("9001"));
+        assert_eq!(
+            vec![
+                (interfaces, "interfaces".to_string()),
+                (interfaces, "interface".to_string()),
+                (interfaces, "mtu".to_string()),
+            ],
+            mtu.target
+        );
+
+        let text = source.as_str();
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|error| (error.kind.clone(), &text[error.span.0..=error.span.1]))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    LeafrefErrorKind::UnknownPrefix {
+                        prefix: "x".to_string()
+                    },
+                    "x:name"
+                ),
+                (
+                    LeafrefErrorKind::NotALeaf {
+                        name: "interfaces".to_string()
+                    },
+                    "if:interfaces"
+                ),
+                (
+                    LeafrefErrorKind::ConfigMismatch {
+                        name: "packets".to_string()
+                    },
+                    "if:packets"
+                ),
+                (
+                    LeafrefErrorKind::TargetNotFound {
+                        name: "unknown".to_string()
+                    },
+                    "rt:unknown"
+                ),
+                (LeafrefErrorKind::AboveRoot, "../../../../id"),
+            ],
+            errors
+        );
+    }
+}
@@ -10,6 +10,7 @@
 // - OpenCurlyBrace
 // - ClosingCurlyBrace
 // - SemiColon
+// - Plus: A lone "+" used to concatenate quoted strings
 // - Other: Any other token, including keywords, numbers, booleans and unquoted strings
 //
 
@@ -17,13 +18,16 @@ use std::str;
 
 use regex::Regex;
 
+use crate::errors::{ParseError, ParseErrorKind};
+
 const TAB: u8 = 9;
 const NEWLINE: u8 = 10;
-const CARRIAGE_RETURN: u8 = 10;
+const CARRIAGE_RETURN: u8 = 13;
 const SPACE: u8 = 32;
 const DOUBLE_QUOTE: u8 = 34;
 const SINGLE_QUOTE: u8 = 39;
 const ASTERISK: u8 = 42;
+const PLUS: u8 = 43;
 const DASH: u8 = 45;
 const SLASH: u8 = 47;
 const SEMICOLON: u8 = 59;
@@ -45,15 +49,19 @@ pub enum TokenType {
     OpenCurlyBrace,
     ClosingCurlyBrace,
     SemiColon,
+    Plus,
     WhiteSpace,
     LineBreak,
     Other,
 }
 
+/// Inclusive start and end byte index of a piece of text in the input buffer
+pub type Span = (usize, usize);
+
 #[derive(Debug, PartialEq)]
 pub struct Token<'a> {
     pub token_type: TokenType,
-    pub span: (usize, usize),
+    pub span: Span,
     pub text: &'a str,
 }
 
@@ -87,13 +95,17 @@ impl DebugTokenExt for Vec<Token<'_>> {
 }
 
 /// 1-based cursor position in a text file
+///
+/// Lines may be terminated by LF, CRLF or a lone CR.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct TextPosition {
-    line: usize,
-    col: usize,
+    pub line: usize,
+    pub col: usize,
 }
 
 impl TextPosition {
-    fn from_buffer_index(buffer: &[u8], index: usize) -> Self {
+    pub fn from_buffer_index(buffer: &[u8], index: usize) -> Self {
         let mut line = 1;
         let mut col = 1;
 
@@ -102,7 +114,14 @@ impl TextPosition {
                 break;
             }
 
-            if *c == NEWLINE {
+            let is_line_break = match *c {
+                NEWLINE => true,
+                // The LF of a CRLF does the counting
+                CARRIAGE_RETURN => buffer.get(i + 1) != Some(&NEWLINE),
+                _ => false,
+            };
+
+            if is_line_break {
                 line += 1;
                 col = 1;
             } else {
@@ -120,26 +139,36 @@ impl core::fmt::Display for TextPosition {
     }
 }
 
+/// Iterator over the tokens in a buffer
+///
+/// Yields an error if the lexer runs into invalid input, such as an unterminated string, after
+/// which the iterator is exhausted.
+///
 pub struct ScanIterator<'a> {
     buffer: &'a [u8],
     cursor: usize,
 }
 
 impl<'a> Iterator for ScanIterator<'a> {
-    type Item = Token<'a>;
+    type Item = Result<Token<'a>, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        match next_token(self.buffer, self.cursor).expect("Parse error") {
-            Some((next_cursor, token)) => {
+        match next_token(self.buffer, self.cursor) {
+            Ok(Some((next_cursor, token))) => {
                 self.cursor = next_cursor;
-                Some(token)
+                Some(Ok(token))
+            }
+            Ok(None) => None,
+            Err(error) => {
+                // There's no sensible way to continue lexing after an error, so skip to the end
+                self.cursor = self.buffer.len();
+                Some(Err(error))
             }
-            None => None,
         }
     }
 }
 
-pub fn scan(buffer: &[u8]) -> ScanIterator {
+pub fn scan(buffer: &[u8]) -> ScanIterator<'_> {
     ScanIterator { buffer, cursor: 0 }
 }
 
@@ -150,7 +179,7 @@ pub fn scan(buffer: &[u8]) -> ScanIterator {
 ///
 /// Returns an error on lexer errors such as unterminated strings or comments.
 ///
-fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token)>, String> {
+fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token<'_>)>, ParseError> {
     let char = match buffer.get(cursor) {
         Some(char) => char,
         None => return Ok(None),
@@ -158,8 +187,13 @@ fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token)>, St
 
     macro_rules! get_str {
         ($length:expr) => {
-            str::from_utf8(buffer.get(cursor..cursor + $length).unwrap())
-                .map_err(|err| format!("{}", err))?
+            str::from_utf8(buffer.get(cursor..cursor + $length).unwrap()).map_err(|_| {
+                ParseError::new(
+                    ParseErrorKind::InvalidUtf8,
+                    (cursor, cursor + $length - 1),
+                    buffer,
+                )
+            })?
         };
     }
 
@@ -185,6 +219,8 @@ fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token)>, St
         read_token!(TokenType::WhiteSpace, whitespace_length)
     } else if let Some(line_break_length) = scan_line_break(buffer, cursor) {
         read_token!(TokenType::LineBreak, line_break_length)
+    } else if scan_plus(buffer, cursor) {
+        read_token!(TokenType::Plus, 1)
     } else if let Some(string_length) = scan_string(buffer, cursor)? {
         read_token!(TokenType::String, string_length)
     } else if let Some(comment_length) = scan_comment(buffer, cursor) {
@@ -202,9 +238,13 @@ fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token)>, St
             read_token!(TokenType::Other, token_length)
         }
     } else {
-        Err(format!(
-            "Unexpected character at position {}: {:?}",
-            cursor, *char as char,
+        Err(ParseError::new(
+            ParseErrorKind::UnexpectedToken {
+                text: (*char as char).to_string(),
+                expected: "a token",
+            },
+            (cursor, cursor),
+            buffer,
         ))
     }
 }
@@ -214,47 +254,55 @@ fn next_token(buffer: &[u8], cursor: usize) -> Result<Option<(usize, Token)>, St
 /// Returns Ok(Some(string_length)) if there is a string at the current position, Ok(None) if
 /// there isn't. Returns an error if the string is never terminated.
 ///
-fn scan_string(buffer: &[u8], cursor: usize) -> Result<Option<usize>, String> {
+fn scan_string(buffer: &[u8], cursor: usize) -> Result<Option<usize>, ParseError> {
     let quote_char = match buffer[cursor] {
         DOUBLE_QUOTE => DOUBLE_QUOTE,
         SINGLE_QUOTE => SINGLE_QUOTE,
         _ => return Ok(None), // This position doesn't start a string, exit early
     };
 
-    let mut prev_char: Option<&u8> = None;
-
     let mut i = cursor + 1;
 
     loop {
-        if let Some(char) = buffer.get(i) {
-            let prev_char_is_backslash = match prev_char {
-                Some(x) => *x == BACKSLASH,
-                None => false,
-            };
-
+        match buffer.get(i) {
             // If the string is closed, we're done!
-            if *char == quote_char && !prev_char_is_backslash {
-                return Ok(Some(i + 1 - cursor));
-            }
+            Some(char) if *char == quote_char => return Ok(Some(i + 1 - cursor)),
 
-            prev_char = Some(char);
-        } else {
-            return Err(format!(
-                "Unexpected end of input, string started at {} was never terminated",
-                TextPosition::from_buffer_index(buffer, cursor),
-            ));
-        }
+            // Escape sequences are only a thing in double quoted strings, single quoted strings
+            // can't contain a single quote at all
+            Some(&BACKSLASH) if quote_char == DOUBLE_QUOTE => i += 2,
 
-        i += 1;
+            Some(_) => i += 1,
+
+            None => {
+                return Err(ParseError::new(
+                    ParseErrorKind::UnterminatedString,
+                    (cursor, buffer.len() - 1),
+                    buffer,
+                ))
+            }
+        }
     }
 }
 
+/// Checks if there is a string concatenation operator at the current position
+///
+/// A "+" only counts as an operator if it stands on its own, for example `"foo" + "bar"` or
+/// `"foo"+"bar"`. Otherwise it's just part of an unquoted string, such as `+1`.
+///
+fn scan_plus(buffer: &[u8], cursor: usize) -> bool {
+    buffer[cursor] == PLUS
+        && buffer
+            .get(cursor + 1)
+            .is_none_or(|c| is_delimiter(c) || [DOUBLE_QUOTE, SINGLE_QUOTE].contains(c))
+}
+
 /// Checks if there is a single-line comment at the current position
 fn scan_comment(buffer: &[u8], cursor: usize) -> Option<usize> {
     let is_forward_slash = |c: &u8| *c == SLASH;
 
-    if !(buffer.get(cursor).map_or(false, is_forward_slash)
-        && buffer.get(cursor + 1).map_or(false, is_forward_slash))
+    if !(buffer.get(cursor).is_some_and(is_forward_slash)
+        && buffer.get(cursor + 1).is_some_and(is_forward_slash))
     {
         return None;
     }
@@ -274,9 +322,9 @@ fn scan_comment(buffer: &[u8], cursor: usize) -> Option<usize> {
 }
 
 /// Checks if there is a block comment at the current position
-fn scan_block_comment(buffer: &[u8], cursor: usize) -> Result<Option<usize>, String> {
-    if !(buffer.get(cursor).map_or(false, |c| *c == SLASH)
-        && buffer.get(cursor + 1).map_or(false, |c| *c == ASTERISK))
+fn scan_block_comment(buffer: &[u8], cursor: usize) -> Result<Option<usize>, ParseError> {
+    if !(buffer.get(cursor).is_some_and(|c| *c == SLASH)
+        && buffer.get(cursor + 1).is_some_and(|c| *c == ASTERISK))
     {
         return Ok(None);
     }
@@ -285,14 +333,15 @@ fn scan_block_comment(buffer: &[u8], cursor: usize) -> Result<Option<usize>, Str
 
     for i in cursor + 2.. {
         if i == buffer.len() {
-            return Err(format!(
-                "Unexpected end of input, block comment started at {} was never terminated",
-                TextPosition::from_buffer_index(buffer, cursor)
+            return Err(ParseError::new(
+                ParseErrorKind::UnterminatedBlockComment,
+                (cursor, buffer.len() - 1),
+                buffer,
             ));
         }
 
-        if buffer.get(i).map_or(false, |c| *c == ASTERISK)
-            && buffer.get(i + 1).map_or(false, |c| *c == SLASH)
+        if buffer.get(i).is_some_and(|c| *c == ASTERISK)
+            && buffer.get(i + 1).is_some_and(|c| *c == SLASH)
         {
             break;
         }
@@ -308,7 +357,7 @@ fn scan_whitespace(buffer: &[u8], cursor: usize) -> Option<usize> {
     for i in cursor.. {
         if buffer
             .get(i)
-            .map_or(false, |char| [SPACE, TAB].contains(char))
+            .is_some_and(|char| [SPACE, TAB].contains(char))
         {
             continue;
         } else {
@@ -323,14 +372,15 @@ fn scan_whitespace(buffer: &[u8], cursor: usize) -> Option<usize> {
 
 /// Checks if there is a line break at this position
 fn scan_line_break(buffer: &[u8], cursor: usize) -> Option<usize> {
-    if buffer.get(cursor).map_or(false, |c| *c == b'\n') {
-        Some(1)
-    } else if buffer.get(cursor).map_or(false, |c| *c == b'\r')
-        && buffer.get(cursor).map_or(false, |c| *c == b'\n')
-    {
-        Some(2)
-    } else {
-        None
+    match buffer.get(cursor) {
+        Some(&NEWLINE) => Some(1),
+        Some(&CARRIAGE_RETURN) if buffer.get(cursor + 1) == Some(&NEWLINE) => Some(2),
+
+        // A lone carriage return isn't a line break according to the ABNF, but files with old Mac
+        // line endings still exist, and it's better to treat it as one than as part of a token
+        Some(&CARRIAGE_RETURN) => Some(1),
+
+        _ => None,
     }
 }
 
@@ -450,7 +500,9 @@ mod test {
         .bytes()
         .collect();
 
-        let tokens: Vec<_> = scan(&buffer).collect();
+        let tokens: Vec<_> = scan(&buffer)
+            .collect::<Result<_, _>>()
+            .expect("Failed to lex YANG");
 
         assert_eq!(
             dedent(
@@ -517,4 +569,130 @@ mod test {
             tokens.human_readable_string(),
         );
     }
+
+    #[test]
+    fn errors() {
+        let error = next_token(b"\"foo", 0).unwrap_err();
+        assert_eq!(ParseErrorKind::UnterminatedString, error.kind);
+        assert_eq!((0, 3), error.span);
+
+        let error = next_token(b"foo\n  /* bar", 6).unwrap_err();
+        assert_eq!(ParseErrorKind::UnterminatedBlockComment, error.kind);
+        assert_eq!((6, 11), error.span);
+        assert_eq!(TextPosition { line: 2, col: 3 }, error.position);
+
+        let error = next_token(b"foo\xff;", 0).unwrap_err();
+        assert_eq!(ParseErrorKind::InvalidUtf8, error.kind);
+        assert_eq!((0, 3), error.span);
+
+        // The scan iterator yields the error and then stops
+        let mut tokens = scan(b"foo \"bar");
+        assert_eq!(TokenType::Other, tokens.next().unwrap().unwrap().token_type);
+        assert_eq!(
+            TokenType::WhiteSpace,
+            tokens.next().unwrap().unwrap().token_type
+        );
+        assert_eq!(
+            ParseErrorKind::UnterminatedString,
+            tokens.next().unwrap().unwrap_err().kind
+        );
+        assert!(tokens.next().is_none());
+    }
+
+    #[test]
+    fn plus() {
+        let buffer = b"\"a\"+\"b\" + 'c' +1;";
+        let tokens: Vec<_> = scan(buffer).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            dedent(
+                r#"
+                String               0 -> 2          "\"a\""
+                Plus                 3 -> 3          "+"
+                String               4 -> 6          "\"b\""
+                WhiteSpace           7 -> 7          " "
+                Plus                 8 -> 8          "+"
+                WhiteSpace           9 -> 9          " "
+                String               10 -> 12        "'c'"
+                WhiteSpace           13 -> 13        " "
+                Other                14 -> 15        "+1"
+                SemiColon            16 -> 16        ";"
+                "#
+            ),
+            tokens.human_readable_string(),
+        );
+    }
+
+    #[test]
+    fn string_escapes() {
+        // An escaped backslash doesn't escape the closing quote, and single quoted strings don't
+        // have escapes at all
+        let tokens: Vec<_> = scan(br#""a\\" "b\"c" 'd\'"#)
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        assert_eq!(
+            vec![r#""a\\""#, " ", r#""b\"c""#, " ", r#"'d\'"#],
+            tokens.iter().map(|token| token.text).collect::<Vec<_>>(),
+        );
+    }
+
+    #[test]
+    fn line_endings() {
+        let buffer = b"module a {\r\n  // CRLF\r\n  prefix a;\r  // CR\r  yang-version 1.1;\n}";
+        let tokens: Vec<_> = scan(buffer).collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(
+            dedent(
+                r#"
+                Other                0 -> 5          "module"
+                WhiteSpace           6 -> 6          " "
+                Other                7 -> 7          "a"
+                WhiteSpace           8 -> 8          " "
+                OpenCurlyBrace       9 -> 9          "{"
+                LineBreak            10 -> 11        "\r\n"
+                WhiteSpace           12 -> 13        "  "
+                Comment              14 -> 20        "// CRLF"
+                LineBreak            21 -> 22        "\r\n"
+                WhiteSpace           23 -> 24        "  "
+                Other                25 -> 30        "prefix"
+                WhiteSpace           31 -> 31        " "
+                Other                32 -> 32        "a"
+                SemiColon            33 -> 33        ";"
+                LineBreak            34 -> 34        "\r"
+                WhiteSpace           35 -> 36        "  "
+                Comment              37 -> 41        "// CR"
+                LineBreak            42 -> 42        "\r"
+                WhiteSpace           43 -> 44        "  "
+                Other                45 -> 56        "yang-version"
+                WhiteSpace           57 -> 57        " "
+                Number               58 -> 60        "1.1"
+                SemiColon            61 -> 61        ";"
+                LineBreak            62 -> 62        "\n"
+                ClosingCurlyBrace    63 -> 63        "}"
+                "#
+            ),
+            tokens.human_readable_string(),
+        );
+
+        let position = |index| {
+            let position = TextPosition::from_buffer_index(buffer, index);
+            (position.line, position.col)
+        };
+
+        assert_eq!((1, 11), position(10));
+        assert_eq!((1, 12), position(11));
+        assert_eq!((2, 1), position(12));
+        assert_eq!((3, 3), position(25));
+        assert_eq!((4, 3), position(37));
+        assert_eq!((5, 3), position(45));
+        assert_eq!((6, 1), position(63));
+
+        // A carriage return ends an unquoted string
+        let tokens: Vec<_> = scan(b"a\r\nb\rc").collect::<Result<_, _>>().unwrap();
+        assert_eq!(
+            vec!["a", "\r\n", "b", "\r", "c"],
+            tokens.iter().map(|token| token.text).collect::<Vec<_>>()
+        );
+    }
 }
@@ -1,7 +1,22 @@
 #[macro_use]
 extern crate lazy_static;
 
+pub mod arguments;
+pub mod ast;
 pub mod constants;
+pub mod cst;
+pub mod errors;
+pub mod evaluation;
+pub mod features;
 pub mod formatting;
+pub mod leafref;
 pub mod lexing;
 pub mod parsing;
+pub mod patterns;
+pub mod prefixes;
+pub mod ranges;
+pub mod repository;
+pub mod schema;
+pub mod types;
+pub mod validation;
+pub mod xpath;
@@ -1,53 +1,91 @@
+use std::borrow::Cow;
+use std::str;
+
 use regex::Regex;
 
 use crate::constants::STATEMENT_KEYWORDS;
-use crate::lexing::{Token, TokenType};
+use crate::errors::{ParseError, ParseErrorKind};
+use crate::lexing::{ScanIterator, Span, Token, TokenType};
 
 lazy_static! {
     /// See "identifier" from ABNF
-    static ref IDENTIFIER_PATTERN: Regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9\-_.]*$").unwrap();
+    pub(crate) static ref IDENTIFIER_PATTERN: Regex = Regex::new(r"^[a-zA-Z_][a-zA-Z0-9\-_.]*$").unwrap();
 
     /// identifier ":" identifier - See "unknown-statement" from ABNF
     static ref EXT_KEYWORD_PATTERN: Regex =
         Regex::new(r"^[a-zA-Z_][a-zA-Z0-9\-_.]*:[a-zA-Z_][a-zA-Z0-9\-_.]*$").unwrap();
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum StatementKeyword {
     Keyword(String),
     ExtensionKeyword(String),
     Invalid(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum Node {
     BlockNode(BlockNode),
     LeafNode(LeafNode),
-    CommentNode(String),
+    CommentNode(CommentNode),
+    ErrorNode(ErrorNode),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct RootNode {
     pub children: Vec<Node>,
 }
 
-#[derive(Debug)]
+/// A statement with a block of sub-statements
+///
+/// All spans are inclusive byte ranges into the parsed buffer, the same as token spans. The node
+/// span covers the whole statement, from the start of the keyword to the closing curly brace.
+///
+#[derive(Debug, Clone)]
 pub struct BlockNode {
     pub keyword: StatementKeyword,
     pub value: Option<NodeValue>,
     pub children: Vec<Node>,
+    pub span: Span,
+    pub keyword_span: Span,
+    pub value_span: Option<Span>,
 }
 
-#[derive(Debug)]
+/// A statement terminated by a semicolon
+///
+/// The node span covers the whole statement, from the start of the keyword to the semicolon.
+///
+#[derive(Debug, Clone)]
 pub struct LeafNode {
     pub keyword: StatementKeyword,
     pub value: NodeValue,
+    pub span: Span,
+    pub keyword_span: Span,
+    pub value_span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct CommentNode {
+    pub text: String,
+    pub span: Span,
+}
+
+/// A statement that couldn't be parsed, only produced by `parse_with_recovery`
+///
+/// The span covers the tokens that were skipped. If the broken statement has a block, the block is
+/// parsed as normal and its statements end up as the children of the error node.
+///
+#[derive(Debug, Clone)]
+pub struct ErrorNode {
+    pub span: Span,
+    pub children: Vec<Node>,
 }
 
 /// The value of a node, currently simply represented as a
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum NodeValue {
-    String(String),
+    /// One or more quoted strings, more than one if they are concatenated with "+"
+    String(Vec<StringPart>),
     Number(String),
     Date(String),
 
@@ -58,10 +96,261 @@ pub enum NodeValue {
     Other(String),
 }
 
+/// A single quoted string in a string value
+///
+/// The text is the string exactly as it appears in the source, including the quotes, while the
+/// value is the decoded string as described by RFC 7950 section 6.1.3.
+///
+#[derive(Debug, Clone)]
+pub struct StringPart {
+    pub text: String,
+    pub value: String,
+    pub span: Span,
+}
+
+impl NodeValue {
+    fn from_token(token: &Token, buffer: &[u8]) -> Self {
+        match token.token_type {
+            TokenType::String => Self::String(vec![StringPart::from_token(token, buffer)]),
+            TokenType::Number => Self::Number(token.text.to_string()),
+            TokenType::Date => Self::Date(token.text.to_string()),
+            _ => Self::Other(token.text.to_string()),
+        }
+    }
+
+    /// Returns the argument of the statement
+    ///
+    /// Quoted strings are decoded, and concatenated strings are joined together.
+    ///
+    pub fn argument(&self) -> Cow<'_, str> {
+        match self {
+            NodeValue::String(parts) => match parts.as_slice() {
+                [part] => Cow::Borrowed(&part.value),
+                parts => Cow::Owned(parts.iter().map(|part| part.value.as_str()).collect()),
+            },
+            NodeValue::Number(text) | NodeValue::Date(text) | NodeValue::Other(text) => {
+                Cow::Borrowed(text)
+            }
+        }
+    }
+}
+
+impl StringPart {
+    fn from_token(token: &Token, buffer: &[u8]) -> Self {
+        let line_start = buffer[..token.span.0]
+            .iter()
+            .rposition(|c| *c == b'\n' || *c == b'\r')
+            .map_or(0, |i| i + 1);
+
+        let column = str::from_utf8(&buffer[line_start..token.span.0])
+            .map_or(0, |text| text.chars().map(char_width).sum());
+
+        Self {
+            text: token.text.to_string(),
+            value: decode_quoted_string(token.text, column),
+            span: token.span,
+        }
+    }
+
+    /// Returns the text of the string without the surrounding quotes
+    pub fn unquoted(&self) -> &str {
+        &self.text[1..self.text.len() - 1]
+    }
+}
+
+/// Decodes a quoted string, as described by RFC 7950 section 6.1.3
+///
+/// Single quoted strings are taken literally. In double quoted strings, whitespace at the end of
+/// each line is removed, continuation lines are unindented up to and including the column of the
+/// opening quote, and backslash escapes are processed.
+///
+/// The column is the 0-based column of the opening quote, where tabs count as 8 columns.
+///
+/// Unknown escape sequences, such as the common "\d" in YANG 1.0 patterns, are left as they are.
+///
+pub fn decode_quoted_string(text: &str, column: usize) -> String {
+    let content = &text[1..text.len() - 1];
+
+    if text.starts_with('\'') {
+        return content.to_string();
+    }
+
+    // Line breaks may be "\r\n", a lone "\r" or "\n", the same as in the lexer
+    let lines: Vec<&str> = content
+        .split("\r\n")
+        .flat_map(|line| line.split(['\r', '\n']))
+        .collect();
+    let mut value = String::with_capacity(content.len());
+
+    for (i, line) in lines.iter().enumerate() {
+        let mut line = *line;
+
+        if i > 0 {
+            value.push('\n');
+            line = strip_indentation(line, column + 1, &mut value);
+        }
+
+        if i < lines.len() - 1 {
+            line = line.trim_end_matches([' ', '\t']);
+        }
+
+        unescape(line, &mut value);
+    }
+
+    value
+}
+
+/// Strips up to the given number of columns of leading whitespace from a line
+///
+/// If a tab straddles the limit, the remainder of the tab is written to the output as spaces.
+///
+fn strip_indentation<'a>(line: &'a str, columns: usize, output: &mut String) -> &'a str {
+    let mut stripped = 0;
+
+    for (i, c) in line.char_indices() {
+        if stripped >= columns || !(c == ' ' || c == '\t') {
+            return &line[i..];
+        }
+
+        stripped += char_width(c);
+
+        if stripped > columns {
+            output.extend(std::iter::repeat_n(' ', stripped - columns));
+        }
+    }
+
+    ""
+}
+
+/// Processes the escape sequences of a double quoted string
+fn unescape(text: &str, output: &mut String) {
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('"') => output.push('"'),
+            Some('\\') => output.push('\\'),
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            }
+            None => output.push('\\'),
+        }
+    }
+}
+
+/// The width of a character when computing the indentation of a string, see RFC 7950 section 6.1.3
+fn char_width(c: char) -> usize {
+    if c == '\t' {
+        8
+    } else {
+        1
+    }
+}
+
 enum ParseState {
     Clean,
-    GotKeyword(StatementKeyword),
-    GotValue(StatementKeyword, NodeValue),
+    GotKeyword {
+        keyword: StatementKeyword,
+        keyword_span: Span,
+    },
+    GotValue {
+        keyword: StatementKeyword,
+        keyword_span: Span,
+        value: NodeValue,
+        value_span: Span,
+    },
+    GotPlus {
+        keyword: StatementKeyword,
+        keyword_span: Span,
+        parts: Vec<StringPart>,
+        value_span: Span,
+    },
+}
+
+impl Node {
+    /// Returns the span of the whole node
+    pub fn span(&self) -> Span {
+        match self {
+            Node::BlockNode(node) => node.span,
+            Node::LeafNode(node) => node.span,
+            Node::CommentNode(node) => node.span,
+            Node::ErrorNode(node) => node.span,
+        }
+    }
+
+    /// Returns the keyword of the statement, or None for comments and errors
+    pub fn keyword(&self) -> Option<&StatementKeyword> {
+        match self {
+            Node::BlockNode(node) => Some(&node.keyword),
+            Node::LeafNode(node) => Some(&node.keyword),
+            Node::CommentNode(_) | Node::ErrorNode(_) => None,
+        }
+    }
+
+    /// Returns the span of the keyword of the statement, or None for comments and errors
+    pub fn keyword_span(&self) -> Option<Span> {
+        match self {
+            Node::BlockNode(node) => Some(node.keyword_span),
+            Node::LeafNode(node) => Some(node.keyword_span),
+            Node::CommentNode(_) | Node::ErrorNode(_) => None,
+        }
+    }
+
+    /// Returns true if this is a statement with the given keyword
+    pub fn is(&self, keyword: &str) -> bool {
+        self.keyword().is_some_and(|k| k.text() == keyword)
+    }
+
+    /// Returns the value of the statement, if it has one
+    pub fn value(&self) -> Option<&NodeValue> {
+        match self {
+            Node::BlockNode(node) => node.value.as_ref(),
+            Node::LeafNode(node) => Some(&node.value),
+            Node::CommentNode(_) | Node::ErrorNode(_) => None,
+        }
+    }
+
+    /// Returns the span of the value of the statement, if it has one
+    pub fn value_span(&self) -> Option<Span> {
+        match self {
+            Node::BlockNode(node) => node.value_span,
+            Node::LeafNode(node) => Some(node.value_span),
+            Node::CommentNode(_) | Node::ErrorNode(_) => None,
+        }
+    }
+
+    /// Returns the argument of the statement, if it has one, see `NodeValue::argument`
+    pub fn argument(&self) -> Option<Cow<'_, str>> {
+        self.value().map(NodeValue::argument)
+    }
+
+    /// Returns the sub-statements and comments of a block, or nothing for other nodes
+    pub fn children(&self) -> &[Node] {
+        match self {
+            Node::BlockNode(node) => &node.children,
+            Node::ErrorNode(node) => &node.children,
+            Node::LeafNode(_) | Node::CommentNode(_) => &[],
+        }
+    }
+}
+
+impl StatementKeyword {
+    /// Returns the keyword as written in the source
+    pub fn text(&self) -> &str {
+        match self {
+            StatementKeyword::Keyword(text)
+            | StatementKeyword::ExtensionKeyword(text)
+            | StatementKeyword::Invalid(text) => text,
+        }
+    }
 }
 
 impl From<&Token<'_>> for StatementKeyword {
@@ -84,137 +373,483 @@ impl From<Token<'_>> for StatementKeyword {
     }
 }
 
-impl From<&Token<'_>> for NodeValue {
-    fn from(token: &Token) -> Self {
-        match token.token_type {
-            TokenType::String => Self::String(token.text.to_string()),
-            TokenType::Number => Self::Number(token.text.to_string()),
-            TokenType::Date => Self::Date(token.text.to_string()),
-            _ => Self::Other(token.text.to_string()),
-        }
-    }
-}
-
-impl From<Token<'_>> for NodeValue {
-    fn from(token: Token) -> Self {
-        (&token).into()
-    }
-}
-
 /// Parses the input bytes as a YANG documents and returns a syntax tree
 ///
 /// The returned node is a virtual "root" block node. This node contains the actual module or
 /// sub-module node as one of its children, as well as any comments that are above or below that
-/// node.
+/// node. Comments within a statement, such as between the keyword and the value, are put in front
+/// of the statement as siblings.
 ///
 /// This parser doesn't strictly enforce the official grammar, and the returned tree may well be
 /// invalid YANG. For example, this function will parse a document with multiple module blocks just
-/// fine, or no module node at all, just a bunch of leafs.
+/// fine, or no module node at all, just a bunch of leafs. Use `validation::validate` to check the
+/// tree against the grammar.
 ///
-pub fn parse(buffer: &[u8]) -> Result<RootNode, String> {
-    let mut tokens = crate::lexing::scan(buffer);
+pub fn parse(buffer: &[u8]) -> Result<RootNode, ParseError> {
+    let mut parser = Parser::new(buffer, false);
 
-    Ok(RootNode {
-        children: parse_statements(&mut tokens)?,
-    })
+    let (children, _) = parser.parse_statements(None)?;
+
+    Ok(RootNode { children })
+}
+
+/// Parses the input bytes as a YANG document, recovering from errors
+///
+/// Rather than stopping at the first error, the parser skips ahead to the end of the broken
+/// statement, meaning the next ";" or "}", and carries on from there. Broken statements are
+/// inserted into the tree as error nodes. A statement that is only missing its terminating ";" is
+/// kept as a regular leaf node.
+///
+/// Returns the full tree along with every error encountered, which is useful for editor tooling
+/// where files are often half-typed.
+///
+pub fn parse_with_recovery(buffer: &[u8]) -> (RootNode, Vec<ParseError>) {
+    let mut parser = Parser::new(buffer, true);
+
+    let (children, _) = parser
+        .parse_statements(None)
+        .expect("Errors are collected rather than returned when recovering");
+
+    (RootNode { children }, parser.errors)
 }
 
-fn parse_statements(tokens: &mut crate::lexing::ScanIterator) -> Result<Vec<Node>, String> {
-    let mut statements: Vec<Node> = vec![];
-    let mut state = ParseState::Clean;
-
-    loop {
-        match tokens.next() {
-            Some(token) => {
-                match state {
-                    ParseState::Clean => {
-                        // From a clean state, we expect to find a statement keyword, a comment or
-                        // a closing curly brace
-                        match token.token_type {
-                            TokenType::WhiteSpace => continue,
-                            TokenType::LineBreak => continue,
-                            TokenType::Comment => {
-                                statements.push(Node::CommentNode(token.text.to_string()))
+struct Parser<'a> {
+    buffer: &'a [u8],
+    tokens: ScanIterator<'a>,
+
+    /// Whether to collect errors and keep going rather than returning the first one
+    recover: bool,
+    errors: Vec<ParseError>,
+}
+
+/// How a statement skipped because of an error ended
+enum SkipEnd {
+    /// The statement ended with a semicolon, a block or the end of the input
+    Statement,
+
+    /// A closing curly brace came first, which also ends the current block
+    ClosingBrace(Span),
+}
+
+impl<'a> Parser<'a> {
+    fn new(buffer: &'a [u8], recover: bool) -> Self {
+        Self {
+            buffer,
+            tokens: crate::lexing::scan(buffer),
+            recover,
+            errors: vec![],
+        }
+    }
+
+    /// Returns the next token, or None on EOF
+    ///
+    /// When recovering, lexer errors are recorded and treated as EOF, since the lexer can't
+    /// continue after an error.
+    ///
+    fn next_token(&mut self) -> Result<Option<Token<'a>>, ParseError> {
+        match self.tokens.next() {
+            Some(Err(error)) if self.recover => {
+                self.errors.push(error);
+                Ok(None)
+            }
+            next => next.transpose(),
+        }
+    }
+
+    /// The index of the last byte of the input
+    fn end_of_input(&self) -> usize {
+        self.buffer.len().saturating_sub(1)
+    }
+
+    /// Returns the error, or records it if we're recovering from errors
+    fn report(&mut self, kind: ParseErrorKind, span: Span) -> Result<(), ParseError> {
+        let error = ParseError::new(kind, span, self.buffer);
+
+        if self.recover {
+            self.errors.push(error);
+            Ok(())
+        } else {
+            Err(error)
+        }
+    }
+
+    /// Parses statements until a closing curly brace or the end of the input
+    ///
+    /// The opening brace is the brace of the block being parsed, or None at the top level. It's
+    /// used for reporting unbalanced braces, which is either a block that is never closed or a
+    /// closing brace at the top level.
+    ///
+    /// Returns the parsed statements, and the span of the closing curly brace if there was one.
+    ///
+    fn parse_statements(
+        &mut self,
+        opening_brace: Option<Span>,
+    ) -> Result<(Vec<Node>, Option<Span>), ParseError> {
+        let mut statements: Vec<Node> = vec![];
+        let mut state = ParseState::Clean;
+
+        // Ends the current block, unless we're at the top level where there's no block to end
+        macro_rules! closing_brace {
+            ($span:expr) => {{
+                if opening_brace.is_some() {
+                    return Ok((statements, Some($span)));
+                }
+
+                self.report(ParseErrorKind::UnbalancedBrace, $span)?;
+
+                state = ParseState::Clean;
+                continue;
+            }};
+        }
+
+        // Reports an error, then skips past the rest of the broken statement
+        macro_rules! error {
+            ($kind:expr, $span:expr, $statement_start:expr, $token:expr) => {{
+                self.report($kind, $span)?;
+
+                let (node, end) = self.skip_statement($statement_start, $token)?;
+                statements.push(Node::ErrorNode(node));
+
+                match end {
+                    SkipEnd::Statement => {
+                        state = ParseState::Clean;
+                        continue;
+                    }
+                    SkipEnd::ClosingBrace(span) => closing_brace!(span),
+                }
+            }};
+        }
+
+        macro_rules! unexpected {
+            ($token:expr, $expected:expr, $statement_start:expr) => {
+                error!(
+                    ParseErrorKind::UnexpectedToken {
+                        text: $token.text.to_string(),
+                        expected: $expected,
+                    },
+                    $token.span,
+                    $statement_start,
+                    Some($token)
+                )
+            };
+        }
+
+        loop {
+            match self.next_token()? {
+                // Comments inside a statement, such as between the keyword and the value, are
+                // kept as siblings in front of the statement
+                Some(token) if token.token_type == TokenType::Comment => {
+                    statements.push(Node::CommentNode(CommentNode {
+                        text: token.text.to_string(),
+                        span: token.span,
+                    }))
+                }
+
+                Some(token) => {
+                    match state {
+                        ParseState::Clean => {
+                            // From a clean state, we expect to find a statement keyword, a comment
+                            // or a closing curly brace
+                            match token.token_type {
+                                TokenType::WhiteSpace => continue,
+                                TokenType::LineBreak => continue,
+                                TokenType::ClosingCurlyBrace => closing_brace!(token.span),
+                                TokenType::Other => {
+                                    state = ParseState::GotKeyword {
+                                        keyword: (&token).into(),
+                                        keyword_span: token.span,
+                                    }
+                                }
+                                _ => unexpected!(
+                                    token,
+                                    "a statement keyword, comment or \"}\"",
+                                    token.span.0
+                                ),
                             }
-                            TokenType::ClosingCurlyBrace => {
-                                return Ok(statements);
+                        }
+
+                        ParseState::GotKeyword {
+                            keyword,
+                            keyword_span,
+                        } => {
+                            match token.token_type {
+                                TokenType::WhiteSpace | TokenType::LineBreak => {
+                                    state = ParseState::GotKeyword {
+                                        keyword,
+                                        keyword_span,
+                                    }
+                                }
+
+                                TokenType::OpenCurlyBrace => {
+                                    // Recurse!
+                                    let (children, closing_span) =
+                                        self.parse_statements(Some(token.span))?;
+
+                                    statements.push(Node::BlockNode(BlockNode {
+                                        keyword,
+                                        value: None,
+                                        children,
+                                        span: (
+                                            keyword_span.0,
+                                            closing_span.map_or(self.end_of_input(), |s| s.1),
+                                        ),
+                                        keyword_span,
+                                        value_span: None,
+                                    }));
+
+                                    state = ParseState::Clean;
+                                }
+
+                                TokenType::SemiColon => error!(
+                                    ParseErrorKind::MissingValue,
+                                    keyword_span,
+                                    keyword_span.0,
+                                    Some(token)
+                                ),
+
+                                TokenType::ClosingCurlyBrace => {
+                                    unexpected!(token, "a value, \";\" or \"{\"", keyword_span.0)
+                                }
+
+                                _ => {
+                                    state = ParseState::GotValue {
+                                        keyword,
+                                        keyword_span,
+                                        value: NodeValue::from_token(&token, self.buffer),
+                                        value_span: token.span,
+                                    };
+                                }
                             }
-                            TokenType::Other => state = ParseState::GotKeyword(token.into()),
-                            _ => return Err(format!("Unexpected token: {:?}", token)),
                         }
-                    }
 
-                    ParseState::GotKeyword(keyword) => {
-                        match token.token_type {
-                            TokenType::WhiteSpace => state = ParseState::GotKeyword(keyword),
-                            TokenType::LineBreak => state = ParseState::GotKeyword(keyword),
+                        ParseState::GotValue {
+                            keyword,
+                            keyword_span,
+                            value,
+                            value_span,
+                        } => {
+                            match token.token_type {
+                                TokenType::WhiteSpace | TokenType::LineBreak => {
+                                    state = ParseState::GotValue {
+                                        keyword,
+                                        keyword_span,
+                                        value,
+                                        value_span,
+                                    }
+                                }
 
-                            TokenType::OpenCurlyBrace => {
-                                // Recurse!
-                                statements.push(Node::BlockNode(BlockNode {
-                                    keyword,
-                                    value: None,
-                                    children: parse_statements(tokens)?,
-                                }));
+                                TokenType::OpenCurlyBrace => {
+                                    // Recurse!
+                                    let (children, closing_span) =
+                                        self.parse_statements(Some(token.span))?;
+
+                                    statements.push(Node::BlockNode(BlockNode {
+                                        keyword,
+                                        value: Some(value),
+                                        children,
+                                        span: (
+                                            keyword_span.0,
+                                            closing_span.map_or(self.end_of_input(), |s| s.1),
+                                        ),
+                                        keyword_span,
+                                        value_span: Some(value_span),
+                                    }));
+
+                                    state = ParseState::Clean;
+                                }
+
+                                TokenType::SemiColon => {
+                                    statements.push(Node::LeafNode(LeafNode {
+                                        keyword,
+                                        value,
+                                        span: (keyword_span.0, token.span.1),
+                                        keyword_span,
+                                        value_span,
+                                    }));
+
+                                    state = ParseState::Clean;
+                                }
+
+                                TokenType::Plus => match value {
+                                    NodeValue::String(parts) => {
+                                        state = ParseState::GotPlus {
+                                            keyword,
+                                            keyword_span,
+                                            parts,
+                                            value_span,
+                                        }
+                                    }
+
+                                    _ => unexpected!(token, "\";\" or \"{\"", keyword_span.0),
+                                },
+
+                                TokenType::ClosingCurlyBrace => {
+                                    // Most likely just a missing semicolon, so keep the statement
+                                    self.report(
+                                        ParseErrorKind::UnexpectedToken {
+                                            text: token.text.to_string(),
+                                            expected: "\";\" or \"{\"",
+                                        },
+                                        token.span,
+                                    )?;
+
+                                    statements.push(Node::LeafNode(LeafNode {
+                                        keyword,
+                                        value,
+                                        span: (keyword_span.0, value_span.1),
+                                        keyword_span,
+                                        value_span,
+                                    }));
+
+                                    closing_brace!(token.span);
+                                }
 
-                                state = ParseState::Clean;
+                                _ => unexpected!(token, "\";\" or \"{\"", keyword_span.0),
                             }
+                        }
 
-                            TokenType::SemiColon => {
-                                return Err("Expected to find a value, not \";\"".to_string());
+                        ParseState::GotPlus {
+                            keyword,
+                            keyword_span,
+                            mut parts,
+                            value_span,
+                        } => match token.token_type {
+                            TokenType::WhiteSpace | TokenType::LineBreak => {
+                                state = ParseState::GotPlus {
+                                    keyword,
+                                    keyword_span,
+                                    parts,
+                                    value_span,
+                                }
                             }
 
-                            _ => {
-                                state = ParseState::GotValue(keyword, token.into());
+                            TokenType::String => {
+                                parts.push(StringPart::from_token(&token, self.buffer));
+
+                                state = ParseState::GotValue {
+                                    keyword,
+                                    keyword_span,
+                                    value: NodeValue::String(parts),
+                                    value_span: (value_span.0, token.span.1),
+                                }
                             }
-                        }
+
+                            _ => unexpected!(token, "a quoted string", keyword_span.0),
+                        },
                     }
+                }
 
-                    ParseState::GotValue(keyword, value) => {
-                        match token.token_type {
-                            TokenType::WhiteSpace => state = ParseState::GotValue(keyword, value),
-                            TokenType::LineBreak => state = ParseState::GotValue(keyword, value),
+                // When we reach the end of the token stream, we're done and can return
+                None => {
+                    let eof_span = (self.buffer.len(), self.buffer.len());
 
-                            TokenType::OpenCurlyBrace => {
-                                // Recurse!
-                                statements.push(Node::BlockNode(BlockNode {
-                                    keyword,
-                                    value: Some(value),
-                                    children: parse_statements(tokens)?,
-                                }));
+                    match state {
+                        ParseState::Clean => {}
 
-                                state = ParseState::Clean;
-                            }
+                        ParseState::GotValue {
+                            keyword,
+                            keyword_span,
+                            value,
+                            value_span,
+                        } => {
+                            self.report(ParseErrorKind::UnexpectedEof, eof_span)?;
 
-                            TokenType::SemiColon => {
-                                statements.push(Node::LeafNode(LeafNode { keyword, value }));
+                            statements.push(Node::LeafNode(LeafNode {
+                                keyword,
+                                value,
+                                span: (keyword_span.0, value_span.1),
+                                keyword_span,
+                                value_span,
+                            }));
+                        }
 
-                                state = ParseState::Clean;
-                            }
+                        ParseState::GotKeyword { keyword_span, .. }
+                        | ParseState::GotPlus { keyword_span, .. } => {
+                            self.report(ParseErrorKind::UnexpectedEof, eof_span)?;
 
-                            _ => {
-                                return Err(format!(
-                                    "Expected semicolon or block, got: {:?}",
-                                    token
-                                ));
-                            }
+                            statements.push(Node::ErrorNode(ErrorNode {
+                                span: (keyword_span.0, self.end_of_input()),
+                                children: vec![],
+                            }));
                         }
                     }
+
+                    if let Some(opening_brace) = opening_brace {
+                        self.report(ParseErrorKind::UnbalancedBrace, opening_brace)?;
+                    }
+
+                    return Ok((statements, None));
+                }
+            };
+        }
+    }
+
+    /// Skips past the rest of a broken statement, starting with the offending token
+    ///
+    /// The statement ends at the next semicolon, block or the end of the input. If a closing
+    /// curly brace comes first, the current block ends there too.
+    ///
+    fn skip_statement(
+        &mut self,
+        start: usize,
+        token: Option<Token<'a>>,
+    ) -> Result<(ErrorNode, SkipEnd), ParseError> {
+        let mut token = token;
+        let mut end = start;
+
+        loop {
+            let current = match token {
+                Some(current) => current,
+                None => {
+                    let node = ErrorNode {
+                        span: (start, end),
+                        children: vec![],
+                    };
+                    return Ok((node, SkipEnd::Statement));
+                }
+            };
+
+            match current.token_type {
+                TokenType::ClosingCurlyBrace => {
+                    let node = ErrorNode {
+                        span: (start, end),
+                        children: vec![],
+                    };
+                    return Ok((node, SkipEnd::ClosingBrace(current.span)));
                 }
+
+                TokenType::SemiColon => {
+                    let node = ErrorNode {
+                        span: (start, current.span.1),
+                        children: vec![],
+                    };
+                    return Ok((node, SkipEnd::Statement));
+                }
+
+                TokenType::OpenCurlyBrace => {
+                    let (children, closing_span) = self.parse_statements(Some(current.span))?;
+                    let end = closing_span.map_or(self.end_of_input(), |s| s.1);
+                    let node = ErrorNode {
+                        span: (start, end),
+                        children,
+                    };
+                    return Ok((node, SkipEnd::Statement));
+                }
+
+                TokenType::WhiteSpace | TokenType::LineBreak | TokenType::Comment => {}
+
+                _ => end = current.span.1,
             }
 
-            // When we reach the end of the token stream, we're done and can return
-            None => match state {
-                ParseState::Clean => return Ok(statements),
-                _ => return Err("Unexpected end of input".to_string()),
-            },
-        };
+            token = self.next_token()?;
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::lexing::TextPosition;
     use pretty_assertions::assert_eq;
 
     fn dedent(text: &str) -> String {
@@ -272,4 +907,355 @@ mod test {
             tree.to_string()
         );
     }
+
+    #[test]
+    fn errors() {
+        let error = parse(b"module test {\n    yang-version;\n}\n").unwrap_err();
+        assert_eq!(ParseErrorKind::MissingValue, error.kind);
+        assert_eq!((18, 29), error.span);
+        assert_eq!((2, 5), (error.position.line, error.position.col));
+        assert_eq!(
+            "Expected to find a value, not \";\" at line 2 col 5",
+            error.to_string()
+        );
+
+        let error = parse(b"module test {\n    prefix foo bar;\n}\n").unwrap_err();
+        assert_eq!(
+            ParseErrorKind::UnexpectedToken {
+                text: "bar".to_string(),
+                expected: "\";\" or \"{\"",
+            },
+            error.kind
+        );
+        assert_eq!((29, 31), error.span);
+
+        let error = parse(b"module test {\n    prefix 'foo;\n}\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnterminatedString, error.kind);
+        assert_eq!((25, 32), error.span);
+
+        let error = parse(b"module test {\n    prefix foo").unwrap_err();
+        assert_eq!(ParseErrorKind::UnexpectedEof, error.kind);
+        assert_eq!((2, 15), (error.position.line, error.position.col));
+    }
+
+    #[test]
+    fn spans() {
+        let buffer = b"// Hi\nmodule test {\n  prefix \"t\";\n}\n";
+        let tree = parse(buffer).expect("Failed to parse YANG");
+
+        let text = |span: Span| std::str::from_utf8(&buffer[span.0..=span.1]).unwrap();
+
+        let comment = match &tree.children[0] {
+            Node::CommentNode(node) => node,
+            _ => panic!("Expected a comment node"),
+        };
+        assert_eq!("// Hi", text(comment.span));
+
+        let module = match &tree.children[1] {
+            Node::BlockNode(node) => node,
+            _ => panic!("Expected a block node"),
+        };
+        assert_eq!("module test {\n  prefix \"t\";\n}", text(module.span));
+        assert_eq!("module", text(module.keyword_span));
+        assert_eq!("test", text(module.value_span.unwrap()));
+
+        let prefix = match &module.children[0] {
+            Node::LeafNode(node) => node,
+            _ => panic!("Expected a leaf node"),
+        };
+        assert_eq!("prefix \"t\";", text(prefix.span));
+        assert_eq!("prefix", text(prefix.keyword_span));
+        assert_eq!("\"t\"", text(prefix.value_span));
+    }
+
+    #[test]
+    fn string_concatenation() {
+        let buffer = b"pattern \"[a-z]\" + '+'\n  +\"[0-9]\"+\"x\";";
+        let tree = parse(buffer).expect("Failed to parse YANG");
+
+        let node = match &tree.children[0] {
+            Node::LeafNode(node) => node,
+            _ => panic!("Expected a leaf node"),
+        };
+
+        let parts = match &node.value {
+            NodeValue::String(parts) => parts,
+            _ => panic!("Expected a string value"),
+        };
+
+        assert_eq!(
+            vec![
+                ("\"[a-z]\"", (8, 14)),
+                ("'+'", (18, 20)),
+                ("\"[0-9]\"", (25, 31)),
+                ("\"x\"", (33, 35))
+            ],
+            parts
+                .iter()
+                .map(|part| (part.text.as_str(), part.span))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!((8, 35), node.value_span);
+        assert_eq!("[a-z]+[0-9]x", node.value.argument());
+
+        // Each part is decoded on its own before being concatenated
+        let tree = parse(b"pattern '\\d' + \"\\d\\n\";").expect("Failed to parse YANG");
+        let node = match &tree.children[0] {
+            Node::LeafNode(node) => node,
+            _ => panic!("Expected a leaf node"),
+        };
+        assert_eq!("\\d\\d\n", node.value.argument());
+
+        let error = parse(b"pattern \"foo\" + bar;").unwrap_err();
+        assert_eq!(
+            ParseErrorKind::UnexpectedToken {
+                text: "bar".to_string(),
+                expected: "a quoted string",
+            },
+            error.kind
+        );
+    }
+
+    #[test]
+    fn string_decoding() {
+        let buffer: Vec<u8> = dedent(
+            r#"
+            description
+              "A multi-line string   
+               with trailing whitespace,
+                 extra indentation,
+              too little indentation
+               and escapes: \"\\\t\n\d";
+            reference 'Single quoted \n strings
+                are taken literally   ';
+            "#,
+        )
+        .bytes()
+        .collect();
+
+        let tree = parse(&buffer).expect("Failed to parse YANG");
+
+        let arguments: Vec<_> = tree
+            .children
+            .iter()
+            .map(|node| match node {
+                Node::LeafNode(node) => node.value.argument().to_string(),
+                _ => panic!("Expected a leaf node"),
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                "A multi-line string\nwith trailing whitespace,\n  extra indentation,\ntoo little indentation\nand escapes: \"\\\t\n\\d",
+                "Single quoted \\n strings\n    are taken literally   ",
+            ],
+            arguments,
+        );
+
+        // Tabs count as 8 columns, and are partially replaced with spaces if needed
+        assert_eq!(
+            "a\n b\n       c",
+            decode_quoted_string("\"a\n\t b\n       \tc\"", 7)
+        );
+    }
+
+    #[test]
+    fn recovery() {
+        let buffer: Vec<u8> = dedent(
+            r#"
+            module test {
+                prefix;
+                container c {
+                    leaf a { type string }
+                    leaf b "x" "y" {
+                        type int8;
+                    }
+                    "stray";
+                }
+                leaf d {
+                    type
+            "#,
+        )
+        .bytes()
+        .collect();
+
+        let (tree, errors) = parse_with_recovery(&buffer);
+
+        assert_eq!(
+            dedent(
+                r#"
+                (root
+                  (Keyword "module" Other
+                    (error)
+                    (Keyword "container" Other
+                      (Keyword "leaf" Other
+                        (Keyword "type" Other))
+                      (error
+                        (Keyword "type" Other))
+                      (error))
+                    (Keyword "leaf" Other
+                      (error))))
+                "#
+            ),
+            tree.to_string()
+        );
+
+        assert_eq!(
+            vec![
+                (ParseErrorKind::MissingValue, (2, 5)),
+                (
+                    ParseErrorKind::UnexpectedToken {
+                        text: "}".to_string(),
+                        expected: "\";\" or \"{\"",
+                    },
+                    (4, 30)
+                ),
+                (
+                    ParseErrorKind::UnexpectedToken {
+                        text: "\"x\"".to_string(),
+                        expected: "\";\" or \"{\"",
+                    },
+                    (5, 16)
+                ),
+                (
+                    ParseErrorKind::UnexpectedToken {
+                        text: "\"stray\"".to_string(),
+                        expected: "a statement keyword, comment or \"}\"",
+                    },
+                    (8, 9)
+                ),
+                (ParseErrorKind::UnexpectedEof, (12, 1)),
+                (ParseErrorKind::UnbalancedBrace, (10, 12)),
+                (ParseErrorKind::UnbalancedBrace, (1, 13)),
+            ],
+            errors
+                .into_iter()
+                .map(|error| (error.kind, (error.position.line, error.position.col)))
+                .collect::<Vec<_>>()
+        );
+
+        let text = |node: &Node| {
+            let span = node.span();
+            std::str::from_utf8(&buffer[span.0..=span.1]).unwrap()
+        };
+
+        let module = match &tree.children[0] {
+            Node::BlockNode(node) => node,
+            _ => panic!("Expected a block node"),
+        };
+        assert_eq!("prefix;", text(&module.children[0]));
+
+        let container = match &module.children[1] {
+            Node::BlockNode(node) => node,
+            _ => panic!("Expected a block node"),
+        };
+        assert_eq!("leaf a { type string }", text(&container.children[0]));
+        assert!(text(&container.children[1]).starts_with("leaf b \"x\" \"y\" {"));
+        assert_eq!("\"stray\";", text(&container.children[2]));
+    }
+
+    #[test]
+    fn comments_inside_statements() {
+        let buffer = b"leaf /* c */ x;\ndescription \"a\" // trailing\n  + /* d */ \"b\";\n";
+        let tree = parse(buffer).expect("Failed to parse YANG");
+
+        let comments: Vec<_> = tree
+            .children
+            .iter()
+            .filter_map(|node| match node {
+                Node::CommentNode(node) => Some(node.text.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec!["/* c */", "// trailing", "/* d */"], comments);
+        assert_eq!(
+            dedent(
+                r#"
+                (root
+                  (comment)
+                  (Keyword "leaf" Other)
+                  (comment)
+                  (comment)
+                  (Keyword "description" String))
+                "#
+            ),
+            tree.to_string()
+        );
+
+        let arguments: Vec<_> = tree
+            .children
+            .iter()
+            .filter_map(|node| match node {
+                Node::LeafNode(node) => Some(node.value.argument()),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(vec!["x", "ab"], arguments);
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let buffer = b"module test {\r\n  description\r\n    \"foo  \r\n     bar\";\r\n}\r\n";
+        let tree = parse(buffer).expect("Failed to parse YANG");
+
+        let module = match &tree.children[0] {
+            Node::BlockNode(node) => node,
+            _ => panic!("Expected a block node"),
+        };
+        let description = match &module.children[0] {
+            Node::LeafNode(node) => node,
+            _ => panic!("Expected a leaf node"),
+        };
+
+        assert_eq!("foo\nbar", description.value.argument());
+
+        // Lone CR line breaks decode the same as LF and CRLF
+        for line_break in ["\n", "\r\n", "\r"] {
+            let text = format!("\"line1  {}     line2\"", line_break);
+            assert_eq!("line1\nline2", decode_quoted_string(&text, 4));
+        }
+
+        let tree = parse(b"description\r  \"foo\r   bar\";\r").expect("Failed to parse YANG");
+        assert_eq!("foo\nbar", tree.children[0].argument().unwrap());
+    }
+
+    #[test]
+    fn unbalanced_braces() {
+        // A block that is never closed points at its opening brace
+        let error = parse(b"module test {\n  container c {\n    leaf l;\n  }\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnbalancedBrace, error.kind);
+        assert_eq!((12, 12), error.span);
+
+        let error = parse(b"module test {\n  container c {\n    leaf l;\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnbalancedBrace, error.kind);
+        assert_eq!((28, 28), error.span);
+
+        // A closing brace at the top level points at itself
+        let error = parse(b"module test {\n}\n}\nmodule other {\n}\n").unwrap_err();
+        assert_eq!(ParseErrorKind::UnbalancedBrace, error.kind);
+        assert_eq!((16, 16), error.span);
+        assert_eq!(TextPosition { line: 3, col: 1 }, error.position);
+
+        // When recovering, the stray brace is skipped and the rest of the document is kept
+        let (tree, errors) = parse_with_recovery(b"module test {\n}\n}\nmodule other {\n}\n");
+        assert_eq!(
+            vec![ParseErrorKind::UnbalancedBrace],
+            errors
+                .into_iter()
+                .map(|error| error.kind)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            dedent(
+                r#"
+                (root
+                  (Keyword "module" Other)
+                  (Keyword "module" Other))
+                "#
+            ),
+            tree.to_string()
+        );
+    }
 }
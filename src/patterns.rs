@@ -0,0 +1,608 @@
+//
+// Pattern restrictions
+//
+// The argument of "pattern" is a regular expression in the syntax of XML Schema Part 2, Appendix
+// F (RFC 7950 section 9.4.5). It differs from the syntax of the regex crate in a few ways: the
+// expression is implicitly anchored at both ends, "^" and "$" are ordinary characters, there are
+// Unicode block escapes such as "\p{IsBasicLatin}", and character classes can be subtracted, as
+// in "[a-z-[aeiou]]". Patterns are therefore parsed here and translated into the regex crate's
+// syntax before being compiled.
+//
+
+use regex::Regex;
+use std::fmt::{self, Display, Formatter};
+
+use crate::arguments::{ArgumentType, Modifier};
+use crate::lexing::Span;
+use crate::repository::ModuleId;
+use crate::schema::Property;
+
+/// The Unicode blocks of XML Schema Part 2, section F.1.1, except for the surrogate blocks which
+/// can't occur in a string
+const BLOCKS: &[(&str, u32, u32)] = &[
+    ("BasicLatin", 0x0000, 0x007F),
+    ("Latin-1Supplement", 0x0080, 0x00FF),
+    ("LatinExtended-A", 0x0100, 0x017F),
+    ("LatinExtended-B", 0x0180, 0x024F),
+    ("IPAExtensions", 0x0250, 0x02AF),
+    ("SpacingModifierLetters", 0x02B0, 0x02FF),
+    ("CombiningDiacriticalMarks", 0x0300, 0x036F),
+    ("Greek", 0x0370, 0x03FF),
+    ("Cyrillic", 0x0400, 0x04FF),
+    ("Armenian", 0x0530, 0x058F),
+    ("Hebrew", 0x0590, 0x05FF),
+    ("Arabic", 0x0600, 0x06FF),
+    ("Syriac", 0x0700, 0x074F),
+    ("Thaana", 0x0780, 0x07BF),
+    ("Devanagari", 0x0900, 0x097F),
+    ("Bengali", 0x0980, 0x09FF),
+    ("Gurmukhi", 0x0A00, 0x0A7F),
+    ("Gujarati", 0x0A80, 0x0AFF),
+    ("Oriya", 0x0B00, 0x0B7F),
+    ("Tamil", 0x0B80, 0x0BFF),
+    ("Telugu", 0x0C00, 0x0C7F),
+    ("Kannada", 0x0C80, 0x0CFF),
+    ("Malayalam", 0x0D00, 0x0D7F),
+    ("Sinhala", 0x0D80, 0x0DFF),
+    ("Thai", 0x0E00, 0x0E7F),
+    ("Lao", 0x0E80, 0x0EFF),
+    ("Tibetan", 0x0F00, 0x0FFF),
+    ("Myanmar", 0x1000, 0x109F),
+    ("Georgian", 0x10A0, 0x10FF),
+    ("HangulJamo", 0x1100, 0x11FF),
+    ("Ethiopic", 0x1200, 0x137F),
+    ("Cherokee", 0x13A0, 0x13FF),
+    ("UnifiedCanadianAboriginalSyllabics", 0x1400, 0x167F),
+    ("Ogham", 0x1680, 0x169F),
+    ("Runic", 0x16A0, 0x16FF),
+    ("Khmer", 0x1780, 0x17FF),
+    ("Mongolian", 0x1800, 0x18AF),
+    ("LatinExtendedAdditional", 0x1E00, 0x1EFF),
+    ("GreekExtended", 0x1F00, 0x1FFF),
+    ("GeneralPunctuation", 0x2000, 0x206F),
+    ("SuperscriptsandSubscripts", 0x2070, 0x209F),
+    ("CurrencySymbols", 0x20A0, 0x20CF),
+    ("CombiningMarksforSymbols", 0x20D0, 0x20FF),
+    ("LetterlikeSymbols", 0x2100, 0x214F),
+    ("NumberForms", 0x2150, 0x218F),
+    ("Arrows", 0x2190, 0x21FF),
+    ("MathematicalOperators", 0x2200, 0x22FF),
+    ("MiscellaneousTechnical", 0x2300, 0x23FF),
+    ("ControlPictures", 0x2400, 0x243F),
+    ("OpticalCharacterRecognition", 0x2440, 0x245F),
+    ("EnclosedAlphanumerics", 0x2460, 0x24FF),
+    ("BoxDrawing", 0x2500, 0x257F),
+    ("BlockElements", 0x2580, 0x259F),
+    ("GeometricShapes", 0x25A0, 0x25FF),
+    ("MiscellaneousSymbols", 0x2600, 0x26FF),
+    ("Dingbats", 0x2700, 0x27BF),
+    ("BraillePatterns", 0x2800, 0x28FF),
+    ("CJKRadicalsSupplement", 0x2E80, 0x2EFF),
+    ("KangxiRadicals", 0x2F00, 0x2FDF),
+    ("IdeographicDescriptionCharacters", 0x2FF0, 0x2FFF),
+    ("CJKSymbolsandPunctuation", 0x3000, 0x303F),
+    ("Hiragana", 0x3040, 0x309F),
+    ("Katakana", 0x30A0, 0x30FF),
+    ("Bopomofo", 0x3100, 0x312F),
+    ("HangulCompatibilityJamo", 0x3130, 0x318F),
+    ("Kanbun", 0x3190, 0x319F),
+    ("BopomofoExtended", 0x31A0, 0x31BF),
+    ("EnclosedCJKLettersandMonths", 0x3200, 0x32FF),
+    ("CJKCompatibility", 0x3300, 0x33FF),
+    ("CJKUnifiedIdeographsExtensionA", 0x3400, 0x4DB5),
+    ("CJKUnifiedIdeographs", 0x4E00, 0x9FFF),
+    ("YiSyllables", 0xA000, 0xA48F),
+    ("YiRadicals", 0xA490, 0xA4CF),
+    ("HangulSyllables", 0xAC00, 0xD7A3),
+    ("PrivateUse", 0xE000, 0xF8FF),
+    ("CJKCompatibilityIdeographs", 0xF900, 0xFAFF),
+    ("AlphabeticPresentationForms", 0xFB00, 0xFB4F),
+    ("ArabicPresentationForms-A", 0xFB50, 0xFDFF),
+    ("CombiningHalfMarks", 0xFE20, 0xFE2F),
+    ("CJKCompatibilityForms", 0xFE30, 0xFE4F),
+    ("SmallFormVariants", 0xFE50, 0xFE6F),
+    ("ArabicPresentationForms-B", 0xFE70, 0xFEFE),
+    ("Specials", 0xFEFF, 0xFEFF),
+    ("HalfwidthandFullwidthForms", 0xFF00, 0xFFEF),
+    ("Specials", 0xFFF0, 0xFFFD),
+    ("OldItalic", 0x10300, 0x1032F),
+    ("Gothic", 0x10330, 0x1034F),
+    ("Deseret", 0x10400, 0x1044F),
+    ("ByzantineMusicalSymbols", 0x1D000, 0x1D0FF),
+    ("MusicalSymbols", 0x1D100, 0x1D1FF),
+    ("MathematicalAlphanumericSymbols", 0x1D400, 0x1D7FF),
+    ("CJKUnifiedIdeographsExtensionB", 0x20000, 0x2A6D6),
+    ("CJKCompatibilityIdeographsSupplement", 0x2F800, 0x2FA1F),
+    ("Tags", 0xE0000, 0xE007F),
+    ("PrivateUse", 0xF0000, 0xFFFFD),
+    ("PrivateUse", 0x100000, 0x10FFFD),
+];
+
+/// The Unicode general categories of XML Schema Part 2, section F.1.1
+const CATEGORIES: &[&str] = &[
+    "L", "Lu", "Ll", "Lt", "Lm", "Lo", "M", "Mn", "Mc", "Me", "N", "Nd", "Nl", "No", "P", "Pc",
+    "Pd", "Ps", "Pe", "Pi", "Pf", "Po", "Z", "Zs", "Zl", "Zp", "S", "Sm", "Sc", "Sk", "So", "C",
+    "Cc", "Cf", "Co", "Cn",
+];
+
+/// The characters that can start an XML name, approximated with Unicode categories
+const NAME_START: &str = r"\p{L}\p{Nl}_:";
+
+/// The characters that can occur in an XML name, approximated with Unicode categories
+const NAME: &str = r"\p{L}\p{Nl}\p{Nd}\p{Mn}\p{Mc}\-_:\.\x{B7}";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub kind: PatternErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar {
+        c: char,
+    },
+    InvalidEscape {
+        c: char,
+    },
+    UnknownBlock {
+        name: String,
+    },
+    UnknownCategory {
+        name: String,
+    },
+
+    /// A malformed "{n,m}" quantifier, or one where m is less than n
+    InvalidQuantifier,
+
+    /// A character range where the end comes before the start, or is a multi-character escape
+    InvalidRange,
+
+    /// The translated expression was rejected by the regex crate, usually for being too large
+    Regex {
+        message: String,
+    },
+}
+
+impl Display for PatternErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PatternErrorKind::UnexpectedEnd => write!(f, "Unexpected end of pattern"),
+            PatternErrorKind::UnexpectedChar { c } => write!(f, "Unexpected {:?}", c),
+            PatternErrorKind::InvalidEscape { c } => write!(f, "Invalid escape \\{}", c),
+            PatternErrorKind::UnknownBlock { name } => write!(f, "Unknown block {:?}", name),
+            PatternErrorKind::UnknownCategory { name } => {
+                write!(f, "Unknown category {:?}", name)
+            }
+            PatternErrorKind::InvalidQuantifier => write!(f, "Invalid quantifier"),
+            PatternErrorKind::InvalidRange => write!(f, "Invalid character range"),
+            PatternErrorKind::Regex { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl Display for PatternError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid pattern: {}", self.kind)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// A compiled "pattern" restriction
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    regex: Regex,
+
+    /// Set by "modifier invert-match", meaning values must not match the expression
+    pub invert: bool,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str, invert: bool) -> Result<Self, PatternErrorKind> {
+        let regex = Regex::new(&translate(pattern)?).map_err(|error| PatternErrorKind::Regex {
+            message: error.to_string(),
+        })?;
+
+        Ok(Self { regex, invert })
+    }
+
+    /// Compiles a "pattern" statement, taking its "modifier" sub-statement into account
+    pub fn from_property(property: &Property) -> Result<Self, PatternError> {
+        let invert = property
+            .node
+            .children()
+            .iter()
+            .find(|child| child.is("modifier"))
+            .and_then(|child| child.argument())
+            .and_then(|arg| Modifier::parse(&arg))
+            == Some(Modifier::InvertMatch);
+
+        let argument = property.argument().unwrap_or_default();
+
+        Self::new(&argument, invert).map_err(|kind| PatternError {
+            kind,
+            module: property.source.module,
+            span: property
+                .node
+                .value_span()
+                .unwrap_or_else(|| property.node.span()),
+        })
+    }
+
+    /// Returns true if the value satisfies the restriction
+    pub fn is_match(&self, value: &str) -> bool {
+        self.regex.is_match(value) != self.invert
+    }
+}
+
+/// Translates an XML Schema regular expression into an anchored expression for the regex crate
+pub fn translate(pattern: &str) -> Result<String, PatternErrorKind> {
+    let mut translator = Translator {
+        chars: pattern.chars().collect(),
+        position: 0,
+    };
+
+    let regex = translator.regex()?;
+
+    match translator.next() {
+        None => Ok(format!(r"\A(?:{})\z", regex)),
+        Some(c) => Err(PatternErrorKind::UnexpectedChar { c }),
+    }
+}
+
+/// The translation of an escape sequence
+enum Escape {
+    Char(char),
+
+    /// A character class, which can be used both on its own and within another class
+    Class(String),
+}
+
+struct Translator {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Translator {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek();
+        self.position += c.is_some() as usize;
+        c
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), PatternErrorKind> {
+        match self.next() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(PatternErrorKind::UnexpectedChar { c }),
+            None => Err(PatternErrorKind::UnexpectedEnd),
+        }
+    }
+
+    /// regExp ::= branch ( '|' branch )*
+    fn regex(&mut self) -> Result<String, PatternErrorKind> {
+        let mut regex = self.branch()?;
+
+        while self.peek() == Some('|') {
+            self.next();
+            regex.push('|');
+            regex += &self.branch()?;
+        }
+
+        Ok(regex)
+    }
+
+    /// branch ::= piece*
+    fn branch(&mut self) -> Result<String, PatternErrorKind> {
+        let mut branch = String::new();
+
+        while !matches!(self.peek(), None | Some('|') | Some(')')) {
+            branch += &self.atom()?;
+            branch += &self.quantifier()?;
+        }
+
+        Ok(branch)
+    }
+
+    fn atom(&mut self) -> Result<String, PatternErrorKind> {
+        match self.next().ok_or(PatternErrorKind::UnexpectedEnd)? {
+            '(' => {
+                let regex = self.regex()?;
+                self.expect(')')?;
+                Ok(format!("(?:{})", regex))
+            }
+            '[' => self.class(),
+            '.' => Ok(r"[^\n\r]".to_string()),
+            '\\' => Ok(match self.escape()? {
+                Escape::Char(c) => literal(c),
+                Escape::Class(class) => class,
+            }),
+            c @ ('?' | '*' | '+' | '{' | '}' | ']') => Err(PatternErrorKind::UnexpectedChar { c }),
+            c => Ok(literal(c)),
+        }
+    }
+
+    /// quantifier ::= [?*+] | ( '{' quantity '}' )
+    fn quantifier(&mut self) -> Result<String, PatternErrorKind> {
+        match self.peek() {
+            Some(c @ ('?' | '*' | '+')) => {
+                self.next();
+                Ok(c.to_string())
+            }
+            Some('{') => {
+                self.next();
+                let mut quantity = String::new();
+
+                loop {
+                    match self.next() {
+                        Some('}') => break,
+                        Some(c @ ('0'..='9' | ',')) => quantity.push(c),
+                        Some(_) => return Err(PatternErrorKind::InvalidQuantifier),
+                        None => return Err(PatternErrorKind::UnexpectedEnd),
+                    }
+                }
+
+                let (min, max) = match quantity.split_once(',') {
+                    Some((min, "")) => (min, None),
+                    Some((min, max)) => (min, Some(max)),
+                    None => (quantity.as_str(), Some(quantity.as_str())),
+                };
+
+                let min = min.parse::<u32>();
+                let max = max.map(str::parse::<u32>).transpose();
+
+                match (min, max) {
+                    (Ok(min), Ok(max)) if max.is_none_or(|max| min <= max) => {
+                        Ok(format!("{{{}}}", quantity))
+                    }
+                    _ => Err(PatternErrorKind::InvalidQuantifier),
+                }
+            }
+            _ => Ok(String::new()),
+        }
+    }
+
+    /// Translates a character class expression, after the opening bracket
+    ///
+    /// Subtractions are translated into the regex crate's "--" operator, with the negation of the
+    /// positive group nested within it, since "[^a-z--[aeiou]]" would negate the difference
+    /// rather than the first operand.
+    ///
+    fn class(&mut self) -> Result<String, PatternErrorKind> {
+        let negated = if self.peek() == Some('^') {
+            self.next();
+            "^"
+        } else {
+            ""
+        };
+
+        let mut items = String::new();
+
+        loop {
+            match self.peek() {
+                None => return Err(PatternErrorKind::UnexpectedEnd),
+                Some(c @ (']' | '[')) if items.is_empty() => {
+                    return Err(PatternErrorKind::UnexpectedChar { c });
+                }
+                Some(']') => {
+                    self.next();
+                    return Ok(format!("[{}{}]", negated, items));
+                }
+                Some('-') if self.peek_at(1) == Some('[') => {
+                    // Only a non-empty group can be subtracted from
+                    if items.is_empty() {
+                        return Err(PatternErrorKind::UnexpectedChar { c: '-' });
+                    }
+
+                    self.position += 2;
+                    let subtracted = self.class()?;
+                    self.expect(']')?;
+                    return Ok(format!("[[{}{}]--{}]", negated, items, subtracted));
+                }
+                Some('[') => return Err(PatternErrorKind::UnexpectedChar { c: '[' }),
+                Some(_) => items += &self.class_item()?,
+            }
+        }
+    }
+
+    /// charRange | charClassEsc
+    fn class_item(&mut self) -> Result<String, PatternErrorKind> {
+        // A dash before the end of the group, or before a subtraction, is a literal dash
+        let is_range = |translator: &Self| {
+            translator.peek() == Some('-') && !matches!(translator.peek_at(1), Some(']' | '['))
+        };
+
+        let start = match self.next().ok_or(PatternErrorKind::UnexpectedEnd)? {
+            '\\' => match self.escape()? {
+                Escape::Char(c) => c,
+                Escape::Class(_) if is_range(self) => return Err(PatternErrorKind::InvalidRange),
+                Escape::Class(class) => return Ok(class),
+            },
+            c => c,
+        };
+
+        if !is_range(self) {
+            return Ok(literal(start));
+        }
+
+        self.next();
+
+        let end = match self.next().ok_or(PatternErrorKind::UnexpectedEnd)? {
+            '\\' => match self.escape()? {
+                Escape::Char(c) => c,
+                Escape::Class(_) => return Err(PatternErrorKind::InvalidRange),
+            },
+            c @ '[' => return Err(PatternErrorKind::UnexpectedChar { c }),
+            c => c,
+        };
+
+        if end < start {
+            return Err(PatternErrorKind::InvalidRange);
+        }
+
+        Ok(format!("{}-{}", literal(start), literal(end)))
+    }
+
+    /// Translates an escape sequence, after the backslash
+    fn escape(&mut self) -> Result<Escape, PatternErrorKind> {
+        let class = |class: &str| Ok(Escape::Class(class.to_string()));
+
+        match self.next().ok_or(PatternErrorKind::UnexpectedEnd)? {
+            'n' => Ok(Escape::Char('\n')),
+            'r' => Ok(Escape::Char('\r')),
+            't' => Ok(Escape::Char('\t')),
+            c @ ('\\' | '|' | '.' | '?' | '*' | '+' | '(' | ')' | '{' | '}' | '-' | '[' | ']'
+            | '^') => Ok(Escape::Char(c)),
+            's' => class(r"[\x20\t\n\r]"),
+            'S' => class(r"[^\x20\t\n\r]"),
+            'd' => class(r"\p{Nd}"),
+            'D' => class(r"\P{Nd}"),
+            'w' => class(r"[^\p{P}\p{Z}\p{C}]"),
+            'W' => class(r"[\p{P}\p{Z}\p{C}]"),
+            'i' => class(&format!("[{}]", NAME_START)),
+            'I' => class(&format!("[^{}]", NAME_START)),
+            'c' => class(&format!("[{}]", NAME)),
+            'C' => class(&format!("[^{}]", NAME)),
+            'p' => self.property(false),
+            'P' => self.property(true),
+            c => Err(PatternErrorKind::InvalidEscape { c }),
+        }
+    }
+
+    /// Translates a category or block escape, after the "p" or "P"
+    fn property(&mut self, negated: bool) -> Result<Escape, PatternErrorKind> {
+        self.expect('{')?;
+
+        let mut name = String::new();
+
+        loop {
+            match self.next() {
+                Some('}') => break,
+                Some(c) => name.push(c),
+                None => return Err(PatternErrorKind::UnexpectedEnd),
+            }
+        }
+
+        if let Some(block) = name.strip_prefix("Is") {
+            let ranges: String = BLOCKS
+                .iter()
+                .filter(|(name, _, _)| *name == block)
+                .map(|(_, start, end)| format!(r"\x{{{:X}}}-\x{{{:X}}}", start, end))
+                .collect();
+
+            if ranges.is_empty() {
+                let name = block.to_string();
+                return Err(PatternErrorKind::UnknownBlock { name });
+            }
+
+            let negation = if negated { "^" } else { "" };
+            Ok(Escape::Class(format!("[{}{}]", negation, ranges)))
+        } else if CATEGORIES.contains(&name.as_str()) {
+            let escape = if negated { 'P' } else { 'p' };
+            Ok(Escape::Class(format!(r"\{}{{{}}}", escape, name)))
+        } else {
+            Err(PatternErrorKind::UnknownCategory { name })
+        }
+    }
+}
+
+/// Escapes a character for use in an expression, both within and outside of character classes
+fn literal(c: char) -> String {
+    regex::escape(c.encode_utf8(&mut [0; 4]))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn matching() {
+        let cases: &[(&str, &str, bool)] = &[
+            ("[a-z]+", "abc", true),
+            ("[a-z]+", "abc1", false),
+            ("a|b", "ab", false),
+            ("^a$", "^a$", true),
+            ("[a-z-[aeiou]]+", "bcd", true),
+            ("[a-z-[aeiou]]+", "bad", false),
+            ("[^a-z-[0-9]]", "A", true),
+            ("[^a-z-[0-9]]", "5", false),
+            (r"\p{IsBasicLatin}*", "abc", true),
+            (r"\p{IsBasicLatin}*", "abç", false),
+            (r"\P{IsBasicLatin}", "ç", true),
+            (r"\p{Lu}\d{2,3}", "A12", true),
+            (r"\p{Lu}\d{2,3}", "A1234", false),
+            (r"[\s\-]+", " -\t", true),
+            (".", "\n", false),
+            (r"\i\c*", "_a-1.b", true),
+            (r"[+\-]?(0|[1-9][0-9]*)", "-10", true),
+            ("", "", true),
+        ];
+
+        for (pattern, value, expected) in cases {
+            let compiled = Pattern::new(pattern, false).unwrap();
+            assert_eq!(
+                *expected,
+                compiled.is_match(value),
+                "{} {:?}",
+                pattern,
+                value
+            );
+        }
+
+        let inverted = Pattern::new("[xX][mM][lL].*", true).unwrap();
+        assert!(inverted.is_match("yang"));
+        assert!(!inverted.is_match("xml-data"));
+    }
+
+    #[test]
+    fn errors() {
+        let cases: &[(&str, PatternErrorKind)] = &[
+            ("[a-", PatternErrorKind::UnexpectedEnd),
+            ("(a", PatternErrorKind::UnexpectedEnd),
+            ("a)", PatternErrorKind::UnexpectedChar { c: ')' }),
+            ("a**", PatternErrorKind::UnexpectedChar { c: '*' }),
+            ("*", PatternErrorKind::UnexpectedChar { c: '*' }),
+            ("a{3,1}", PatternErrorKind::InvalidQuantifier),
+            ("a{x}", PatternErrorKind::InvalidQuantifier),
+            ("[z-a]", PatternErrorKind::InvalidRange),
+            (r"[\d-z]", PatternErrorKind::InvalidRange),
+            ("[-[a]]", PatternErrorKind::UnexpectedChar { c: '-' }),
+            ("[]", PatternErrorKind::UnexpectedChar { c: ']' }),
+            (r"\b", PatternErrorKind::InvalidEscape { c: 'b' }),
+            (
+                r"\p{IsKlingon}",
+                PatternErrorKind::UnknownBlock {
+                    name: "Klingon".to_string(),
+                },
+            ),
+            (
+                r"\p{Foo}",
+                PatternErrorKind::UnknownCategory {
+                    name: "Foo".to_string(),
+                },
+            ),
+        ];
+
+        for (pattern, expected) in cases {
+            assert_eq!(
+                Some(expected),
+                Pattern::new(pattern, false).err().as_ref(),
+                "{}",
+                pattern
+            );
+        }
+    }
+}
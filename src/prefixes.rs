@@ -0,0 +1,452 @@
+//
+// Prefix resolution
+//
+// Identifiers that refer to definitions in other modules are written with the prefix of an
+// import, like "inet:ip-address", and extension statements are always written with a prefix. This
+// module links those prefixes to the modules they refer to, using the "prefix" and "import"
+// statements of the module they appear in.
+//
+
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+use crate::lexing::Span;
+use crate::parsing::{Node, NodeValue, StatementKeyword};
+use crate::repository::{LoadedModule, ModuleId, ModuleRepository};
+
+/// The module a prefix refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PrefixTarget {
+    /// The prefix of the module itself, or of the module a submodule belongs to
+    Local,
+    Import(ModuleId),
+}
+
+/// The prefixes that are valid within a module
+#[derive(Debug, Clone)]
+pub struct PrefixMap {
+    local: String,
+    imports: HashMap<String, ModuleId>,
+}
+
+impl PrefixMap {
+    /// Returns the prefixes of a module
+    ///
+    /// The module's own prefix takes precedence over imports with the same prefix, and the first
+    /// of several imports with the same prefix over the others. `resolve_prefixes` reports such
+    /// imports as errors.
+    ///
+    pub fn new(module: &LoadedModule) -> Self {
+        Self::with_errors(module).0
+    }
+
+    /// Returns the prefixes of a module, along with an error for every import with a prefix that
+    /// is already in use
+    fn with_errors(module: &LoadedModule) -> (Self, Vec<ResolutionError>) {
+        let mut imports = HashMap::new();
+        let mut errors = vec![];
+
+        let statements = module
+            .root
+            .children
+            .iter()
+            .flat_map(|node| node.children())
+            .filter(|node| node.is("import"));
+
+        // The linked imports are in the same order as the import statements
+        for (import, statement) in module.imports.iter().zip(statements) {
+            let prefix_node = match statement.children().iter().find(|node| node.is("prefix")) {
+                Some(node) => node,
+                None => continue,
+            };
+
+            if import.prefix == module.prefix || imports.contains_key(&import.prefix) {
+                errors.push(ResolutionError {
+                    kind: ResolutionErrorKind::DuplicatePrefix {
+                        prefix: import.prefix.clone(),
+                    },
+                    span: prefix_node.value_span().unwrap_or(prefix_node.span()),
+                });
+            } else {
+                imports.insert(import.prefix.clone(), import.module);
+            }
+        }
+
+        let prefixes = Self {
+            local: module.prefix.clone(),
+            imports,
+        };
+
+        (prefixes, errors)
+    }
+
+    /// Resolves a prefix, where no prefix at all refers to the local module
+    pub fn resolve(&self, prefix: Option<&str>) -> Option<PrefixTarget> {
+        match prefix {
+            None => Some(PrefixTarget::Local),
+            Some(prefix) if prefix == self.local => Some(PrefixTarget::Local),
+            Some(prefix) => self.imports.get(prefix).copied().map(PrefixTarget::Import),
+        }
+    }
+}
+
+/// Where a reference to another definition appears
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    /// The argument of "type"
+    Type,
+    /// The argument of "base"
+    Base,
+    /// The argument of "uses"
+    Uses,
+    /// A feature name in an "if-feature" expression
+    IfFeature,
+    /// A node in the target of "augment", "deviation" or "refine"
+    SchemaNode,
+    /// The keyword of an extension statement
+    Extension,
+}
+
+/// An identifier in a module, linked to the module it refers to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub kind: ReferenceKind,
+    pub prefix: Option<String>,
+    pub name: String,
+    pub target: PrefixTarget,
+
+    /// The span of the identifier, including its prefix
+    ///
+    /// For quoted arguments where the identifier can't be located exactly, such as in strings
+    /// with escape sequences, this is the span of the whole argument.
+    ///
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolutionError {
+    pub kind: ResolutionErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolutionErrorKind {
+    /// A prefix that is neither the module's own prefix nor the prefix of an import
+    UnknownPrefix { prefix: String },
+
+    /// Two imports, or an import and the module itself, use the same prefix
+    DuplicatePrefix { prefix: String },
+}
+
+impl Display for ResolutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ResolutionErrorKind::UnknownPrefix { prefix } => {
+                write!(f, "Unknown prefix {:?}", prefix)
+            }
+            ResolutionErrorKind::DuplicatePrefix { prefix } => {
+                write!(f, "The prefix {:?} is already in use", prefix)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ResolutionError {}
+
+/// Resolves the prefixes of all references in a loaded module
+///
+/// Unprefixed identifiers refer to the local module, so they are returned as local references.
+/// Note that this includes the names of built-in types, such as "string".
+///
+pub fn resolve_prefixes(
+    repository: &ModuleRepository,
+    id: ModuleId,
+) -> (Vec<Reference>, Vec<ResolutionError>) {
+    let module = repository.get(id);
+    let (prefixes, errors) = PrefixMap::with_errors(module);
+    let mut resolver = Resolver {
+        prefixes,
+        references: vec![],
+        errors,
+    };
+
+    for node in module.root.children.iter() {
+        resolver.visit(node);
+    }
+
+    (resolver.references, resolver.errors)
+}
+
+struct Resolver {
+    prefixes: PrefixMap,
+    references: Vec<Reference>,
+    errors: Vec<ResolutionError>,
+}
+
+impl Resolver {
+    fn visit(&mut self, node: &Node) {
+        match node.keyword() {
+            Some(StatementKeyword::ExtensionKeyword(keyword)) => {
+                let span = node.keyword_span().unwrap_or(node.span());
+                self.reference(ReferenceKind::Extension, keyword, span);
+            }
+            Some(StatementKeyword::Keyword(keyword)) => match keyword.as_str() {
+                "type" => self.identifier_ref(node, ReferenceKind::Type),
+                "base" => self.identifier_ref(node, ReferenceKind::Base),
+                "uses" => self.identifier_ref(node, ReferenceKind::Uses),
+                "if-feature" => self.if_feature(node),
+                "augment" | "deviation" | "refine" => self.schema_node_id(node),
+                _ => {}
+            },
+            Some(StatementKeyword::Invalid(_)) | None => {}
+        }
+
+        for child in node.children() {
+            self.visit(child);
+        }
+    }
+
+    fn identifier_ref(&mut self, node: &Node, kind: ReferenceKind) {
+        if let Some(argument) = node.argument() {
+            let span = argument_span(node, 0, argument.len());
+            self.reference(kind, &argument, span);
+        }
+    }
+
+    /// Resolves the features in an if-feature expression, skipping operators and parentheses
+    fn if_feature(&mut self, node: &Node) {
+        let argument = match node.argument() {
+            Some(argument) => argument,
+            None => return,
+        };
+
+        for (offset, word) in words(&argument, |c| c.is_whitespace() || c == '(' || c == ')') {
+            if !matches!(word, "and" | "or" | "not") {
+                let span = argument_span(node, offset, word.len());
+                self.reference(ReferenceKind::IfFeature, word, span);
+            }
+        }
+    }
+
+    /// Resolves the nodes in a schema node identifier, such as "/if:interfaces/if:interface"
+    fn schema_node_id(&mut self, node: &Node) {
+        let argument = match node.argument() {
+            Some(argument) => argument,
+            None => return,
+        };
+
+        for (offset, word) in words(&argument, |c| c == '/' || c.is_whitespace()) {
+            let span = argument_span(node, offset, word.len());
+            self.reference(ReferenceKind::SchemaNode, word, span);
+        }
+    }
+
+    fn reference(&mut self, kind: ReferenceKind, text: &str, span: Span) {
+        let (prefix, name) = match text.split_once(':') {
+            Some((prefix, name)) => (Some(prefix), name),
+            None => (None, text),
+        };
+
+        match self.prefixes.resolve(prefix) {
+            Some(target) => self.references.push(Reference {
+                kind,
+                prefix: prefix.map(str::to_string),
+                name: name.to_string(),
+                target,
+                span,
+            }),
+            None => self.errors.push(ResolutionError {
+                kind: ResolutionErrorKind::UnknownPrefix {
+                    prefix: prefix.unwrap_or_default().to_string(),
+                },
+                span,
+            }),
+        }
+    }
+}
+
+/// Splits the text into words, returning the byte offset of each word
+fn words(text: &str, separator: impl Fn(char) -> bool) -> Vec<(usize, &str)> {
+    let mut words = vec![];
+    let mut start = None;
+
+    for (i, c) in text.char_indices() {
+        match (separator(c), start) {
+            (true, Some(s)) => {
+                words.push((s, &text[s..i]));
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        words.push((s, &text[s..]));
+    }
+
+    words
+}
+
+/// Returns the span of part of the argument of a node
+///
+/// The offset and length refer to the decoded argument. This maps exactly onto the source when the
+/// argument is unquoted, or a single quoted string without escapes or line breaks, otherwise the
+/// span of the whole argument is returned.
+///
+pub fn argument_span(node: &Node, offset: usize, length: usize) -> Span {
+    let value_span = match node.value_span() {
+        Some(span) => span,
+        None => return node.span(),
+    };
+
+    let start = match node.value() {
+        Some(NodeValue::String(parts)) => match parts.as_slice() {
+            [part] if part.unquoted() == part.value => value_span.0 + 1,
+            _ => return value_span,
+        },
+        Some(_) => value_span.0,
+        None => return value_span,
+    };
+
+    (start + offset, start + offset + length.max(1) - 1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    #[test]
+    fn resolution() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            concat!(
+                "module main {\n",
+                "  namespace \"urn:main\";\n",
+                "  prefix m;\n",
+                "  import ietf-inet-types { prefix inet; }\n",
+                "  inet:annotation foo;\n",
+                "  leaf address {\n",
+                "    type inet:ip-address;\n",
+                "    if-feature \"m:a and (not b or x:c)\";\n",
+                "  }\n",
+                "  augment \"/m:top/bad:child\" {\n",
+                "    uses grp;\n",
+                "  }\n",
+                "}\n",
+            ),
+        );
+        repository.add_source(
+            "ietf-inet-types.yang",
+            "module ietf-inet-types { namespace \"urn:inet\"; prefix inet; }",
+        );
+
+        let id = repository
+            .load("main", None)
+            .expect("Failed to load module");
+        let inet = repository.find("ietf-inet-types", None).unwrap();
+
+        let (references, errors) = resolve_prefixes(&repository, id);
+
+        let summary: Vec<_> = references
+            .iter()
+            .map(|r| {
+                (
+                    r.kind,
+                    r.prefix.as_deref(),
+                    r.name.as_str(),
+                    r.target,
+                    r.span,
+                )
+            })
+            .collect();
+
+        use PrefixTarget::*;
+        use ReferenceKind::*;
+
+        assert_eq!(
+            vec![
+                (
+                    Extension,
+                    Some("inet"),
+                    "annotation",
+                    Import(inet),
+                    (94, 108)
+                ),
+                (Type, Some("inet"), "ip-address", Import(inet), (141, 155)),
+                (IfFeature, Some("m"), "a", Local, (174, 176)),
+                (IfFeature, None, "b", Local, (187, 187)),
+                (SchemaNode, Some("m"), "top", Local, (215, 219)),
+                (Uses, None, "grp", Local, (243, 245)),
+            ],
+            summary
+        );
+
+        assert_eq!(
+            vec![
+                ResolutionError {
+                    kind: ResolutionErrorKind::UnknownPrefix {
+                        prefix: "x".to_string()
+                    },
+                    span: (192, 194),
+                },
+                ResolutionError {
+                    kind: ResolutionErrorKind::UnknownPrefix {
+                        prefix: "bad".to_string()
+                    },
+                    span: (221, 229),
+                },
+            ],
+            errors
+        );
+    }
+
+    #[test]
+    fn duplicate_prefixes() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            concat!(
+                "module main { namespace \"urn:main\"; prefix m; import a { prefix m; }\n",
+                "  import b { prefix x; } import c { prefix x; } }",
+            ),
+        );
+        repository.add_source("a.yang", "module a { namespace \"urn:a\"; prefix a; }");
+        repository.add_source("b.yang", "module b { namespace \"urn:b\"; prefix b; }");
+        repository.add_source("c.yang", "module c { namespace \"urn:c\"; prefix c; }");
+
+        let id = repository
+            .load("main", None)
+            .expect("Failed to load module");
+        let (_, errors) = resolve_prefixes(&repository, id);
+
+        assert_eq!(
+            vec![
+                ResolutionError {
+                    kind: ResolutionErrorKind::DuplicatePrefix {
+                        prefix: "m".to_string()
+                    },
+                    span: (64, 64),
+                },
+                ResolutionError {
+                    kind: ResolutionErrorKind::DuplicatePrefix {
+                        prefix: "x".to_string()
+                    },
+                    span: (112, 112),
+                },
+            ],
+            errors
+        );
+
+        // The module's own prefix and the first import with a prefix win
+        let b = repository.find("b", None).unwrap();
+        let prefixes = PrefixMap::new(repository.get(id));
+        assert_eq!(Some(PrefixTarget::Local), prefixes.resolve(Some("m")));
+        assert_eq!(Some(PrefixTarget::Import(b)), prefixes.resolve(Some("x")));
+    }
+}
@@ -0,0 +1,274 @@
+//
+// Range and length expressions
+//
+// The arguments of "range" and "length" (RFC 7950 sections 9.2.4 and 9.4.4) are lists of
+// intervals such as "1..10 | 20..max", where "min" and "max" stand for the bounds of the type
+// being restricted. Each restriction in a chain of typedefs must be a subset of the one before it,
+// starting with the value space of the built-in type.
+//
+// Values are represented as i128, which holds every int64 and uint64 value. Decimal64 values are
+// scaled by their fraction digits, so "2.50" with two fraction digits is 250.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::types::BuiltinType;
+
+/// An inclusive interval of values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interval {
+    pub min: i128,
+    pub max: i128,
+}
+
+/// A set of values, made up of disjoint intervals in ascending order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ranges {
+    pub intervals: Vec<Interval>,
+
+    /// The number of fraction digits that values are scaled by
+    pub scale: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeError {
+    /// The argument doesn't follow the "range-arg" or "length-arg" syntax
+    Syntax,
+
+    /// The bounds of the intervals are not in ascending order, or the intervals overlap
+    NotAscending,
+
+    /// A value that is outside the ranges of the type being restricted
+    NotSubset,
+}
+
+impl Display for RangeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeError::Syntax => write!(f, "Invalid syntax"),
+            RangeError::NotAscending => write!(f, "The bounds must be in ascending order"),
+            RangeError::NotSubset => write!(f, "Not a subset of the base type's ranges"),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+impl Ranges {
+    /// Returns the value space of a built-in type with a "range" restriction
+    ///
+    /// The fraction digits are only used for decimal64, whose value space is the int64 range
+    /// scaled down by them.
+    ///
+    pub fn builtin(builtin: BuiltinType, fraction_digits: u8) -> Option<Self> {
+        let (min, max, scale) = match builtin {
+            BuiltinType::Int8 => (i8::MIN as i128, i8::MAX as i128, 0),
+            BuiltinType::Int16 => (i16::MIN as i128, i16::MAX as i128, 0),
+            BuiltinType::Int32 => (i32::MIN as i128, i32::MAX as i128, 0),
+            BuiltinType::Int64 => (i64::MIN as i128, i64::MAX as i128, 0),
+            BuiltinType::Uint8 => (0, u8::MAX as i128, 0),
+            BuiltinType::Uint16 => (0, u16::MAX as i128, 0),
+            BuiltinType::Uint32 => (0, u32::MAX as i128, 0),
+            BuiltinType::Uint64 => (0, u64::MAX as i128, 0),
+            BuiltinType::Decimal64 => (i64::MIN as i128, i64::MAX as i128, fraction_digits),
+            _ => return None,
+        };
+
+        Some(Self {
+            intervals: vec![Interval { min, max }],
+            scale: scale as u32,
+        })
+    }
+
+    /// Returns the lengths allowed for a string or binary without a "length" restriction
+    pub fn length() -> Self {
+        Self {
+            intervals: vec![Interval {
+                min: 0,
+                max: u64::MAX as i128,
+            }],
+            scale: 0,
+        }
+    }
+
+    /// Parses a "range" or "length" argument that restricts these ranges
+    pub fn restrict(&self, text: &str) -> Result<Ranges, RangeError> {
+        let lowest = self.intervals.first().ok_or(RangeError::NotSubset)?.min;
+        let highest = self.intervals.last().ok_or(RangeError::NotSubset)?.max;
+
+        let boundary = |text: &str| match text.trim() {
+            "min" => Ok(lowest),
+            "max" => Ok(highest),
+            text => parse_number(text, self.scale).ok_or(RangeError::Syntax),
+        };
+
+        let mut intervals: Vec<Interval> = vec![];
+
+        for part in text.split('|') {
+            let interval = match part.split_once("..") {
+                Some((min, max)) => Interval {
+                    min: boundary(min)?,
+                    max: boundary(max)?,
+                },
+                None => {
+                    let value = boundary(part)?;
+                    Interval {
+                        min: value,
+                        max: value,
+                    }
+                }
+            };
+
+            if interval.min > interval.max
+                || intervals
+                    .last()
+                    .is_some_and(|last| interval.min <= last.max)
+            {
+                return Err(RangeError::NotAscending);
+            }
+
+            intervals.push(interval);
+        }
+
+        let is_subset = intervals.iter().all(|interval| {
+            self.intervals
+                .iter()
+                .any(|parent| parent.min <= interval.min && interval.max <= parent.max)
+        });
+
+        if !is_subset {
+            return Err(RangeError::NotSubset);
+        }
+
+        Ok(Ranges {
+            intervals,
+            scale: self.scale,
+        })
+    }
+
+    pub fn contains(&self, value: i128) -> bool {
+        self.intervals
+            .iter()
+            .any(|interval| interval.min <= value && value <= interval.max)
+    }
+
+    /// Parses a value in the lexical representation of RFC 7950 section 9 and checks that it's
+    /// within the ranges, returning None if it isn't a valid number
+    pub fn contains_value(&self, text: &str) -> Option<bool> {
+        parse(text, self.scale, true).map(|value| self.contains(value))
+    }
+}
+
+/// Parses a bound of a "range" or "length" argument, which is an integer, or a decimal number
+/// scaled by the given number of fraction digits
+///
+/// Returns None for decimal numbers with more fraction digits than the scale, for any decimal
+/// point if the scale is 0, and for a "+" sign or leading zeros, which the "range-arg" and
+/// "length-arg" syntax doesn't allow.
+///
+pub fn parse_number(text: &str, scale: u32) -> Option<i128> {
+    parse(text, scale, false)
+}
+
+/// Parses a number, where `lexical` allows the "+" sign and leading zeros of the lexical
+/// representation of values in RFC 7950 sections 9.2.1 and 9.3.1
+fn parse(text: &str, scale: u32, lexical: bool) -> Option<i128> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None if lexical => (false, text.strip_prefix('+').unwrap_or(text)),
+        None => (false, text),
+    };
+
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) if scale > 0 && !fraction.is_empty() => (integer, fraction),
+        Some(_) => return None,
+        None => (digits, ""),
+    };
+
+    let all_digits = |text: &str| text.bytes().all(|b| b.is_ascii_digit());
+
+    if integer.is_empty() || !all_digits(integer) || !all_digits(fraction) {
+        return None;
+    }
+
+    if !lexical && integer.len() > 1 && integer.starts_with('0') {
+        return None;
+    }
+
+    let fraction = fraction.trim_end_matches('0');
+
+    if fraction.len() as u32 > scale {
+        return None;
+    }
+
+    let padding = 10i128.checked_pow(scale - fraction.len() as u32)?;
+    let value = format!("{}{}", integer, fraction)
+        .parse::<i128>()
+        .ok()?
+        .checked_mul(padding)?;
+
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ranges() {
+        let uint8 = Ranges::builtin(BuiltinType::Uint8, 0).unwrap();
+        let ranges = uint8.restrict("1..10 | 20 .. max").unwrap();
+
+        assert_eq!(
+            vec![Interval { min: 1, max: 10 }, Interval { min: 20, max: 255 }],
+            ranges.intervals
+        );
+        assert!(ranges.contains(5));
+        assert!(!ranges.contains(15));
+        assert_eq!(Some(true), ranges.contains_value("255"));
+        assert_eq!(None, ranges.contains_value("abc"));
+
+        let derived = ranges.restrict("min..5 | 30").unwrap();
+        assert_eq!(
+            vec![Interval { min: 1, max: 5 }, Interval { min: 30, max: 30 }],
+            derived.intervals
+        );
+
+        assert_eq!(Err(RangeError::NotSubset), ranges.restrict("5..15"));
+        assert_eq!(Err(RangeError::NotSubset), uint8.restrict("-1..5"));
+        assert_eq!(Err(RangeError::NotAscending), uint8.restrict("10..1"));
+        assert_eq!(
+            Err(RangeError::NotAscending),
+            uint8.restrict("1..10 | 5..20")
+        );
+        assert_eq!(Err(RangeError::Syntax), uint8.restrict("1..x"));
+        assert_eq!(Err(RangeError::Syntax), uint8.restrict("1.5"));
+        assert_eq!(Err(RangeError::Syntax), uint8.restrict("1.0"));
+        assert_eq!(Err(RangeError::Syntax), uint8.restrict("+1..5"));
+        assert_eq!(Err(RangeError::Syntax), uint8.restrict("1..007"));
+        assert_eq!(None, uint8.contains_value("1.0"));
+        assert_eq!(Some(true), uint8.contains_value("+1"));
+        assert_eq!(Some(true), uint8.contains_value("007"));
+        assert_eq!(None, uint8.contains_value("+-1"));
+
+        let decimal = Ranges::builtin(BuiltinType::Decimal64, 2).unwrap();
+        let ranges = decimal.restrict("-1.5..2.25").unwrap();
+        assert_eq!(
+            vec![Interval {
+                min: -150,
+                max: 225
+            }],
+            ranges.intervals
+        );
+        assert_eq!(Some(true), ranges.contains_value("2.20"));
+        assert_eq!(Some(false), ranges.contains_value("2.26"));
+        assert_eq!(None, ranges.contains_value("2.255"));
+        assert_eq!(Some(true), ranges.contains_value("+2"));
+        assert_eq!(
+            Ok(vec![Interval { min: 0, max: 50 }]),
+            decimal.restrict("0..0.5").map(|r| r.intervals)
+        );
+        assert_eq!(Err(RangeError::Syntax), decimal.restrict("00.5"));
+    }
+}
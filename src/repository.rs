@@ -0,0 +1,790 @@
+//
+// Module repository
+//
+// YANG modules import other modules and include submodules by name, so parsing a single file is
+// rarely enough. The repository locates modules in a list of search directories, using the
+// "name.yang" and "name@revision.yang" file naming conventions from RFC 7950 section 5.2, parses
+// each of them once and links every import and include to the module it refers to.
+//
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::{Path, PathBuf};
+
+use crate::arguments::{ArgumentType, RevisionDate};
+use crate::ast::{substatement_argument, AstError, Document};
+use crate::errors::ParseError;
+use crate::parsing::{parse, RootNode};
+
+/// Identifies a module in a repository
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ModuleId(pub usize);
+
+/// A parsed module or submodule, along with the modules it's linked to
+#[derive(Debug)]
+pub struct LoadedModule {
+    pub name: String,
+
+    /// The most recent revision of the module, if it has any revision statements
+    pub revision: Option<RevisionDate>,
+
+    pub path: PathBuf,
+    pub kind: ModuleKind,
+
+    /// The prefix of the module itself, from "prefix" or "belongs-to"
+    pub prefix: String,
+
+    pub imports: Vec<LinkedImport>,
+    pub includes: Vec<ModuleId>,
+    pub root: RootNode,
+}
+
+impl LoadedModule {
+    /// Returns the namespace of the module, which is None for submodules
+    pub fn namespace(&self) -> Option<Cow<'_, str>> {
+        let module = self.root.children.iter().find(|node| node.is("module"))?;
+        substatement_argument(module, "namespace")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleKind {
+    Module,
+    Submodule {
+        belongs_to: String,
+
+        /// The module that includes the submodule, or None if no loaded module includes it
+        module: Option<ModuleId>,
+    },
+}
+
+/// An import statement, linked to the imported module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkedImport {
+    pub prefix: String,
+    pub module: ModuleId,
+}
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    /// No file with the module was found in the search paths
+    NotFound {
+        name: String,
+        revision: Option<RevisionDate>,
+    },
+
+    Io {
+        path: PathBuf,
+        error: std::io::Error,
+    },
+
+    Parse {
+        path: PathBuf,
+        error: ParseError,
+    },
+
+    Ast {
+        path: PathBuf,
+        error: AstError,
+    },
+
+    /// The file doesn't contain the module it's named after, or a submodule was imported, or a
+    /// module was included
+    UnexpectedModule {
+        path: PathBuf,
+        expected: String,
+        found: String,
+    },
+
+    /// An included submodule belongs to a different module
+    WrongParent {
+        path: PathBuf,
+        submodule: String,
+        belongs_to: String,
+        expected: String,
+    },
+
+    /// A chain of imports or includes that leads back to where it started
+    Cycle {
+        chain: Vec<String>,
+    },
+}
+
+impl Display for RepositoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RepositoryError::NotFound {
+                name,
+                revision: Some(revision),
+            } => write!(f, "Module {:?} with revision {} not found", name, revision),
+            RepositoryError::NotFound {
+                name,
+                revision: None,
+            } => write!(f, "Module {:?} not found", name),
+            RepositoryError::Io { path, error } => {
+                write!(f, "Failed to read {}: {}", path.display(), error)
+            }
+            RepositoryError::Parse { path, error } => {
+                write!(f, "Failed to parse {}: {}", path.display(), error)
+            }
+            RepositoryError::Ast { path, error } => {
+                write!(f, "Invalid module in {}: {}", path.display(), error)
+            }
+            RepositoryError::UnexpectedModule {
+                path,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Expected to find {} in {}, found {}",
+                expected,
+                path.display(),
+                found
+            ),
+            RepositoryError::WrongParent {
+                path,
+                submodule,
+                belongs_to,
+                expected,
+            } => write!(
+                f,
+                "Submodule {:?} in {} belongs to {:?}, not {:?}",
+                submodule,
+                path.display(),
+                belongs_to,
+                expected
+            ),
+            RepositoryError::Cycle { chain } => {
+                write!(f, "Circular imports or includes: {}", chain.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+/// What a module is loaded as, used to check that the file contains the right kind of module
+#[derive(Clone, Copy)]
+enum Expected<'a> {
+    Module,
+    Submodule { of: &'a str },
+    Any,
+}
+
+/// A file that may contain a module, either an added source or a file in a search directory
+enum Candidate {
+    Source(String),
+    File(PathBuf),
+}
+
+/// A set of modules, loaded on demand from a list of search directories
+#[derive(Debug, Default)]
+pub struct ModuleRepository {
+    search_paths: Vec<PathBuf>,
+
+    /// In-memory files, searched before the directories
+    sources: HashMap<String, Vec<u8>>,
+
+    modules: Vec<LoadedModule>,
+}
+
+impl ModuleRepository {
+    pub fn new<P: Into<PathBuf>>(search_paths: impl IntoIterator<Item = P>) -> Self {
+        Self {
+            search_paths: search_paths.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Adds an in-memory file, which is found as if it was in one of the search directories
+    ///
+    /// The filename must follow the same naming conventions as files on disk.
+    ///
+    pub fn add_source(&mut self, filename: impl Into<String>, buffer: impl Into<Vec<u8>>) {
+        self.sources.insert(filename.into(), buffer.into());
+    }
+
+    /// Loads a module by name, along with everything it imports and includes
+    ///
+    /// Without a revision, the most recent revision found is loaded, unless a module with the
+    /// name is already loaded.
+    ///
+    pub fn load(
+        &mut self,
+        name: &str,
+        revision: Option<RevisionDate>,
+    ) -> Result<ModuleId, RepositoryError> {
+        self.load_module(name, revision, Expected::Module, &mut vec![])
+    }
+
+    /// Loads a module from the given file, along with everything it imports and includes
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> Result<ModuleId, RepositoryError> {
+        let path = path.as_ref().to_path_buf();
+
+        if let Some(id) = self.modules.iter().position(|module| module.path == path) {
+            return Ok(ModuleId(id));
+        }
+
+        let buffer = std::fs::read(&path).map_err(|error| RepositoryError::Io {
+            path: path.clone(),
+            error,
+        })?;
+
+        let root = parse_file(&path, &buffer)?;
+        let id = self.load_root(path, root, None, Expected::Any, &mut vec![])?;
+
+        Ok(id.expect("Any revision is accepted when none is requested"))
+    }
+
+    pub fn get(&self, id: ModuleId) -> &LoadedModule {
+        &self.modules[id.0]
+    }
+
+    /// Finds a loaded module by name, and revision if given
+    pub fn find(&self, name: &str, revision: Option<RevisionDate>) -> Option<ModuleId> {
+        self.modules
+            .iter()
+            .position(|module| {
+                module.name == name && (revision.is_none() || module.revision == revision)
+            })
+            .map(ModuleId)
+    }
+
+    /// Returns all loaded modules, in the order they finished loading
+    ///
+    /// A module always comes after everything it imports and includes.
+    ///
+    pub fn modules(&self) -> impl Iterator<Item = (ModuleId, &LoadedModule)> {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(id, module)| (ModuleId(id), module))
+    }
+
+    fn load_module(
+        &mut self,
+        name: &str,
+        revision: Option<RevisionDate>,
+        expected: Expected,
+        stack: &mut Vec<String>,
+    ) -> Result<ModuleId, RepositoryError> {
+        if let Some(id) = self.find(name, revision) {
+            return Ok(id);
+        }
+
+        // Unreadable files are skipped in favour of the other candidates, and only reported if
+        // none of them is the module
+        let mut read_error = None;
+
+        for candidate in self.locate(name, revision) {
+            let (path, root) = match candidate {
+                Candidate::Source(filename) => {
+                    let path = PathBuf::from(&filename);
+                    let root = parse_file(&path, &self.sources[&filename])?;
+                    (path, root)
+                }
+                Candidate::File(path) => match std::fs::read(&path) {
+                    Ok(buffer) => {
+                        let root = parse_file(&path, &buffer)?;
+                        (path, root)
+                    }
+                    Err(error) => {
+                        read_error.get_or_insert(RepositoryError::Io { path, error });
+                        continue;
+                    }
+                },
+            };
+
+            match self.load_root(path, root, revision, expected, stack)? {
+                Some(id) => return Ok(id),
+                None => continue,
+            }
+        }
+
+        Err(read_error.unwrap_or_else(|| RepositoryError::NotFound {
+            name: name.to_string(),
+            revision,
+        }))
+    }
+
+    /// Returns the candidate files for a module, most recent revision first
+    ///
+    /// Nothing is read here, so each candidate is only read when it's tried.
+    ///
+    fn locate(&self, name: &str, revision: Option<RevisionDate>) -> Vec<Candidate> {
+        let mut candidates: Vec<(Option<RevisionDate>, PathBuf, Candidate)> = vec![];
+
+        for filename in self.sources.keys() {
+            if let Some(file_revision) = match_filename(filename, name) {
+                let candidate = Candidate::Source(filename.clone());
+                candidates.push((file_revision, PathBuf::from(filename), candidate));
+            }
+        }
+
+        for directory in self.search_paths.iter() {
+            let entries = match std::fs::read_dir(directory) {
+                Ok(entries) => entries,
+                // Missing search directories are simply skipped
+                Err(_) => continue,
+            };
+
+            for entry in entries.flatten() {
+                let filename = entry.file_name();
+
+                if let Some(file_revision) = filename.to_str().and_then(|f| match_filename(f, name))
+                {
+                    candidates.push((file_revision, entry.path(), Candidate::File(entry.path())));
+                }
+            }
+        }
+
+        // Files named after the requested revision are the most likely match, otherwise the most
+        // recent revision wins, with files without a revision in the name last
+        candidates.sort_by(|(a, a_path, _), (b, b_path, _)| {
+            let exact = |r: &Option<RevisionDate>| revision.is_some() && *r == revision;
+            exact(b)
+                .cmp(&exact(a))
+                .then(b.cmp(a))
+                .then(a_path.cmp(b_path))
+        });
+
+        candidates
+            .into_iter()
+            .map(|(_, _, candidate)| candidate)
+            .collect()
+    }
+
+    /// Links a parsed module, or returns None if it's not the requested revision
+    ///
+    /// A module that is already loaded, such as one that was found by name before being loaded
+    /// by path, is returned as is.
+    ///
+    fn load_root(
+        &mut self,
+        path: PathBuf,
+        root: RootNode,
+        revision: Option<RevisionDate>,
+        expected: Expected,
+        stack: &mut Vec<String>,
+    ) -> Result<Option<ModuleId>, RepositoryError> {
+        let header = ModuleHeader::read(&root).map_err(|error| RepositoryError::Ast {
+            path: path.clone(),
+            error,
+        })?;
+
+        if revision.is_some() && header.revision != revision {
+            return Ok(None);
+        }
+
+        let found = match &header.belongs_to {
+            Some(_) => format!("submodule {:?}", header.name),
+            None => format!("module {:?}", header.name),
+        };
+
+        match (expected, &header.belongs_to) {
+            (Expected::Module, None) | (Expected::Any, _) => {}
+            (Expected::Submodule { of }, Some(belongs_to)) => {
+                if belongs_to != of {
+                    return Err(RepositoryError::WrongParent {
+                        path,
+                        submodule: header.name,
+                        belongs_to: belongs_to.clone(),
+                        expected: of.to_string(),
+                    });
+                }
+            }
+            (Expected::Module, Some(_)) => {
+                return Err(RepositoryError::UnexpectedModule {
+                    path,
+                    expected: "a module".to_string(),
+                    found,
+                })
+            }
+            (Expected::Submodule { .. }, None) => {
+                return Err(RepositoryError::UnexpectedModule {
+                    path,
+                    expected: "a submodule".to_string(),
+                    found,
+                })
+            }
+        }
+
+        let loaded = self
+            .modules
+            .iter()
+            .position(|module| module.name == header.name && module.revision == header.revision);
+
+        if let Some(id) = loaded {
+            return Ok(Some(ModuleId(id)));
+        }
+
+        if let Some(position) = stack.iter().position(|name| *name == header.name) {
+            let mut chain = stack[position..].to_vec();
+            chain.push(header.name);
+            return Err(RepositoryError::Cycle { chain });
+        }
+
+        stack.push(header.name.clone());
+
+        let module_name = header.belongs_to.as_deref().unwrap_or(&header.name);
+        let mut imports = vec![];
+        let mut includes = vec![];
+
+        for (name, prefix, revision) in header.imports.iter() {
+            let module = self.load_module(name, *revision, Expected::Module, stack)?;
+            imports.push(LinkedImport {
+                prefix: prefix.clone(),
+                module,
+            });
+        }
+
+        for (name, revision) in header.includes.iter() {
+            let expected = Expected::Submodule { of: module_name };
+            includes.push(self.load_module(name, *revision, expected, stack)?);
+        }
+
+        stack.pop();
+
+        self.modules.push(LoadedModule {
+            name: header.name,
+            revision: header.revision,
+            path,
+            kind: match header.belongs_to {
+                Some(belongs_to) => ModuleKind::Submodule {
+                    belongs_to,
+                    module: None,
+                },
+                None => ModuleKind::Module,
+            },
+            prefix: header.prefix,
+            imports,
+            includes,
+            root,
+        });
+
+        let id = ModuleId(self.modules.len() - 1);
+
+        // Submodules are linked to the module that includes them, directly or through other
+        // submodules, since several revisions of it may be loaded
+        if self.modules[id.0].kind == ModuleKind::Module {
+            let mut pending = self.modules[id.0].includes.clone();
+
+            while let Some(include) = pending.pop() {
+                let submodule = &mut self.modules[include.0];
+
+                if let ModuleKind::Submodule { module, .. } = &mut submodule.kind {
+                    if module.replace(id) != Some(id) {
+                        pending.extend(submodule.includes.iter().copied());
+                    }
+                }
+            }
+        }
+
+        Ok(Some(id))
+    }
+}
+
+fn parse_file(path: &Path, buffer: &[u8]) -> Result<RootNode, RepositoryError> {
+    parse(buffer).map_err(|error| RepositoryError::Parse {
+        path: path.to_path_buf(),
+        error,
+    })
+}
+
+/// Returns the revision in the filename if the filename matches the module name
+///
+/// The outer option is None if the filename doesn't match, the inner option is None if the
+/// filename doesn't contain a revision.
+///
+fn match_filename(filename: &str, name: &str) -> Option<Option<RevisionDate>> {
+    let stem = filename.strip_suffix(".yang")?;
+
+    if stem == name {
+        return Some(None);
+    }
+
+    let revision = stem.strip_prefix(name)?.strip_prefix('@')?;
+    RevisionDate::parse(revision).map(Some)
+}
+
+/// The parts of a module needed to locate and link its dependencies
+struct ModuleHeader {
+    name: String,
+    revision: Option<RevisionDate>,
+    prefix: String,
+    belongs_to: Option<String>,
+    imports: Vec<(String, String, Option<RevisionDate>)>,
+    includes: Vec<(String, Option<RevisionDate>)>,
+}
+
+impl ModuleHeader {
+    fn read(root: &RootNode) -> Result<Self, AstError> {
+        let document = Document::try_from(root)?;
+
+        let date = |text: Option<Cow<str>>| text.and_then(|t| RevisionDate::parse(&t));
+
+        let (revisions, imports, includes, prefix, belongs_to) = match document {
+            Document::Module(module) => (
+                module
+                    .revisions()
+                    .filter_map(|r| RevisionDate::parse(&r.date()))
+                    .max(),
+                module.imports().collect::<Vec<_>>(),
+                module.includes().collect::<Vec<_>>(),
+                module.prefix().unwrap_or_default().into_owned(),
+                None,
+            ),
+            Document::Submodule(submodule) => (
+                submodule
+                    .revisions()
+                    .filter_map(|r| RevisionDate::parse(&r.date()))
+                    .max(),
+                submodule.imports().collect::<Vec<_>>(),
+                submodule.includes().collect::<Vec<_>>(),
+                submodule
+                    .belongs_to()
+                    .and_then(|b| b.prefix())
+                    .unwrap_or_default()
+                    .into_owned(),
+                submodule.belongs_to().map(|b| b.module_name().into_owned()),
+            ),
+        };
+
+        Ok(Self {
+            name: document.name().into_owned(),
+            revision: revisions,
+            prefix,
+            belongs_to,
+            imports: imports
+                .iter()
+                .map(|import| {
+                    (
+                        import.module_name().into_owned(),
+                        import.prefix().unwrap_or_default().into_owned(),
+                        date(import.revision_date()),
+                    )
+                })
+                .collect(),
+            includes: includes
+                .iter()
+                .map(|include| {
+                    (
+                        include.module_name().into_owned(),
+                        date(include.revision_date()),
+                    )
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn date(text: &str) -> Option<RevisionDate> {
+        RevisionDate::parse(text)
+    }
+
+    #[test]
+    fn imports_and_includes() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            r#"
+            module main {
+                namespace "urn:main";
+                prefix m;
+                import types { prefix t; revision-date 2020-01-01; }
+                import common { prefix c; }
+                include main-sub;
+            }
+            "#,
+        );
+        repository.add_source(
+            "main-sub.yang",
+            r#"
+            submodule main-sub {
+                belongs-to main { prefix m; }
+                import common { prefix cmn; }
+            }
+            "#,
+        );
+        repository.add_source(
+            "types@2020-01-01.yang",
+            "module types { namespace \"urn:t\"; prefix t; revision 2020-01-01; }",
+        );
+        repository.add_source(
+            "types@2021-01-01.yang",
+            "module types { namespace \"urn:t\"; prefix t; revision 2021-01-01; }",
+        );
+        repository.add_source(
+            "common@2019-01-01.yang",
+            "module common { namespace \"urn:c\"; prefix c; revision 2019-01-01; }",
+        );
+        repository.add_source(
+            "common@2022-01-01.yang",
+            "module common { namespace \"urn:c\"; prefix c; revision 2022-01-01; }",
+        );
+
+        let id = repository
+            .load("main", None)
+            .expect("Failed to load module");
+        let main = repository.get(id);
+
+        assert_eq!("main", main.name);
+        assert_eq!(ModuleKind::Module, main.kind);
+        assert_eq!("m", main.prefix);
+        assert_eq!(Some("urn:main".into()), main.namespace());
+
+        let imports: Vec<_> = main
+            .imports
+            .iter()
+            .map(|import| {
+                let module = repository.get(import.module);
+                (
+                    import.prefix.as_str(),
+                    module.name.as_str(),
+                    module.revision,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("t", "types", date("2020-01-01")),
+                ("c", "common", date("2022-01-01")),
+            ],
+            imports
+        );
+
+        let submodule = repository.get(main.includes[0]);
+        assert_eq!(
+            ModuleKind::Submodule {
+                belongs_to: "main".to_string(),
+                module: Some(id),
+            },
+            submodule.kind
+        );
+
+        // The submodule import is linked to the already loaded module
+        assert_eq!(main.imports[1].module, submodule.imports[0].module);
+
+        // Every module is parsed once
+        assert_eq!(4, repository.modules().count());
+
+        // With several revisions of a module loaded, each submodule is linked to its own
+        repository.add_source(
+            "main@2020-01-01.yang",
+            r#"
+            module main {
+                namespace "urn:main";
+                prefix m;
+                revision 2020-01-01;
+                include main-sub { revision-date 2020-01-01; }
+            }
+            "#,
+        );
+        repository.add_source(
+            "main-sub@2020-01-01.yang",
+            "submodule main-sub { belongs-to main { prefix m; } revision 2020-01-01; }",
+        );
+
+        let old = repository
+            .load("main", date("2020-01-01"))
+            .expect("Failed to load module");
+        let old_sub = repository.get(old).includes[0];
+        assert_ne!(old_sub, repository.get(id).includes[0]);
+        assert_eq!(old, crate::schema::main_module(&repository, old_sub));
+        assert_eq!(
+            id,
+            crate::schema::main_module(&repository, repository.get(id).includes[0])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "a.yang",
+            "module a { namespace \"urn:a\"; prefix a; import b { prefix b; } }",
+        );
+        repository.add_source(
+            "b.yang",
+            "module b { namespace \"urn:b\"; prefix b; import a { prefix a; } }",
+        );
+        repository.add_source(
+            "c.yang",
+            "module c { namespace \"urn:c\"; prefix c; import missing { prefix m; } }",
+        );
+        repository.add_source(
+            "d.yang",
+            "module d { namespace \"urn:d\"; prefix d; include e; }",
+        );
+        repository.add_source("e.yang", "submodule e { belongs-to other { prefix o; } }");
+        repository.add_source(
+            "f.yang",
+            "module f { namespace \"urn:f\"; prefix f; import e { prefix e; } }",
+        );
+
+        match repository.load("a", None) {
+            Err(RepositoryError::Cycle { chain }) => assert_eq!(vec!["a", "b", "a"], chain),
+            result => panic!("Expected a cycle, got {:?}", result),
+        }
+
+        match repository.load("c", None) {
+            Err(RepositoryError::NotFound { name, revision }) => {
+                assert_eq!(("missing", None), (name.as_str(), revision))
+            }
+            result => panic!("Expected a missing module, got {:?}", result),
+        }
+
+        assert!(matches!(
+            repository.load("d", None),
+            Err(RepositoryError::WrongParent { .. })
+        ));
+        assert!(matches!(
+            repository.load("f", None),
+            Err(RepositoryError::UnexpectedModule { .. })
+        ));
+        assert!(matches!(
+            repository.load("a", date("2000-01-01")),
+            Err(RepositoryError::NotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn search_paths() {
+        let directory =
+            std::env::temp_dir().join(format!("yangparse-repository-test-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("disk.yang"),
+            "module disk { namespace \"urn:disk\"; prefix d; revision 2023-05-01; }",
+        )
+        .unwrap();
+
+        // Unreadable candidates are skipped in favour of the others
+        std::fs::create_dir_all(directory.join("disk@2020-01-01.yang")).unwrap();
+
+        // A copy of a loaded module is not loaded again
+        std::fs::copy(directory.join("disk.yang"), directory.join("copy.yang")).unwrap();
+
+        let mut repository = ModuleRepository::new([directory.join("missing"), directory.clone()]);
+        let result = repository.load("disk", date("2023-05-01"));
+        let copy = repository.load_file(directory.join("copy.yang"));
+
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let id = result.expect("Failed to load module");
+        let module = repository.get(id);
+        assert_eq!(directory.join("disk.yang"), module.path);
+        assert_eq!(date("2023-05-01"), module.revision);
+        assert_eq!(id, copy.expect("Failed to load module"));
+        assert_eq!(1, repository.modules().count());
+    }
+}
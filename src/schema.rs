@@ -0,0 +1,1535 @@
+//
+// Schema compilation
+//
+// The parsed tree represents the source faithfully, but code generators and validators need the
+// effective schema, meaning what the data tree actually looks like once every grouping has been
+// inlined at its "uses" sites (RFC 7950 section 7.13). This module compiles the modules in a
+// repository into such a schema tree.
+//
+// The schema nodes keep their non-schema sub-statements (type, config, must, description and so
+// on) as cloned generic nodes, along with references to where each of them came from. The source
+// is needed to resolve prefixes and typedefs, which are looked up in the scope of the module and
+// grouping the statement was written in, not the one it ended up in.
+//
+
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+
+use crate::arguments::{ArgumentType, DeviateKind, IdentifierRef};
+use crate::lexing::Span;
+use crate::parsing::{Node, StatementKeyword};
+use crate::prefixes::{PrefixMap, PrefixTarget};
+use crate::repository::{ModuleId, ModuleKind, ModuleRepository};
+
+/// Points at a statement in one of the modules of a repository
+///
+/// The path holds the index of each statement within its parent, starting with the index of the
+/// module statement in the root node. Walking up the path gives the lexical scope of the
+/// statement.
+///
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SourceRef {
+    pub module: ModuleId,
+    pub path: Vec<usize>,
+}
+
+impl SourceRef {
+    /// Returns the statement, which must exist in the repository
+    pub fn node<'r>(&self, repository: &'r ModuleRepository) -> &'r Node {
+        let root = &repository.get(self.module).root;
+
+        self.path[1..]
+            .iter()
+            .fold(&root.children[self.path[0]], |node, i| &node.children()[*i])
+    }
+
+    pub fn child(&self, index: usize) -> SourceRef {
+        let mut path = self.path.clone();
+        path.push(index);
+
+        SourceRef {
+            module: self.module,
+            path,
+        }
+    }
+
+    /// Returns the enclosing statement, or None for the module statement
+    pub fn parent(&self) -> Option<SourceRef> {
+        (self.path.len() > 1).then(|| SourceRef {
+            module: self.module,
+            path: self.path[..self.path.len() - 1].to_vec(),
+        })
+    }
+
+    /// Returns the span of the argument of the statement, or the whole statement if it has none
+    pub fn span(&self, repository: &ModuleRepository) -> Span {
+        let node = self.node(repository);
+        node.value_span().unwrap_or_else(|| node.span())
+    }
+}
+
+/// A sub-statement of a schema node which is not a schema node itself, such as "type" or "config"
+#[derive(Debug, Clone)]
+pub struct Property {
+    pub node: Node,
+    pub source: SourceRef,
+}
+
+impl Property {
+    pub fn keyword(&self) -> &str {
+        self.node.keyword().map_or("", StatementKeyword::text)
+    }
+
+    pub fn argument(&self) -> Option<Cow<'_, str>> {
+        self.node.argument()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaNodeKind {
+    Container,
+    Leaf,
+    LeafList,
+    List,
+    Choice,
+    Case,
+    Anydata,
+    Anyxml,
+    Rpc,
+    Action,
+    Input,
+    Output,
+    Notification,
+}
+
+impl SchemaNodeKind {
+    pub fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "container" => Some(SchemaNodeKind::Container),
+            "leaf" => Some(SchemaNodeKind::Leaf),
+            "leaf-list" => Some(SchemaNodeKind::LeafList),
+            "list" => Some(SchemaNodeKind::List),
+            "choice" => Some(SchemaNodeKind::Choice),
+            "case" => Some(SchemaNodeKind::Case),
+            "anydata" => Some(SchemaNodeKind::Anydata),
+            "anyxml" => Some(SchemaNodeKind::Anyxml),
+            "rpc" => Some(SchemaNodeKind::Rpc),
+            "action" => Some(SchemaNodeKind::Action),
+            "input" => Some(SchemaNodeKind::Input),
+            "output" => Some(SchemaNodeKind::Output),
+            "notification" => Some(SchemaNodeKind::Notification),
+            _ => None,
+        }
+    }
+
+    pub fn keyword(&self) -> &'static str {
+        match self {
+            SchemaNodeKind::Container => "container",
+            SchemaNodeKind::Leaf => "leaf",
+            SchemaNodeKind::LeafList => "leaf-list",
+            SchemaNodeKind::List => "list",
+            SchemaNodeKind::Choice => "choice",
+            SchemaNodeKind::Case => "case",
+            SchemaNodeKind::Anydata => "anydata",
+            SchemaNodeKind::Anyxml => "anyxml",
+            SchemaNodeKind::Rpc => "rpc",
+            SchemaNodeKind::Action => "action",
+            SchemaNodeKind::Input => "input",
+            SchemaNodeKind::Output => "output",
+            SchemaNodeKind::Notification => "notification",
+        }
+    }
+}
+
+/// A node in the compiled schema tree
+#[derive(Debug, Clone)]
+pub struct SchemaNode {
+    pub kind: SchemaNodeKind,
+
+    /// The argument of the statement, or the keyword for "input" and "output"
+    pub name: String,
+
+    /// The module whose namespace the node belongs to
+    ///
+    /// This is the module where the node ended up, not where it was written. Nodes from a
+    /// grouping belong to the module with the "uses" statement.
+    ///
+    pub module: ModuleId,
+
+    /// The statement that defined the node
+    pub source: SourceRef,
+
+    pub properties: Vec<Property>,
+
+    /// The "when" statements of the "uses" and "augment" statements the node came from
+    ///
+    /// Unlike a "when" statement on the node itself, the context node of these conditions is the
+    /// parent of the node.
+    ///
+    pub conditions: Vec<Property>,
+
+    pub children: Vec<SchemaNode>,
+}
+
+impl SchemaNode {
+    /// Returns the first property with the given keyword
+    pub fn property(&self, keyword: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.keyword() == keyword)
+    }
+
+    /// Returns all properties with the given keyword
+    pub fn properties<'a>(&'a self, keyword: &'a str) -> impl Iterator<Item = &'a Property> {
+        self.properties
+            .iter()
+            .filter(move |p| p.keyword() == keyword)
+    }
+
+    /// Returns the argument of the first property with the given keyword
+    pub fn argument(&self, keyword: &str) -> Option<Cow<'_, str>> {
+        self.property(keyword).and_then(Property::argument)
+    }
+
+    /// Replaces all properties with the same keyword as the given one
+    pub fn set_property(&mut self, property: Property) {
+        let keyword = property.keyword().to_string();
+        self.properties.retain(|p| p.keyword() != keyword);
+        self.properties.push(property);
+    }
+
+    pub fn child(&self, module: ModuleId, name: &str) -> Option<&SchemaNode> {
+        self.children
+            .iter()
+            .find(|child| child.module == module && child.name == name)
+    }
+
+    pub fn child_mut(&mut self, module: ModuleId, name: &str) -> Option<&mut SchemaNode> {
+        self.children
+            .iter_mut()
+            .find(|child| child.module == module && child.name == name)
+    }
+}
+
+/// The compiled schema of all modules in a repository
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    /// The top level data nodes, RPCs and notifications of all modules
+    pub nodes: Vec<SchemaNode>,
+}
+
+impl Schema {
+    /// Compiles all modules in the repository
+    ///
+    /// Every "uses" statement is expanded, the nodes of top level "augment" statements are grafted
+    /// into their targets, and finally "deviation" statements are applied. Errors don't stop the
+    /// compilation, but the offending statements are left out of the schema.
+    ///
+    pub fn compile(repository: &ModuleRepository) -> (Self, Vec<SchemaError>) {
+        let mut compiler = Compiler::new(repository);
+        let mut schema = Schema::default();
+
+        for (id, module) in repository.modules() {
+            if module.kind != ModuleKind::Module {
+                continue;
+            }
+
+            for member in module_family(repository, id) {
+                if let Some(source) = module_statement(repository, member) {
+                    schema.nodes.extend(compiler.compile_children(&source, id));
+                }
+            }
+        }
+
+        compiler.apply_augments(&mut schema);
+        compiler.apply_deviations(&mut schema);
+
+        (schema, compiler.errors)
+    }
+
+    /// Finds a node by its path of module and node names, including choice and case nodes
+    pub fn node(&self, path: &[(ModuleId, &str)]) -> Option<&SchemaNode> {
+        let ((module, name), rest) = path.split_first()?;
+        let mut node = self
+            .nodes
+            .iter()
+            .find(|node| node.module == *module && node.name == *name)?;
+
+        for (module, name) in rest {
+            node = node.child(*module, name)?;
+        }
+
+        Some(node)
+    }
+
+    pub fn node_mut(&mut self, path: &[(ModuleId, &str)]) -> Option<&mut SchemaNode> {
+        let ((module, name), rest) = path.split_first()?;
+        let mut node = self
+            .nodes
+            .iter_mut()
+            .find(|node| node.module == *module && node.name == *name)?;
+
+        for (module, name) in rest {
+            node = node.child_mut(*module, name)?;
+        }
+
+        Some(node)
+    }
+}
+
+/// Prints the schema as an indented tree of statement keywords and names
+impl Display for Schema {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn print(f: &mut Formatter, node: &SchemaNode, depth: usize) -> fmt::Result {
+            match node.kind {
+                SchemaNodeKind::Input | SchemaNodeKind::Output => {
+                    writeln!(f, "{}{}", "  ".repeat(depth), node.kind.keyword())?
+                }
+                _ => writeln!(
+                    f,
+                    "{}{} {}",
+                    "  ".repeat(depth),
+                    node.kind.keyword(),
+                    node.name
+                )?,
+            }
+
+            for child in node.children.iter() {
+                print(f, child, depth + 1)?;
+            }
+
+            Ok(())
+        }
+
+        for node in self.nodes.iter() {
+            print(f, node, 0)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    pub kind: SchemaErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaErrorKind {
+    UnknownPrefix {
+        prefix: String,
+    },
+    UnknownGrouping {
+        name: String,
+    },
+
+    /// A grouping that uses itself, directly or indirectly
+    RecursiveGrouping {
+        name: String,
+    },
+
+    /// The target of a "refine", "augment" or "deviation" statement doesn't exist
+    TargetNotFound {
+        target: String,
+    },
+
+    /// A "deviate add" of a property that can only occur once and already exists
+    PropertyExists {
+        keyword: String,
+    },
+
+    /// A "deviate replace" or "deviate delete" of a property that doesn't exist
+    PropertyMissing {
+        keyword: String,
+    },
+}
+
+impl Display for SchemaError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            SchemaErrorKind::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            SchemaErrorKind::UnknownGrouping { name } => {
+                write!(f, "Unknown grouping {:?}", name)
+            }
+            SchemaErrorKind::RecursiveGrouping { name } => {
+                write!(f, "The grouping {:?} uses itself", name)
+            }
+            SchemaErrorKind::TargetNotFound { target } => {
+                write!(f, "Target node {:?} not found", target)
+            }
+            SchemaErrorKind::PropertyExists { keyword } => {
+                write!(f, "The target node already has a {:?} statement", keyword)
+            }
+            SchemaErrorKind::PropertyMissing { keyword } => {
+                write!(f, "The target node has no matching {:?} statement", keyword)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Why a definition couldn't be found, see `find_definition`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionError {
+    UnknownPrefix(String),
+    NotFound,
+}
+
+/// Returns the module that includes a submodule, or the module itself
+pub fn main_module(repository: &ModuleRepository, id: ModuleId) -> ModuleId {
+    match &repository.get(id).kind {
+        ModuleKind::Module => id,
+        ModuleKind::Submodule { module, .. } => module.unwrap_or(id),
+    }
+}
+
+/// Returns the main module of a module or submodule, followed by all of its submodules
+pub fn module_family(repository: &ModuleRepository, id: ModuleId) -> Vec<ModuleId> {
+    let mut family = vec![main_module(repository, id)];
+    let mut i = 0;
+
+    while i < family.len() {
+        for include in repository.get(family[i]).includes.iter() {
+            if !family.contains(include) {
+                family.push(*include);
+            }
+        }
+
+        i += 1;
+    }
+
+    family
+}
+
+/// Returns the module or submodule statement of a loaded module
+pub fn module_statement(repository: &ModuleRepository, id: ModuleId) -> Option<SourceRef> {
+    repository
+        .get(id)
+        .root
+        .children
+        .iter()
+        .position(|node| node.is("module") || node.is("submodule"))
+        .map(|index| SourceRef {
+            module: id,
+            path: vec![index],
+        })
+}
+
+/// Returns the top level statements with the given keyword in all modules and submodules
+///
+/// Each statement is returned along with the main module of the module it's in.
+///
+pub fn top_level_statements(
+    repository: &ModuleRepository,
+    keyword: &str,
+) -> Vec<(SourceRef, ModuleId)> {
+    let mut statements = vec![];
+
+    for (id, module) in repository.modules() {
+        if module.kind != ModuleKind::Module {
+            continue;
+        }
+
+        for member in module_family(repository, id) {
+            if let Some(source) = module_statement(repository, member) {
+                for (i, child) in source.node(repository).children().iter().enumerate() {
+                    if child.is(keyword) {
+                        statements.push((source.child(i), id));
+                    }
+                }
+            }
+        }
+    }
+
+    statements
+}
+
+/// Resolves a prefix used in a module to a module, where the local prefix gives the main module
+pub fn resolve_prefix(
+    repository: &ModuleRepository,
+    id: ModuleId,
+    prefix: Option<&str>,
+) -> Option<ModuleId> {
+    match PrefixMap::new(repository.get(id)).resolve(prefix)? {
+        PrefixTarget::Local => Some(main_module(repository, id)),
+        PrefixTarget::Import(import) => Some(import),
+    }
+}
+
+/// Resolves the prefixes of a schema node identifier, such as "/if:interfaces/if:interface"
+///
+/// Returns the module and name of each node in the path, where unprefixed nodes belong to the
+/// main module of the given module.
+///
+pub fn resolve_schema_node_id(
+    repository: &ModuleRepository,
+    id: ModuleId,
+    text: &str,
+) -> Result<Vec<(ModuleId, String)>, SchemaErrorKind> {
+    text.split('/')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| {
+            let (prefix, name) = match segment.split_once(':') {
+                Some((prefix, name)) => (Some(prefix), name),
+                None => (None, segment),
+            };
+
+            match resolve_prefix(repository, id, prefix) {
+                Some(module) => Ok((module, name.to_string())),
+                None => Err(SchemaErrorKind::UnknownPrefix {
+                    prefix: prefix.unwrap_or_default().to_string(),
+                }),
+            }
+        })
+        .collect()
+}
+
+/// Finds a top level definition, such as a grouping or a feature, in a module or its submodules
+pub fn find_top_level(
+    repository: &ModuleRepository,
+    id: ModuleId,
+    keyword: &str,
+    name: &str,
+) -> Option<SourceRef> {
+    module_family(repository, id)
+        .into_iter()
+        .filter_map(|member| module_statement(repository, member))
+        .find_map(|module| find_child(repository, &module, keyword, name))
+}
+
+/// Finds the definition an identifier reference refers to, such as the grouping of a "uses"
+///
+/// Unprefixed references are looked up in the lexical scope of the referring statement, meaning
+/// the enclosing statements from the innermost and out, and finally the top level of the module
+/// and its submodules. Prefixed references are looked up at the top level of the imported module.
+///
+pub fn find_definition(
+    repository: &ModuleRepository,
+    from: &SourceRef,
+    keyword: &str,
+    reference: &str,
+) -> Result<SourceRef, DefinitionError> {
+    let reference = IdentifierRef::parse(reference).ok_or(DefinitionError::NotFound)?;
+    let prefixes = PrefixMap::new(repository.get(from.module));
+
+    match prefixes.resolve(reference.prefix.as_deref()) {
+        None => Err(DefinitionError::UnknownPrefix(
+            reference.prefix.unwrap_or_default(),
+        )),
+        Some(PrefixTarget::Import(id)) => find_top_level(repository, id, keyword, &reference.name)
+            .ok_or(DefinitionError::NotFound),
+        Some(PrefixTarget::Local) => {
+            let mut scope = from.parent();
+
+            while let Some(current) = scope.filter(|scope| scope.path.len() > 1) {
+                if let Some(found) = find_child(repository, &current, keyword, &reference.name) {
+                    return Ok(found);
+                }
+
+                scope = current.parent();
+            }
+
+            find_top_level(repository, from.module, keyword, &reference.name)
+                .ok_or(DefinitionError::NotFound)
+        }
+    }
+}
+
+fn find_child(
+    repository: &ModuleRepository,
+    parent: &SourceRef,
+    keyword: &str,
+    name: &str,
+) -> Option<SourceRef> {
+    parent
+        .node(repository)
+        .children()
+        .iter()
+        .position(|node| node.is(keyword) && node.argument().is_some_and(|arg| arg == name))
+        .map(|index| parent.child(index))
+}
+
+/// Returns true for sub-statements that are kept as properties of a schema node
+fn is_property(node: &Node) -> bool {
+    match node.keyword() {
+        Some(StatementKeyword::Keyword(keyword)) => {
+            SchemaNodeKind::from_keyword(keyword).is_none()
+                && !matches!(keyword.as_str(), "uses" | "typedef" | "grouping")
+        }
+        Some(StatementKeyword::ExtensionKeyword(_)) => true,
+        Some(StatementKeyword::Invalid(_)) | None => false,
+    }
+}
+
+/// Finds a node by the module and name of each node in a path
+pub(crate) fn find_descendant_mut<'a>(
+    nodes: &'a mut [SchemaNode],
+    path: &[(ModuleId, &str)],
+) -> Option<&'a mut SchemaNode> {
+    let ((module, name), rest) = path.split_first()?;
+    let node = nodes
+        .iter_mut()
+        .find(|node| node.module == *module && node.name == *name)?;
+
+    if rest.is_empty() {
+        Some(node)
+    } else {
+        find_descendant_mut(&mut node.children, rest)
+    }
+}
+
+/// Wraps data nodes directly in a choice in implicit case nodes, as described in RFC 7950
+/// section 7.9.2
+pub(crate) fn wrap_cases(parent: SchemaNodeKind, nodes: Vec<SchemaNode>) -> Vec<SchemaNode> {
+    if parent != SchemaNodeKind::Choice {
+        return nodes;
+    }
+
+    nodes
+        .into_iter()
+        .map(|node| match node.kind {
+            SchemaNodeKind::Case => node,
+            _ => SchemaNode {
+                kind: SchemaNodeKind::Case,
+                name: node.name.clone(),
+                module: node.module,
+                source: node.source.clone(),
+                properties: vec![],
+                conditions: vec![],
+                children: vec![node],
+            },
+        })
+        .collect()
+}
+
+pub(crate) struct Compiler<'r> {
+    pub(crate) repository: &'r ModuleRepository,
+    pub(crate) errors: Vec<SchemaError>,
+
+    /// The groupings currently being expanded, used to detect recursion
+    groupings: Vec<SourceRef>,
+}
+
+impl<'r> Compiler<'r> {
+    pub(crate) fn new(repository: &'r ModuleRepository) -> Self {
+        Self {
+            repository,
+            errors: vec![],
+            groupings: vec![],
+        }
+    }
+
+    pub(crate) fn error(&mut self, kind: SchemaErrorKind, source: &SourceRef) {
+        self.errors.push(SchemaError {
+            kind,
+            module: source.module,
+            span: source.span(self.repository),
+        });
+    }
+
+    /// Compiles the schema node sub-statements of a statement, expanding "uses"
+    pub(crate) fn compile_children(
+        &mut self,
+        parent: &SourceRef,
+        namespace: ModuleId,
+    ) -> Vec<SchemaNode> {
+        let mut nodes = vec![];
+
+        for (i, child) in parent.node(self.repository).children().iter().enumerate() {
+            let keyword = match child.keyword() {
+                Some(StatementKeyword::Keyword(keyword)) => keyword,
+                _ => continue,
+            };
+
+            if keyword == "uses" {
+                nodes.extend(self.expand_uses(&parent.child(i), namespace));
+            } else if let Some(kind) = SchemaNodeKind::from_keyword(keyword) {
+                nodes.push(self.compile_node(kind, parent.child(i), namespace));
+            }
+        }
+
+        nodes
+    }
+
+    fn compile_node(
+        &mut self,
+        kind: SchemaNodeKind,
+        source: SourceRef,
+        namespace: ModuleId,
+    ) -> SchemaNode {
+        let node = source.node(self.repository);
+
+        let properties = node
+            .children()
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| is_property(child))
+            .map(|(i, child)| Property {
+                node: child.clone(),
+                source: source.child(i),
+            })
+            .collect();
+
+        let children = self.compile_children(&source, namespace);
+
+        // Input and output have no argument, so they're named after their keyword, the same as
+        // in schema node identifiers
+        let name = match kind {
+            SchemaNodeKind::Input | SchemaNodeKind::Output => kind.keyword().to_string(),
+            _ => node.argument().unwrap_or_default().into_owned(),
+        };
+
+        SchemaNode {
+            kind,
+            name,
+            module: namespace,
+            source,
+            properties,
+            conditions: vec![],
+            children: wrap_cases(kind, children),
+        }
+    }
+
+    fn expand_uses(&mut self, uses: &SourceRef, namespace: ModuleId) -> Vec<SchemaNode> {
+        let node = uses.node(self.repository);
+        let name = node.argument().unwrap_or_default();
+
+        let grouping = match find_definition(self.repository, uses, "grouping", &name) {
+            Ok(grouping) => grouping,
+            Err(DefinitionError::UnknownPrefix(prefix)) => {
+                self.error(SchemaErrorKind::UnknownPrefix { prefix }, uses);
+                return vec![];
+            }
+            Err(DefinitionError::NotFound) => {
+                let name = name.into_owned();
+                self.error(SchemaErrorKind::UnknownGrouping { name }, uses);
+                return vec![];
+            }
+        };
+
+        if self.groupings.contains(&grouping) {
+            let name = name.into_owned();
+            self.error(SchemaErrorKind::RecursiveGrouping { name }, uses);
+            return vec![];
+        }
+
+        self.groupings.push(grouping.clone());
+        let mut nodes = self.compile_children(&grouping, namespace);
+        self.groupings.pop();
+
+        self.inherit(&mut nodes, uses);
+
+        for (i, child) in node.children().iter().enumerate() {
+            if child.is("refine") {
+                self.refine(&mut nodes, &uses.child(i), namespace);
+            } else if child.is("augment") {
+                self.augment_descendant(&mut nodes, &uses.child(i), namespace);
+            }
+        }
+
+        nodes
+    }
+
+    /// Applies the "when" and "if-feature" statements of a "uses" or "augment" to its nodes
+    pub(crate) fn inherit(&self, nodes: &mut [SchemaNode], statement: &SourceRef) {
+        for (i, child) in statement
+            .node(self.repository)
+            .children()
+            .iter()
+            .enumerate()
+        {
+            let property = Property {
+                node: child.clone(),
+                source: statement.child(i),
+            };
+
+            for node in nodes.iter_mut() {
+                if child.is("when") {
+                    node.conditions.push(property.clone());
+                } else if child.is("if-feature") {
+                    node.properties.push(property.clone());
+                }
+            }
+        }
+    }
+
+    /// Resolves the descendant schema node identifier of a "refine" or an "augment" in a "uses"
+    ///
+    /// The nodes of the grouping belong to the namespace of the module with the "uses", even if
+    /// the "uses" itself is inside a grouping of another module, so names in the local module are
+    /// taken to be in that namespace.
+    ///
+    fn resolve_descendant(
+        &mut self,
+        statement: &SourceRef,
+        namespace: ModuleId,
+    ) -> Option<Vec<(ModuleId, String)>> {
+        let target = statement
+            .node(self.repository)
+            .argument()
+            .unwrap_or_default();
+        let local = main_module(self.repository, statement.module);
+
+        match resolve_schema_node_id(self.repository, statement.module, &target) {
+            Ok(path) => Some(
+                path.into_iter()
+                    .map(|(module, name)| match module == local {
+                        true => (namespace, name),
+                        false => (module, name),
+                    })
+                    .collect(),
+            ),
+            Err(kind) => {
+                self.error(kind, statement);
+                None
+            }
+        }
+    }
+
+    fn refine(&mut self, nodes: &mut [SchemaNode], refine: &SourceRef, namespace: ModuleId) {
+        let statement = refine.node(self.repository);
+
+        let path = match self.resolve_descendant(refine, namespace) {
+            Some(path) => path,
+            None => return,
+        };
+        let path: Vec<(ModuleId, &str)> =
+            path.iter().map(|(m, name)| (*m, name.as_str())).collect();
+
+        let node = match find_descendant_mut(nodes, &path) {
+            Some(node) => node,
+            None => {
+                let target = statement.argument().unwrap_or_default().into_owned();
+                self.error(SchemaErrorKind::TargetNotFound { target }, refine);
+                return;
+            }
+        };
+
+        // Refined defaults replace all the defaults of a leaf-list, not just one of them
+        if statement.children().iter().any(|child| child.is("default")) {
+            node.properties
+                .retain(|property| property.keyword() != "default");
+        }
+
+        for (i, child) in statement.children().iter().enumerate() {
+            let property = Property {
+                node: child.clone(),
+                source: refine.child(i),
+            };
+
+            match child.keyword() {
+                Some(StatementKeyword::Keyword(keyword))
+                    if matches!(keyword.as_str(), "must" | "if-feature" | "default") =>
+                {
+                    node.properties.push(property)
+                }
+                Some(StatementKeyword::Keyword(_)) => node.set_property(property),
+                Some(StatementKeyword::ExtensionKeyword(_)) => node.properties.push(property),
+                Some(StatementKeyword::Invalid(_)) | None => {}
+            }
+        }
+    }
+
+    /// Grafts the nodes of top level "augment" statements into their targets
+    ///
+    /// An augment may target nodes added by another augment, so augments whose target is missing
+    /// are retried for as long as the other augments make progress.
+    ///
+    fn apply_augments(&mut self, schema: &mut Schema) {
+        let mut pending = top_level_statements(self.repository, "augment");
+
+        loop {
+            let mut remaining = vec![];
+
+            for (augment, namespace) in pending.iter() {
+                let target = augment.node(self.repository).argument().unwrap_or_default();
+
+                let path = match resolve_schema_node_id(self.repository, augment.module, &target) {
+                    Ok(path) => path,
+                    Err(kind) => {
+                        self.error(kind, augment);
+                        continue;
+                    }
+                };
+
+                let path: Vec<(ModuleId, &str)> =
+                    path.iter().map(|(m, name)| (*m, name.as_str())).collect();
+
+                if schema.node(&path).is_none() {
+                    remaining.push((augment.clone(), *namespace));
+                    continue;
+                }
+
+                let mut children = self.compile_children(augment, *namespace);
+                self.inherit(&mut children, augment);
+
+                let target = schema.node_mut(&path).unwrap();
+                let children = wrap_cases(target.kind, children);
+                target.children.extend(children);
+            }
+
+            if remaining.is_empty() || remaining.len() == pending.len() {
+                pending = remaining;
+                break;
+            }
+
+            pending = remaining;
+        }
+
+        for (augment, _) in pending {
+            let target = augment
+                .node(self.repository)
+                .argument()
+                .unwrap_or_default()
+                .into_owned();
+            self.error(SchemaErrorKind::TargetNotFound { target }, &augment);
+        }
+    }
+
+    /// Applies all top level "deviation" statements, see RFC 7950 section 7.20.3
+    fn apply_deviations(&mut self, schema: &mut Schema) {
+        for (deviation, _) in top_level_statements(self.repository, "deviation") {
+            let statement = deviation.node(self.repository);
+            let target = statement.argument().unwrap_or_default();
+
+            let path = match resolve_schema_node_id(self.repository, deviation.module, &target) {
+                Ok(path) => path,
+                Err(kind) => {
+                    self.error(kind, &deviation);
+                    continue;
+                }
+            };
+
+            let path: Vec<(ModuleId, &str)> =
+                path.iter().map(|(m, name)| (*m, name.as_str())).collect();
+
+            if schema.node(&path).is_none() {
+                let target = target.into_owned();
+                self.error(SchemaErrorKind::TargetNotFound { target }, &deviation);
+                continue;
+            }
+
+            for (i, deviate) in statement.children().iter().enumerate() {
+                if !deviate.is("deviate") {
+                    continue;
+                }
+
+                let kind = match deviate.argument().and_then(|arg| DeviateKind::parse(&arg)) {
+                    Some(kind) => kind,
+                    None => continue,
+                };
+
+                if kind == DeviateKind::NotSupported {
+                    let ((module, name), parent) = path.split_last().unwrap();
+                    let siblings = match parent {
+                        [] => &mut schema.nodes,
+                        parent => &mut schema.node_mut(parent).unwrap().children,
+                    };
+
+                    siblings.retain(|node| !(node.module == *module && node.name == *name));
+                    break;
+                }
+
+                let node = schema.node_mut(&path).unwrap();
+                self.deviate(node, kind, &deviation.child(i));
+            }
+        }
+    }
+
+    fn deviate(&mut self, node: &mut SchemaNode, kind: DeviateKind, deviate: &SourceRef) {
+        for (i, child) in deviate.node(self.repository).children().iter().enumerate() {
+            let source = deviate.child(i);
+            let keyword = match child.keyword() {
+                Some(keyword) => keyword.text().to_string(),
+                None => continue,
+            };
+
+            let multiple = matches!(keyword.as_str(), "must" | "unique")
+                || (keyword == "default" && node.kind == SchemaNodeKind::LeafList);
+
+            match kind {
+                DeviateKind::Add => {
+                    if !multiple && node.property(&keyword).is_some() {
+                        self.error(SchemaErrorKind::PropertyExists { keyword }, &source);
+                    } else {
+                        node.properties.push(Property {
+                            node: child.clone(),
+                            source,
+                        });
+                    }
+                }
+                DeviateKind::Replace => {
+                    if node.property(&keyword).is_none() {
+                        self.error(SchemaErrorKind::PropertyMissing { keyword }, &source);
+                    } else {
+                        node.set_property(Property {
+                            node: child.clone(),
+                            source,
+                        });
+                    }
+                }
+                DeviateKind::Delete => {
+                    let argument = child.argument();
+                    let position = node
+                        .properties
+                        .iter()
+                        .position(|p| p.keyword() == keyword && p.argument() == argument);
+
+                    match position {
+                        Some(position) => {
+                            node.properties.remove(position);
+                        }
+                        None => {
+                            self.error(SchemaErrorKind::PropertyMissing { keyword }, &source);
+                        }
+                    }
+                }
+                DeviateKind::NotSupported => {}
+            }
+        }
+    }
+
+    fn augment_descendant(
+        &mut self,
+        nodes: &mut [SchemaNode],
+        augment: &SourceRef,
+        namespace: ModuleId,
+    ) {
+        let path = match self.resolve_descendant(augment, namespace) {
+            Some(path) => path,
+            None => return,
+        };
+        let path: Vec<(ModuleId, &str)> =
+            path.iter().map(|(m, name)| (*m, name.as_str())).collect();
+
+        let mut children = self.compile_children(augment, namespace);
+        self.inherit(&mut children, augment);
+
+        match find_descendant_mut(nodes, &path) {
+            Some(node) => {
+                let children = wrap_cases(node.kind, children);
+                node.children.extend(children);
+            }
+            None => {
+                let target = augment
+                    .node(self.repository)
+                    .argument()
+                    .unwrap_or_default()
+                    .into_owned();
+                self.error(SchemaErrorKind::TargetNotFound { target }, augment);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        let mut text = textwrap::dedent(text).trim().to_string();
+        text.push('\n');
+        text
+    }
+
+    #[test]
+    fn uses_expansion() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    import types { prefix t; }
+
+                    grouping address {
+                        leaf ip { type string; }
+                    }
+
+                    container interfaces {
+                        grouping address {
+                            leaf local-ip { type string; }
+                        }
+
+                        list interface {
+                            key name;
+                            leaf name { type string; }
+                            uses address;
+                            uses t:counters {
+                                when "../name != 'lo'";
+                                refine "m:stats/in" {
+                                    description "Refined";
+                                    config false;
+                                }
+                                refine "t:stats" {
+                                    description "Not in the namespace of main";
+                                }
+                                augment "stats" {
+                                    leaf extra { type uint8; }
+                                }
+                            }
+                            choice mode {
+                                leaf auto { type empty; }
+                                case manual {
+                                    uses address;
+                                }
+                            }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "types.yang",
+            dedent(
+                r#"
+                module types {
+                    namespace "urn:types";
+                    prefix t;
+
+                    grouping counters {
+                        container stats {
+                            leaf in { type uint64; config true; }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository
+            .load("main", None)
+            .expect("Failed to load module");
+        let (schema, errors) = Schema::compile(&repository);
+
+        assert_eq!(
+            vec![SchemaErrorKind::TargetNotFound {
+                target: "t:stats".to_string()
+            }],
+            errors
+                .into_iter()
+                .map(|error| error.kind)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            dedent(
+                r#"
+                container interfaces
+                  list interface
+                    leaf name
+                    leaf local-ip
+                    container stats
+                      leaf in
+                      leaf extra
+                    choice mode
+                      case auto
+                        leaf auto
+                      case manual
+                        leaf local-ip
+                "#
+            ),
+            schema.to_string()
+        );
+
+        let stats = schema
+            .node(&[(main, "interfaces"), (main, "interface"), (main, "stats")])
+            .unwrap();
+        assert_eq!(1, stats.conditions.len());
+
+        let leaf = &stats.children[0];
+        assert_eq!(main, leaf.module);
+        assert_eq!(Some("Refined".into()), leaf.argument("description"));
+        assert_eq!(Some("false".into()), leaf.argument("config"));
+        assert_eq!(1, leaf.properties("config").count());
+
+        // The type is still resolved in the scope of the module it was written in
+        let types = repository.find("types", None).unwrap();
+        assert_eq!(types, leaf.property("type").unwrap().source.module);
+    }
+
+    #[test]
+    fn errors() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    grouping a {
+                        container c { uses b; }
+                    }
+                    grouping b {
+                        uses a;
+                    }
+
+                    uses a;
+                    uses missing;
+                    uses x:a;
+                    uses b {
+                        refine nothing { description "Nope"; }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository
+            .load("main", None)
+            .expect("Failed to load module");
+        let (_, errors) = Schema::compile(&repository);
+
+        let kinds: Vec<_> = errors.iter().map(|error| error.kind.clone()).collect();
+
+        assert_eq!(
+            vec![
+                SchemaErrorKind::RecursiveGrouping {
+                    name: "a".to_string()
+                },
+                SchemaErrorKind::UnknownGrouping {
+                    name: "missing".to_string()
+                },
+                SchemaErrorKind::UnknownPrefix {
+                    prefix: "x".to_string()
+                },
+                SchemaErrorKind::RecursiveGrouping {
+                    name: "b".to_string()
+                },
+                SchemaErrorKind::TargetNotFound {
+                    target: "nothing".to_string()
+                },
+            ],
+            kinds
+        );
+        assert_eq!(main, errors[0].module);
+    }
+
+    #[test]
+    fn augments() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "interfaces.yang",
+            dedent(
+                r#"
+                module interfaces {
+                    namespace "urn:if";
+                    prefix if;
+
+                    container interfaces {
+                        list interface {
+                            key name;
+                            leaf name { type string; }
+                            choice mode {
+                                leaf auto { type empty; }
+                            }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "vendor.yang",
+            dedent(
+                r#"
+                module vendor {
+                    namespace "urn:vendor";
+                    prefix v;
+
+                    import interfaces { prefix if; }
+
+                    augment "/if:interfaces/if:interface/v:settings" {
+                        leaf speed { type uint32; }
+                    }
+
+                    augment "/if:interfaces/if:interface" {
+                        when "if:name != 'lo'";
+                        container settings {
+                            leaf mtu { type uint16; }
+                        }
+                    }
+
+                    augment "/if:interfaces/if:interface/if:mode" {
+                        leaf manual { type empty; }
+                    }
+
+                    augment "/if:interfaces/if:missing" {
+                        leaf nope { type empty; }
+                    }
+
+                    augment "/x:interfaces" {
+                        leaf nope { type empty; }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let interfaces = repository.load("interfaces", None).unwrap();
+        let vendor = repository.load("vendor", None).unwrap();
+        let (schema, errors) = Schema::compile(&repository);
+
+        assert_eq!(
+            dedent(
+                r#"
+                container interfaces
+                  list interface
+                    leaf name
+                    choice mode
+                      case auto
+                        leaf auto
+                      case manual
+                        leaf manual
+                    container settings
+                      leaf mtu
+                      leaf speed
+                "#
+            ),
+            schema.to_string()
+        );
+
+        let settings = schema
+            .node(&[
+                (interfaces, "interfaces"),
+                (interfaces, "interface"),
+                (vendor, "settings"),
+            ])
+            .unwrap();
+
+        assert_eq!(vendor, settings.module);
+        assert_eq!(
+            Some("urn:vendor".into()),
+            repository.get(settings.module).namespace()
+        );
+        assert_eq!(
+            vec![Some("if:name != 'lo'".into())],
+            settings
+                .conditions
+                .iter()
+                .map(Property::argument)
+                .collect::<Vec<_>>()
+        );
+
+        let kinds: Vec<_> = errors.iter().map(|error| error.kind.clone()).collect();
+        assert_eq!(
+            vec![
+                SchemaErrorKind::UnknownPrefix {
+                    prefix: "x".to_string()
+                },
+                SchemaErrorKind::TargetNotFound {
+                    target: "/if:interfaces/if:missing".to_string()
+                },
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn deviations() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "system.yang",
+            dedent(
+                r#"
+                module system {
+                    namespace "urn:system";
+                    prefix sys;
+
+                    container system {
+                        leaf hostname {
+                            type string;
+                            must "string-length(.) > 0";
+                        }
+                        leaf location { type string; }
+                        leaf-list dns {
+                            type string;
+                            default "1.1.1.1";
+                        }
+                        leaf timezone { type string; }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "device.yang",
+            dedent(
+                r#"
+                module device {
+                    namespace "urn:device";
+                    prefix dev;
+
+                    import system { prefix sys; }
+
+                    deviation "/sys:system/sys:location" {
+                        deviate not-supported;
+                    }
+                    deviation "/sys:system/sys:hostname" {
+                        deviate add {
+                            default "device";
+                            config true;
+                        }
+                        deviate replace {
+                            type uint8;
+                        }
+                        deviate delete {
+                            must "string-length(.) > 0";
+                        }
+                    }
+                    deviation "/sys:system/sys:dns" {
+                        deviate add {
+                            default "8.8.8.8";
+                        }
+                    }
+                    deviation "/sys:system/sys:timezone" {
+                        deviate add { type string; }
+                        deviate replace { units "seconds"; }
+                        deviate delete { must "false()"; }
+                    }
+                    deviation "/sys:system/sys:missing" {
+                        deviate not-supported;
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let system = repository.load("system", None).unwrap();
+        repository.load("device", None).unwrap();
+        let (schema, errors) = Schema::compile(&repository);
+
+        assert_eq!(
+            dedent(
+                r#"
+                container system
+                  leaf hostname
+                  leaf-list dns
+                  leaf timezone
+                "#
+            ),
+            schema.to_string()
+        );
+
+        let hostname = schema
+            .node(&[(system, "system"), (system, "hostname")])
+            .unwrap();
+        assert_eq!(Some("device".into()), hostname.argument("default"));
+        assert_eq!(Some("true".into()), hostname.argument("config"));
+        assert_eq!(Some("uint8".into()), hostname.argument("type"));
+        assert!(hostname.property("must").is_none());
+
+        let dns = schema.node(&[(system, "system"), (system, "dns")]).unwrap();
+        assert_eq!(2, dns.properties("default").count());
+
+        let kinds: Vec<_> = errors.iter().map(|error| error.kind.clone()).collect();
+        assert_eq!(
+            vec![
+                SchemaErrorKind::PropertyExists {
+                    keyword: "type".to_string()
+                },
+                SchemaErrorKind::PropertyMissing {
+                    keyword: "units".to_string()
+                },
+                SchemaErrorKind::PropertyMissing {
+                    keyword: "must".to_string()
+                },
+                SchemaErrorKind::TargetNotFound {
+                    target: "/sys:system/sys:missing".to_string()
+                },
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn operations() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "system.yang",
+            dedent(
+                r#"
+                module system {
+                    namespace "urn:system";
+                    prefix sys;
+
+                    rpc reset {
+                        input {
+                            leaf name { type string; }
+                            leaf delay { type uint32; }
+                        }
+                        output {
+                            leaf status { type string; }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "vendor.yang",
+            dedent(
+                r#"
+                module vendor {
+                    namespace "urn:vendor";
+                    prefix v;
+
+                    import system { prefix sys; }
+
+                    augment "/sys:reset/sys:input" {
+                        leaf force { type boolean; }
+                    }
+
+                    deviation "/sys:reset/sys:input/sys:name" {
+                        deviate add { must "string-length(.) > 0"; }
+                    }
+
+                    deviation "/sys:reset/sys:output/sys:status" {
+                        deviate not-supported;
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let system = repository.load("system", None).unwrap();
+        repository.load("vendor", None).unwrap();
+        let (schema, errors) = Schema::compile(&repository);
+
+        assert_eq!(Vec::<SchemaError>::new(), errors);
+        assert_eq!(
+            dedent(
+                r#"
+                rpc reset
+                  input
+                    leaf name
+                    leaf delay
+                    leaf force
+                  output
+                "#
+            ),
+            schema.to_string()
+        );
+
+        let name = schema
+            .node(&[(system, "reset"), (system, "input"), (system, "name")])
+            .unwrap();
+        assert_eq!(Some("string-length(.) > 0".into()), name.argument("must"));
+    }
+}
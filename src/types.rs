@@ -0,0 +1,830 @@
+//
+// Type resolution
+//
+// Every leaf has a type, which is either one of the built-in types of RFC 7950 section 9 or a
+// typedef, which in turn is derived from another type. This module follows such typedef chains
+// down to the built-in type, collecting the restrictions added along the way, so that users of a
+// schema can see the effective type of a leaf without walking the modules themselves.
+//
+// Typedefs are looked up in the scope of the "type" statement that refers to them, see
+// `schema::find_definition`, so typedefs within groupings and imported modules are found the same
+// way as top level ones.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::arguments::{ArgumentType, FractionDigits, IdentifierRef};
+use crate::lexing::Span;
+use crate::parsing::StatementKeyword;
+use crate::patterns::{Pattern, PatternError, PatternErrorKind};
+use crate::ranges::{RangeError, Ranges};
+use crate::repository::{ModuleId, ModuleRepository};
+use crate::schema::{find_definition, DefinitionError, Property, SchemaNode, SourceRef};
+
+/// The built-in types of RFC 7950 section 4.2.4
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BuiltinType {
+    Binary,
+    Bits,
+    Boolean,
+    Decimal64,
+    Empty,
+    Enumeration,
+    Identityref,
+    InstanceIdentifier,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Leafref,
+    String,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Union,
+}
+
+impl BuiltinType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "binary" => BuiltinType::Binary,
+            "bits" => BuiltinType::Bits,
+            "boolean" => BuiltinType::Boolean,
+            "decimal64" => BuiltinType::Decimal64,
+            "empty" => BuiltinType::Empty,
+            "enumeration" => BuiltinType::Enumeration,
+            "identityref" => BuiltinType::Identityref,
+            "instance-identifier" => BuiltinType::InstanceIdentifier,
+            "int8" => BuiltinType::Int8,
+            "int16" => BuiltinType::Int16,
+            "int32" => BuiltinType::Int32,
+            "int64" => BuiltinType::Int64,
+            "leafref" => BuiltinType::Leafref,
+            "string" => BuiltinType::String,
+            "uint8" => BuiltinType::Uint8,
+            "uint16" => BuiltinType::Uint16,
+            "uint32" => BuiltinType::Uint32,
+            "uint64" => BuiltinType::Uint64,
+            "union" => BuiltinType::Union,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuiltinType::Binary => "binary",
+            BuiltinType::Bits => "bits",
+            BuiltinType::Boolean => "boolean",
+            BuiltinType::Decimal64 => "decimal64",
+            BuiltinType::Empty => "empty",
+            BuiltinType::Enumeration => "enumeration",
+            BuiltinType::Identityref => "identityref",
+            BuiltinType::InstanceIdentifier => "instance-identifier",
+            BuiltinType::Int8 => "int8",
+            BuiltinType::Int16 => "int16",
+            BuiltinType::Int32 => "int32",
+            BuiltinType::Int64 => "int64",
+            BuiltinType::Leafref => "leafref",
+            BuiltinType::String => "string",
+            BuiltinType::Uint8 => "uint8",
+            BuiltinType::Uint16 => "uint16",
+            BuiltinType::Uint32 => "uint32",
+            BuiltinType::Uint64 => "uint64",
+            BuiltinType::Union => "union",
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            BuiltinType::Int8
+                | BuiltinType::Int16
+                | BuiltinType::Int32
+                | BuiltinType::Int64
+                | BuiltinType::Uint8
+                | BuiltinType::Uint16
+                | BuiltinType::Uint32
+                | BuiltinType::Uint64
+        )
+    }
+
+    /// Returns true if the type can be restricted with the given sub-statement of "type"
+    pub fn allows(&self, keyword: &str) -> bool {
+        match keyword {
+            "range" => self.is_integer() || *self == BuiltinType::Decimal64,
+            "fraction-digits" => *self == BuiltinType::Decimal64,
+            "length" => matches!(self, BuiltinType::String | BuiltinType::Binary),
+            "pattern" => *self == BuiltinType::String,
+            "enum" => *self == BuiltinType::Enumeration,
+            "bit" => *self == BuiltinType::Bits,
+            "path" => *self == BuiltinType::Leafref,
+            "require-instance" => {
+                matches!(self, BuiltinType::Leafref | BuiltinType::InstanceIdentifier)
+            }
+            "base" => *self == BuiltinType::Identityref,
+            "type" => *self == BuiltinType::Union,
+            _ => false,
+        }
+    }
+
+    /// Returns the restriction the built-in type can't be used without, if any
+    fn required(&self) -> Option<&'static str> {
+        match self {
+            BuiltinType::Decimal64 => Some("fraction-digits"),
+            BuiltinType::Enumeration => Some("enum"),
+            BuiltinType::Bits => Some("bit"),
+            BuiltinType::Leafref => Some("path"),
+            BuiltinType::Identityref => Some("base"),
+            BuiltinType::Union => Some("type"),
+            _ => None,
+        }
+    }
+}
+
+impl Display for BuiltinType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An enum of an enumeration with its value, or a bit with its position
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedValue {
+    pub name: String,
+    pub value: i64,
+}
+
+/// The effective type of a leaf, with the restrictions of every typedef in its chain
+///
+/// Restrictions that can be repeated along the chain are kept in the order they were added,
+/// starting with the one closest to the built-in type.
+///
+#[derive(Debug, Clone)]
+pub struct ResolvedType {
+    pub builtin: BuiltinType,
+
+    /// The typedefs that were followed, starting with the one closest to the built-in type
+    pub typedefs: Vec<SourceRef>,
+
+    /// The "range" statements, where each one narrows the ones before it
+    pub ranges: Vec<Property>,
+
+    /// The "length" statements, where each one narrows the ones before it
+    pub lengths: Vec<Property>,
+
+    /// The values allowed by the innermost "range" statement, if any
+    pub range: Option<Ranges>,
+
+    /// The lengths allowed by the innermost "length" statement, if any
+    pub length: Option<Ranges>,
+
+    /// The "pattern" statements along with their compiled form, which a value must match all of
+    pub patterns: Vec<(Property, Pattern)>,
+
+    pub fraction_digits: Option<u8>,
+
+    /// The enums of an enumeration, after any restrictions
+    pub enums: Vec<NamedValue>,
+
+    /// The bits of a bits type, after any restrictions
+    pub bits: Vec<NamedValue>,
+
+    pub path: Option<Property>,
+    pub require_instance: Option<bool>,
+    pub bases: Vec<Property>,
+
+    /// The member types of a union
+    pub members: Vec<ResolvedType>,
+
+    /// The default value of the outermost typedef that has one
+    pub default: Option<Property>,
+}
+
+impl ResolvedType {
+    fn new(builtin: BuiltinType) -> Self {
+        Self {
+            builtin,
+            typedefs: vec![],
+            ranges: vec![],
+            lengths: vec![],
+            range: None,
+            length: None,
+            patterns: vec![],
+            fraction_digits: None,
+            enums: vec![],
+            bits: vec![],
+            path: None,
+            require_instance: None,
+            bases: vec![],
+            members: vec![],
+            default: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeError {
+    pub kind: TypeErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeErrorKind {
+    UnknownPrefix {
+        prefix: String,
+    },
+    UnknownType {
+        name: String,
+    },
+
+    /// A typedef that is derived from itself, directly or indirectly
+    RecursiveTypedef {
+        name: String,
+    },
+
+    /// A restriction that the type doesn't allow, or only allows on the built-in type itself
+    IllegalRestriction {
+        keyword: String,
+        builtin: BuiltinType,
+    },
+
+    /// A built-in type used without a restriction it requires, such as decimal64 without
+    /// "fraction-digits"
+    MissingRestriction {
+        keyword: String,
+        builtin: BuiltinType,
+    },
+
+    /// A "range" or "length" that can't be parsed, or that widens the ranges of its base type
+    InvalidRange {
+        keyword: String,
+        error: RangeError,
+    },
+
+    InvalidPattern {
+        error: PatternErrorKind,
+    },
+
+    /// An enum or bit in a derived type that isn't in the type it's derived from
+    UnknownMember {
+        name: String,
+    },
+
+    /// An enum or bit in a derived type with a different value than in the type it's derived from
+    ValueMismatch {
+        name: String,
+    },
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TypeErrorKind::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            TypeErrorKind::UnknownType { name } => write!(f, "Unknown type {:?}", name),
+            TypeErrorKind::RecursiveTypedef { name } => {
+                write!(f, "The typedef {:?} is derived from itself", name)
+            }
+            TypeErrorKind::IllegalRestriction { keyword, builtin } => {
+                write!(f, "{:?} can't be used to restrict {}", keyword, builtin)
+            }
+            TypeErrorKind::MissingRestriction { keyword, builtin } => {
+                write!(f, "The {} type requires a {:?} statement", builtin, keyword)
+            }
+            TypeErrorKind::InvalidRange { keyword, error } => {
+                write!(f, "Invalid {:?} restriction: {}", keyword, error)
+            }
+            TypeErrorKind::InvalidPattern { error } => write!(f, "Invalid pattern: {}", error),
+            TypeErrorKind::UnknownMember { name } => {
+                write!(f, "{:?} is not defined in the base type", name)
+            }
+            TypeErrorKind::ValueMismatch { name } => {
+                write!(f, "{:?} has a different value in the base type", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TypeError {}
+
+/// Resolves a "type" statement to its built-in type
+///
+/// Returns None if the chain of typedefs can't be followed to a built-in type, in which case the
+/// errors say why. Illegal restrictions are reported, but otherwise left out of the result.
+///
+pub fn resolve_type(
+    repository: &ModuleRepository,
+    source: &SourceRef,
+) -> (Option<ResolvedType>, Vec<TypeError>) {
+    let mut resolver = Resolver {
+        repository,
+        errors: vec![],
+        typedefs: vec![],
+    };
+
+    let resolved = resolver.resolve(source);
+    (resolved, resolver.errors)
+}
+
+/// Resolves the type of a leaf or leaf-list in a compiled schema, or None for other nodes
+pub fn resolve_node_type(
+    repository: &ModuleRepository,
+    node: &SchemaNode,
+) -> (Option<ResolvedType>, Vec<TypeError>) {
+    match node.property("type") {
+        Some(property) => resolve_type(repository, &property.source),
+        None => (None, vec![]),
+    }
+}
+
+struct Resolver<'r> {
+    repository: &'r ModuleRepository,
+    errors: Vec<TypeError>,
+
+    /// The typedefs currently being resolved, used to detect recursion
+    typedefs: Vec<SourceRef>,
+}
+
+impl Resolver<'_> {
+    fn error(&mut self, kind: TypeErrorKind, source: &SourceRef) {
+        self.errors.push(TypeError {
+            kind,
+            module: source.module,
+            span: source.span(self.repository),
+        });
+    }
+
+    fn resolve(&mut self, source: &SourceRef) -> Option<ResolvedType> {
+        let name = source
+            .node(self.repository)
+            .argument()
+            .unwrap_or_default()
+            .into_owned();
+
+        let builtin = IdentifierRef::parse(&name)
+            .filter(|reference| reference.prefix.is_none())
+            .and_then(|reference| BuiltinType::from_name(&reference.name));
+
+        let mut resolved = match builtin {
+            Some(builtin) => ResolvedType::new(builtin),
+            None => self.resolve_typedef(source, &name)?,
+        };
+
+        self.restrict(&mut resolved, source, builtin.is_none());
+        Some(resolved)
+    }
+
+    fn resolve_typedef(&mut self, source: &SourceRef, name: &str) -> Option<ResolvedType> {
+        let typedef = match find_definition(self.repository, source, "typedef", name) {
+            Ok(typedef) => typedef,
+            Err(DefinitionError::UnknownPrefix(prefix)) => {
+                self.error(TypeErrorKind::UnknownPrefix { prefix }, source);
+                return None;
+            }
+            Err(DefinitionError::NotFound) => {
+                let name = name.to_string();
+                self.error(TypeErrorKind::UnknownType { name }, source);
+                return None;
+            }
+        };
+
+        if self.typedefs.contains(&typedef) {
+            let name = name.to_string();
+            self.error(TypeErrorKind::RecursiveTypedef { name }, source);
+            return None;
+        }
+
+        // A missing "type" is reported by validation
+        let children = typedef.node(self.repository).children();
+        let index = children.iter().position(|node| node.is("type"))?;
+
+        self.typedefs.push(typedef.clone());
+        let base = self.resolve(&typedef.child(index));
+        self.typedefs.pop();
+
+        let mut resolved = base?;
+
+        if let Some(index) = children.iter().position(|node| node.is("default")) {
+            resolved.default = Some(Property {
+                node: children[index].clone(),
+                source: typedef.child(index),
+            });
+        }
+
+        resolved.typedefs.push(typedef);
+        Some(resolved)
+    }
+
+    /// Adds the restrictions of a "type" statement to the type it refers to
+    fn restrict(&mut self, resolved: &mut ResolvedType, source: &SourceRef, derived: bool) {
+        let builtin = resolved.builtin;
+        let mut members = vec![];
+        let mut intervals = vec![];
+
+        for (i, child) in source.node(self.repository).children().iter().enumerate() {
+            let keyword = match child.keyword() {
+                Some(StatementKeyword::Keyword(keyword)) => keyword.as_str(),
+                _ => continue,
+            };
+
+            let child_source = source.child(i);
+            let builtin_only = matches!(keyword, "fraction-digits" | "path" | "base" | "type");
+
+            if !builtin.allows(keyword) || (derived && builtin_only) {
+                let keyword = keyword.to_string();
+                self.error(
+                    TypeErrorKind::IllegalRestriction { keyword, builtin },
+                    &child_source,
+                );
+                continue;
+            }
+
+            let property = Property {
+                node: child.clone(),
+                source: child_source.clone(),
+            };
+
+            match keyword {
+                "range" | "length" => intervals.push(property),
+                "pattern" => match Pattern::from_property(&property) {
+                    Ok(pattern) => resolved.patterns.push((property, pattern)),
+                    Err(PatternError { kind, module, span }) => self.errors.push(TypeError {
+                        kind: TypeErrorKind::InvalidPattern { error: kind },
+                        module,
+                        span,
+                    }),
+                },
+                "fraction-digits" => {
+                    resolved.fraction_digits = child
+                        .argument()
+                        .and_then(|arg| FractionDigits::parse(&arg))
+                        .map(|digits| digits.0);
+                }
+                "path" => resolved.path = Some(property),
+                "require-instance" => {
+                    resolved.require_instance = child.argument().and_then(|arg| bool::parse(&arg));
+                }
+                "base" => resolved.bases.push(property),
+                "enum" | "bit" => members.push(child_source),
+                "type" => {
+                    if let Some(member) = self.resolve(&child_source) {
+                        resolved.members.push(member);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The fraction digits of a decimal64 are needed first, so ranges are parsed last
+        for property in intervals {
+            let keyword = property.keyword().to_string();
+            let argument = property.argument().unwrap_or_default();
+
+            let current = match keyword.as_str() {
+                "range" => resolved.range.clone().or_else(|| {
+                    Ranges::builtin(builtin, resolved.fraction_digits.unwrap_or_default())
+                }),
+                _ => Some(resolved.length.clone().unwrap_or_else(Ranges::length)),
+            };
+
+            match current.map(|current| current.restrict(&argument)) {
+                Some(Ok(ranges)) if keyword == "range" => {
+                    resolved.range = Some(ranges);
+                    resolved.ranges.push(property);
+                }
+                Some(Ok(ranges)) => {
+                    resolved.length = Some(ranges);
+                    resolved.lengths.push(property);
+                }
+                Some(Err(error)) => {
+                    self.error(
+                        TypeErrorKind::InvalidRange { keyword, error },
+                        &property.source,
+                    );
+                }
+                None => {}
+            }
+        }
+
+        match builtin {
+            BuiltinType::Enumeration if !members.is_empty() || !derived => {
+                resolved.enums = self.members(&resolved.enums, &members, "value", derived);
+            }
+            BuiltinType::Bits if !members.is_empty() || !derived => {
+                resolved.bits = self.members(&resolved.bits, &members, "position", derived);
+            }
+            _ => {}
+        }
+
+        let missing = builtin.required().filter(|keyword| {
+            !derived
+                && !source
+                    .node(self.repository)
+                    .children()
+                    .iter()
+                    .any(|child| child.is(keyword))
+        });
+
+        if let Some(keyword) = missing {
+            let keyword = keyword.to_string();
+            self.error(
+                TypeErrorKind::MissingRestriction { keyword, builtin },
+                source,
+            );
+        }
+    }
+
+    /// Assigns values to the enums or bits of a type, see RFC 7950 sections 9.6.4 and 9.7.4
+    ///
+    /// In a derived type, the enums or bits must be a subset of the base type, and keep the
+    /// values they have there.
+    ///
+    fn members(
+        &mut self,
+        base: &[NamedValue],
+        members: &[SourceRef],
+        value_keyword: &str,
+        derived: bool,
+    ) -> Vec<NamedValue> {
+        let mut values = vec![];
+        let mut next = 0;
+
+        for source in members {
+            let node = source.node(self.repository);
+            let name = node.argument().unwrap_or_default().into_owned();
+
+            let explicit = node
+                .children()
+                .iter()
+                .find(|child| child.is(value_keyword))
+                .and_then(|child| child.argument())
+                .and_then(|arg| match value_keyword {
+                    "value" => i32::parse(&arg).map(i64::from),
+                    _ => u32::parse(&arg).map(i64::from),
+                });
+
+            if !derived {
+                let value = explicit.unwrap_or(next);
+                next = next.max(value + 1);
+                values.push(NamedValue { name, value });
+                continue;
+            }
+
+            match base.iter().find(|member| member.name == name) {
+                None => self.error(TypeErrorKind::UnknownMember { name }, source),
+                Some(member) if explicit.is_some_and(|value| value != member.value) => {
+                    self.error(TypeErrorKind::ValueMismatch { name }, source)
+                }
+                Some(member) => values.push(member.clone()),
+            }
+        }
+
+        values
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::Schema;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        textwrap::dedent(text).trim_start().to_string()
+    }
+
+    #[test]
+    fn typedef_chains() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "types.yang",
+            dedent(
+                r#"
+                module types {
+                    namespace "urn:types";
+                    prefix t;
+
+                    typedef percent {
+                        type uint8 { range "0..100"; }
+                        default 0;
+                    }
+                    typedef color {
+                        type enumeration {
+                            enum red;
+                            enum green { value 5; }
+                            enum blue;
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    import types { prefix t; }
+
+                    typedef small-percent {
+                        type t:percent { range "0..10"; }
+                    }
+
+                    grouping settings {
+                        typedef name {
+                            type string {
+                                length "1..64";
+                                pattern "[a-z]+";
+                            }
+                        }
+                        leaf name { type name { pattern "x.*"; } }
+                    }
+
+                    container settings {
+                        uses settings;
+                        leaf level { type small-percent; }
+                        leaf color {
+                            type t:color {
+                                enum green;
+                                enum blue;
+                            }
+                        }
+                        leaf value {
+                            type union {
+                                type int32;
+                                type decimal64 { fraction-digits 2; }
+                            }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+
+        let resolve = |name: &str| {
+            let node = schema.node(&[(main, "settings"), (main, name)]).unwrap();
+            let (resolved, errors) = resolve_node_type(&repository, node);
+            assert_eq!(Vec::<TypeError>::new(), errors);
+            resolved.unwrap()
+        };
+
+        let level = resolve("level");
+        let arguments = |properties: &[Property]| -> Vec<String> {
+            properties
+                .iter()
+                .map(|p| p.argument().unwrap().into_owned())
+                .collect()
+        };
+        assert_eq!(BuiltinType::Uint8, level.builtin);
+        assert_eq!(2, level.typedefs.len());
+        assert_eq!(vec!["0..100", "0..10"], arguments(&level.ranges));
+        assert_eq!(
+            Some(true),
+            level.range.as_ref().unwrap().contains_value("10")
+        );
+        assert_eq!(
+            Some(false),
+            level.range.as_ref().unwrap().contains_value("11")
+        );
+        assert_eq!(Some("0".into()), level.default.unwrap().argument());
+
+        let name = resolve("name");
+        assert_eq!(BuiltinType::String, name.builtin);
+        assert_eq!(vec!["1..64"], arguments(&name.lengths));
+        let (patterns, matchers): (Vec<_>, Vec<_>) = name.patterns.into_iter().unzip();
+        assert_eq!(vec!["[a-z]+", "x.*"], arguments(&patterns));
+        assert!(matchers.iter().all(|pattern| pattern.is_match("xyz")));
+        assert!(!matchers.iter().all(|pattern| pattern.is_match("abc")));
+
+        let color = resolve("color");
+        assert_eq!(
+            vec![
+                NamedValue {
+                    name: "green".to_string(),
+                    value: 5
+                },
+                NamedValue {
+                    name: "blue".to_string(),
+                    value: 6
+                },
+            ],
+            color.enums
+        );
+
+        let value = resolve("value");
+        let members: Vec<_> = value.members.iter().map(|m| m.builtin).collect();
+        assert_eq!(vec![BuiltinType::Int32, BuiltinType::Decimal64], members);
+        assert_eq!(Some(2), value.members[1].fraction_digits);
+    }
+
+    #[test]
+    fn errors() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    typedef loop-a { type loop-b; }
+                    typedef loop-b { type loop-a; }
+                    typedef color {
+                        type enumeration { enum red; enum green; }
+                    }
+                    typedef amount {
+                        type decimal64 {
+                            fraction-digits 2;
+                            range "-1..100";
+                        }
+                    }
+
+                    leaf a { type string { range "1..2"; } }
+                    leaf b { type decimal64; }
+                    leaf c { type amount { fraction-digits 3; } }
+                    leaf d { type color { enum blue; enum green { value 7; } } }
+                    leaf e { type loop-a; }
+                    leaf f { type x:foo; }
+                    leaf g { type foo; }
+                    leaf h { type amount { range "-1.5..max"; } }
+                    leaf i { type amount { range "1..1.005"; } }
+                    leaf j { type string { pattern "[a-"; } }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+
+        let errors: Vec<_> = ["a", "b", "c", "d", "e", "f", "g", "h", "i", "j"]
+            .iter()
+            .flat_map(|name| {
+                let node = schema.node(&[(main, name)]).unwrap();
+                resolve_node_type(&repository, node).1
+            })
+            .collect();
+
+        // Invalid patterns point at the pattern itself
+        assert_eq!((732, 736), errors.last().unwrap().span);
+
+        let kinds: Vec<_> = errors.into_iter().map(|error| error.kind).collect();
+
+        assert_eq!(
+            vec![
+                TypeErrorKind::IllegalRestriction {
+                    keyword: "range".to_string(),
+                    builtin: BuiltinType::String
+                },
+                TypeErrorKind::MissingRestriction {
+                    keyword: "fraction-digits".to_string(),
+                    builtin: BuiltinType::Decimal64
+                },
+                TypeErrorKind::IllegalRestriction {
+                    keyword: "fraction-digits".to_string(),
+                    builtin: BuiltinType::Decimal64
+                },
+                TypeErrorKind::UnknownMember {
+                    name: "blue".to_string()
+                },
+                TypeErrorKind::ValueMismatch {
+                    name: "green".to_string()
+                },
+                TypeErrorKind::RecursiveTypedef {
+                    name: "loop-a".to_string()
+                },
+                TypeErrorKind::UnknownPrefix {
+                    prefix: "x".to_string()
+                },
+                TypeErrorKind::UnknownType {
+                    name: "foo".to_string()
+                },
+                TypeErrorKind::InvalidRange {
+                    keyword: "range".to_string(),
+                    error: RangeError::NotSubset
+                },
+                TypeErrorKind::InvalidRange {
+                    keyword: "range".to_string(),
+                    error: RangeError::Syntax
+                },
+                TypeErrorKind::InvalidPattern {
+                    error: PatternErrorKind::UnexpectedEnd
+                },
+            ],
+            kinds
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//
+// Validation of statement placement and cardinality
+//
+// The parser happily builds a tree out of any well-formed sequence of statements. This module
+// checks the tree against the grammar, using the table of allowed sub-statements in the constants
+// module: every statement must be allowed in its parent, statements must not occur more often than
+// the grammar allows, and required statements must be present.
+//
+
+use std::fmt::{self, Display, Formatter};
+
+use crate::constants::{substatements, Cardinality};
+use crate::lexing::Span;
+use crate::parsing::{Node, RootNode, StatementKeyword};
+
+/// A statement that is misplaced, repeated or missing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    pub kind: ValidationErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationErrorKind {
+    /// The document doesn't contain a module or submodule
+    MissingModule,
+
+    /// The document contains more than one module or submodule
+    MultipleModules,
+
+    /// A statement that isn't allowed in its parent, which is None at the top level
+    NotAllowed {
+        keyword: String,
+        parent: Option<String>,
+    },
+
+    /// A statement that occurs more often than its parent allows
+    TooMany { keyword: String, parent: String },
+
+    /// A required statement is missing from its parent
+    Missing { keyword: String, parent: String },
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ValidationErrorKind::MissingModule => {
+                write!(f, "No module or submodule statement found")
+            }
+            ValidationErrorKind::MultipleModules => {
+                write!(f, "Found more than one module or submodule statement")
+            }
+            ValidationErrorKind::NotAllowed {
+                keyword,
+                parent: Some(parent),
+            } => write!(f, "{:?} is not allowed in {:?}", keyword, parent),
+            ValidationErrorKind::NotAllowed {
+                keyword,
+                parent: None,
+            } => write!(f, "{:?} is not allowed at the top level", keyword),
+            ValidationErrorKind::TooMany { keyword, parent } => {
+                write!(f, "{:?} may only occur once in {:?}", keyword, parent)
+            }
+            ValidationErrorKind::Missing { keyword, parent } => {
+                write!(f, "{:?} is missing from {:?}", keyword, parent)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Validates the placement and cardinality of all statements in the tree
+///
+/// All errors are collected and returned, so an empty list means the tree is valid. Statements
+/// that couldn't be parsed (error nodes) are skipped, as are the contents of extension statements,
+/// which are defined by the extension.
+///
+pub fn validate(root: &RootNode) -> Vec<ValidationError> {
+    let mut errors = vec![];
+    let mut module_count = 0;
+
+    for node in root.children.iter() {
+        let (keyword, keyword_span) = match (node.keyword(), node.keyword_span()) {
+            (Some(keyword), Some(span)) => (keyword, span),
+            _ => continue,
+        };
+
+        if matches!(keyword, StatementKeyword::Keyword(k) if k == "module" || k == "submodule") {
+            module_count += 1;
+
+            if module_count > 1 {
+                errors.push(ValidationError {
+                    kind: ValidationErrorKind::MultipleModules,
+                    span: keyword_span,
+                });
+            }
+
+            validate_statement(node, keyword.text(), &mut errors);
+        } else {
+            errors.push(ValidationError {
+                kind: ValidationErrorKind::NotAllowed {
+                    keyword: keyword.text().to_string(),
+                    parent: None,
+                },
+                span: keyword_span,
+            });
+        }
+    }
+
+    if module_count == 0 {
+        errors.push(ValidationError {
+            kind: ValidationErrorKind::MissingModule,
+            span: (0, 0),
+        });
+    }
+
+    errors
+}
+
+fn validate_statement(node: &Node, keyword: &str, errors: &mut Vec<ValidationError>) {
+    let rules = match substatements(keyword) {
+        Some(rules) => rules,
+        None => return,
+    };
+
+    let mut counts = vec![0; rules.len()];
+
+    for child in node.children() {
+        let (child_keyword, child_span) = match (child.keyword(), child.keyword_span()) {
+            (Some(StatementKeyword::ExtensionKeyword(_)), _) => continue,
+            (Some(keyword), Some(span)) => (keyword.text(), span),
+            _ => continue,
+        };
+
+        let index = rules.iter().position(|(k, _)| *k == child_keyword);
+
+        match index {
+            Some(index) => {
+                counts[index] += 1;
+
+                let (_, cardinality) = rules[index];
+                let single = matches!(cardinality, Cardinality::Optional | Cardinality::Required);
+
+                if single && counts[index] > 1 {
+                    errors.push(ValidationError {
+                        kind: ValidationErrorKind::TooMany {
+                            keyword: child_keyword.to_string(),
+                            parent: keyword.to_string(),
+                        },
+                        span: child_span,
+                    });
+                }
+            }
+            None => errors.push(ValidationError {
+                kind: ValidationErrorKind::NotAllowed {
+                    keyword: child_keyword.to_string(),
+                    parent: Some(keyword.to_string()),
+                },
+                span: child_span,
+            }),
+        }
+
+        // Misplaced statements are still validated, since their contents may be fine
+        validate_statement(child, child_keyword, errors);
+    }
+
+    for ((substatement, cardinality), count) in rules.iter().zip(counts) {
+        let required = matches!(cardinality, Cardinality::Required | Cardinality::OneOrMore);
+
+        if required && count == 0 {
+            errors.push(ValidationError {
+                kind: ValidationErrorKind::Missing {
+                    keyword: substatement.to_string(),
+                    parent: keyword.to_string(),
+                },
+                span: node.keyword_span().unwrap_or_else(|| node.span()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::constants::STATEMENT_KEYWORDS;
+    use crate::parsing::parse;
+    use pretty_assertions::assert_eq;
+
+    fn dedent(text: &str) -> String {
+        let mut text = textwrap::dedent(text).trim().to_string();
+        text.push('\n');
+        text
+    }
+
+    fn validate_text(text: &str) -> Vec<ValidationError> {
+        let buffer: Vec<u8> = dedent(text).bytes().collect();
+        let tree = parse(&buffer).expect("Failed to parse YANG");
+        validate(&tree)
+    }
+
+    #[test]
+    fn table_covers_all_keywords() {
+        for keyword in STATEMENT_KEYWORDS {
+            assert!(
+                substatements(keyword).is_some(),
+                "No sub-statements for {:?}",
+                keyword
+            );
+        }
+    }
+
+    #[test]
+    fn valid() {
+        let errors = validate_text(
+            r#"
+            module test {
+                yang-version 1.1;
+                namespace "urn:test";
+                prefix t;
+
+                import other { prefix o; }
+
+                ex:annotation "anything { goes }" {
+                    key nonsense;
+                }
+
+                container c {
+                    list l {
+                        key name;
+                        leaf name { type string; }
+                    }
+                }
+            }
+            "#,
+        );
+
+        assert_eq!(Vec::<ValidationError>::new(), errors);
+    }
+
+    #[test]
+    fn errors() {
+        let errors = validate_text(
+            r#"
+            module test {
+                namespace "urn:a";
+                namespace "urn:b";
+
+                container c {
+                    key name;
+                    leaf name;
+                }
+            }
+            "#,
+        );
+
+        assert_eq!(
+            vec![
+                ValidationError {
+                    kind: ValidationErrorKind::TooMany {
+                        keyword: "namespace".to_string(),
+                        parent: "module".to_string(),
+                    },
+                    span: (41, 49),
+                },
+                ValidationError {
+                    kind: ValidationErrorKind::NotAllowed {
+                        keyword: "key".to_string(),
+                        parent: Some("container".to_string()),
+                    },
+                    span: (87, 89),
+                },
+                ValidationError {
+                    kind: ValidationErrorKind::Missing {
+                        keyword: "type".to_string(),
+                        parent: "leaf".to_string(),
+                    },
+                    span: (105, 108),
+                },
+                ValidationError {
+                    kind: ValidationErrorKind::Missing {
+                        keyword: "prefix".to_string(),
+                        parent: "module".to_string(),
+                    },
+                    span: (0, 5),
+                },
+            ],
+            errors
+        );
+
+        assert_eq!(
+            "\"prefix\" is missing from \"module\"",
+            errors[3].to_string()
+        );
+    }
+
+    #[test]
+    fn top_level() {
+        let kinds = |text| {
+            validate_text(text)
+                .into_iter()
+                .map(|error| error.kind)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            vec![
+                ValidationErrorKind::NotAllowed {
+                    keyword: "leaf".to_string(),
+                    parent: None,
+                },
+                ValidationErrorKind::MissingModule,
+            ],
+            kinds("leaf foo { type string; }")
+        );
+
+        assert_eq!(
+            vec![ValidationErrorKind::MultipleModules],
+            kinds(
+                r#"
+                module a { namespace "urn:a"; prefix a; }
+                submodule b { belongs-to a { prefix a; } }
+                "#
+            )
+        );
+    }
+}
@@ -0,0 +1,1548 @@
+//
+// XPath expressions
+//
+// The arguments of "must" and "when", among others, are XPath 1.0 expressions (RFC 7950 section
+// 6.4), extended with the functions of RFC 7950 section 10. This module parses them into an
+// expression tree, where every expression and location step has the span it was parsed from.
+//
+// Spans within an expression are byte offsets into the decoded argument, in the same inclusive
+// form as the spans of the lexer. `prefixes::argument_span` maps them onto the source when the
+// argument allows it.
+//
+
+use std::collections::HashSet;
+use std::fmt::{self, Display, Formatter};
+use std::ptr;
+
+use crate::lexing::Span;
+use crate::prefixes::argument_span;
+use crate::repository::{ModuleId, ModuleRepository};
+use crate::schema::{resolve_prefix, Property, Schema, SchemaNode, SchemaNodeKind};
+
+/// The functions of XPath 1.0 and RFC 7950 section 10, with their minimum and maximum number of
+/// arguments
+const FUNCTIONS: &[(&str, usize, usize)] = &[
+    ("last", 0, 0),
+    ("position", 0, 0),
+    ("count", 1, 1),
+    ("id", 1, 1),
+    ("local-name", 0, 1),
+    ("namespace-uri", 0, 1),
+    ("name", 0, 1),
+    ("string", 0, 1),
+    ("concat", 2, usize::MAX),
+    ("starts-with", 2, 2),
+    ("contains", 2, 2),
+    ("substring-before", 2, 2),
+    ("substring-after", 2, 2),
+    ("substring", 2, 3),
+    ("string-length", 0, 1),
+    ("normalize-space", 0, 1),
+    ("translate", 3, 3),
+    ("boolean", 1, 1),
+    ("not", 1, 1),
+    ("true", 0, 0),
+    ("false", 0, 0),
+    ("lang", 1, 1),
+    ("number", 0, 1),
+    ("sum", 1, 1),
+    ("floor", 1, 1),
+    ("ceiling", 1, 1),
+    ("round", 1, 1),
+    ("current", 0, 0),
+    ("deref", 1, 1),
+    ("derived-from", 2, 2),
+    ("derived-from-or-self", 2, 2),
+    ("re-match", 2, 2),
+    ("enum-value", 1, 1),
+    ("bit-is-set", 2, 2),
+];
+
+/// The node types that can be used as node tests, like "text()"
+const NODE_TYPES: &[&str] = &["comment", "text", "processing-instruction", "node"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Binary {
+        operator: BinaryOperator,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    Negate(Box<Expr>),
+    Literal(String),
+    Number(f64),
+    Variable(String),
+    Function {
+        name: String,
+        arguments: Vec<Expr>,
+    },
+
+    /// A primary expression followed by predicates, like "$nodes[1]"
+    Filter {
+        expr: Box<Expr>,
+        predicates: Vec<Expr>,
+    },
+
+    /// A location path, with abbreviations such as ".." expanded into full steps
+    Path {
+        start: PathStart,
+        steps: Vec<Step>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Modulo,
+    Union,
+}
+
+/// Where a location path starts
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathStart {
+    /// A relative path, starting at the context node
+    Context,
+
+    /// An absolute path, starting at the root
+    Root,
+
+    /// A path that continues from the node set of a filter expression, like "current()/../name"
+    Expr(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub axis: Axis,
+    pub test: NodeTest,
+    pub predicates: Vec<Expr>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Ancestor,
+    AncestorOrSelf,
+    Attribute,
+    Child,
+    Descendant,
+    DescendantOrSelf,
+    Following,
+    FollowingSibling,
+    Namespace,
+    Parent,
+    Preceding,
+    PrecedingSibling,
+    Self_,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeTest {
+    Name {
+        prefix: Option<String>,
+        name: String,
+    },
+
+    /// "*" or "prefix:*"
+    Wildcard {
+        prefix: Option<String>,
+    },
+    Node,
+    Text,
+    Comment,
+    ProcessingInstruction(Option<String>),
+}
+
+impl Axis {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "ancestor" => Axis::Ancestor,
+            "ancestor-or-self" => Axis::AncestorOrSelf,
+            "attribute" => Axis::Attribute,
+            "child" => Axis::Child,
+            "descendant" => Axis::Descendant,
+            "descendant-or-self" => Axis::DescendantOrSelf,
+            "following" => Axis::Following,
+            "following-sibling" => Axis::FollowingSibling,
+            "namespace" => Axis::Namespace,
+            "parent" => Axis::Parent,
+            "preceding" => Axis::Preceding,
+            "preceding-sibling" => Axis::PrecedingSibling,
+            "self" => Axis::Self_,
+            _ => return None,
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Axis::Ancestor => "ancestor",
+            Axis::AncestorOrSelf => "ancestor-or-self",
+            Axis::Attribute => "attribute",
+            Axis::Child => "child",
+            Axis::Descendant => "descendant",
+            Axis::DescendantOrSelf => "descendant-or-self",
+            Axis::Following => "following",
+            Axis::FollowingSibling => "following-sibling",
+            Axis::Namespace => "namespace",
+            Axis::Parent => "parent",
+            Axis::Preceding => "preceding",
+            Axis::PrecedingSibling => "preceding-sibling",
+            Axis::Self_ => "self",
+        }
+    }
+}
+
+impl BinaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            BinaryOperator::Or => "or",
+            BinaryOperator::And => "and",
+            BinaryOperator::Equal => "=",
+            BinaryOperator::NotEqual => "!=",
+            BinaryOperator::Less => "<",
+            BinaryOperator::LessOrEqual => "<=",
+            BinaryOperator::Greater => ">",
+            BinaryOperator::GreaterOrEqual => ">=",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Subtract => "-",
+            BinaryOperator::Multiply => "*",
+            BinaryOperator::Divide => "div",
+            BinaryOperator::Modulo => "mod",
+            BinaryOperator::Union => "|",
+        }
+    }
+}
+
+/// Prints expressions in their unabbreviated form, with parentheses around every binary operation
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ExprKind::Binary {
+                operator,
+                left,
+                right,
+            } => write!(f, "({} {} {})", left, operator.symbol(), right),
+            ExprKind::Negate(expr) => write!(f, "-{}", expr),
+            ExprKind::Literal(text) if text.contains('\'') => write!(f, "\"{}\"", text),
+            ExprKind::Literal(text) => write!(f, "'{}'", text),
+            ExprKind::Number(number) => write!(f, "{}", number),
+            ExprKind::Variable(name) => write!(f, "${}", name),
+            ExprKind::Function { name, arguments } => {
+                write!(f, "{}(", name)?;
+
+                for (i, argument) in arguments.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+
+                    write!(f, "{}", argument)?;
+                }
+
+                write!(f, ")")
+            }
+            ExprKind::Filter { expr, predicates } => {
+                write!(f, "{}", expr)?;
+
+                for predicate in predicates {
+                    write!(f, "[{}]", predicate)?;
+                }
+
+                Ok(())
+            }
+            ExprKind::Path { start, steps } => {
+                match start {
+                    PathStart::Context => {}
+                    PathStart::Root => write!(f, "/")?,
+                    PathStart::Expr(expr) => write!(f, "{}/", expr)?,
+                }
+
+                for (i, step) in steps.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "/")?;
+                    }
+
+                    write!(f, "{}", step)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.axis.name(), self.test)?;
+
+        for predicate in self.predicates.iter() {
+            write!(f, "[{}]", predicate)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for NodeTest {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeTest::Name { prefix, name } => match prefix {
+                Some(prefix) => write!(f, "{}:{}", prefix, name),
+                None => write!(f, "{}", name),
+            },
+            NodeTest::Wildcard { prefix } => match prefix {
+                Some(prefix) => write!(f, "{}:*", prefix),
+                None => write!(f, "*"),
+            },
+            NodeTest::Node => write!(f, "node()"),
+            NodeTest::Text => write!(f, "text()"),
+            NodeTest::Comment => write!(f, "comment()"),
+            NodeTest::ProcessingInstruction(None) => write!(f, "processing-instruction()"),
+            NodeTest::ProcessingInstruction(Some(target)) => {
+                write!(f, "processing-instruction('{}')", target)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XPathError {
+    pub kind: XPathErrorKind,
+
+    /// The span within the expression
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum XPathErrorKind {
+    UnexpectedChar {
+        c: char,
+    },
+    UnexpectedToken,
+    UnexpectedEnd,
+    UnterminatedLiteral,
+    UnknownAxis {
+        name: String,
+    },
+    UnknownFunction {
+        name: String,
+    },
+
+    /// A function called with too few or too many arguments
+    ArgumentCount {
+        name: String,
+    },
+
+    /// A prefix that is neither the module's own prefix nor the prefix of an import
+    UnknownPrefix {
+        prefix: String,
+    },
+
+    /// A prefixed name that isn't a schema node of the module the prefix refers to
+    UnknownNode {
+        name: String,
+    },
+}
+
+impl Display for XPathErrorKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            XPathErrorKind::UnexpectedChar { c } => write!(f, "Unexpected {:?}", c),
+            XPathErrorKind::UnexpectedToken => write!(f, "Unexpected token"),
+            XPathErrorKind::UnexpectedEnd => write!(f, "Unexpected end of expression"),
+            XPathErrorKind::UnterminatedLiteral => write!(f, "Unterminated string literal"),
+            XPathErrorKind::UnknownAxis { name } => write!(f, "Unknown axis {:?}", name),
+            XPathErrorKind::UnknownFunction { name } => write!(f, "Unknown function {:?}", name),
+            XPathErrorKind::ArgumentCount { name } => {
+                write!(f, "Wrong number of arguments for {:?}", name)
+            }
+            XPathErrorKind::UnknownPrefix { prefix } => write!(f, "Unknown prefix {:?}", prefix),
+            XPathErrorKind::UnknownNode { name } => write!(f, "Unknown schema node {:?}", name),
+        }
+    }
+}
+
+impl Display for XPathError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for XPathError {}
+
+/// An error in the expression of a statement, with the span mapped onto the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpressionError {
+    pub kind: XPathErrorKind,
+
+    /// The module the span refers to
+    pub module: ModuleId,
+    pub span: Span,
+}
+
+impl Display for ExpressionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Slash,
+    DoubleSlash,
+    Dot,
+    DotDot,
+    At,
+    Comma,
+    ColonColon,
+    LeftParen,
+    RightParen,
+    LeftBracket,
+    RightBracket,
+    Pipe,
+    Plus,
+    Minus,
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+
+    /// "*" as a name test
+    Star,
+
+    /// "*" as the multiplication operator
+    Multiply,
+
+    /// "and", "or", "div" or "mod" as an operator
+    OperatorName(String),
+
+    /// An NCName, a QName, or a "prefix:*" name test
+    Name(String),
+    Literal(String),
+    Number(f64),
+    Variable(String),
+}
+
+/// Parses an XPath expression
+pub fn parse_xpath(text: &str) -> Result<Expr, XPathError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+        end: text.len(),
+    };
+
+    let expr = parser.expr()?;
+
+    match parser.tokens.get(parser.position) {
+        None => Ok(expr),
+        Some((_, span)) => Err(XPathError {
+            kind: XPathErrorKind::UnexpectedToken,
+            span: *span,
+        }),
+    }
+}
+
+/// Checks the expressions of statements, such as "must", against a compiled schema
+pub struct ExpressionChecker<'r, 's> {
+    repository: &'r ModuleRepository,
+    schema: &'s Schema,
+
+    /// The module and name of every schema node, for steps that can't be followed through the
+    /// schema
+    known: HashSet<(ModuleId, &'s str)>,
+}
+
+/// The nodes a location path can be at, each as the chain of nodes from the root including
+/// choices and cases, or None once the path can't be followed through the schema
+type Positions<'s> = Option<Vec<Vec<&'s SchemaNode>>>;
+
+impl<'r, 's> ExpressionChecker<'r, 's> {
+    pub fn new(repository: &'r ModuleRepository, schema: &'s Schema) -> Self {
+        let mut known = HashSet::new();
+        collect_nodes(&schema.nodes, &mut known);
+
+        Self {
+            repository,
+            schema,
+            known,
+        }
+    }
+
+    /// Parses the expression of a statement of the schema node at the given path, and checks
+    /// that the names in it refer to schema nodes
+    ///
+    /// Steps on the child, parent and self axes are followed through the schema, starting at the
+    /// node or at the root. Once a path takes any other axis, or starts at an expression other
+    /// than "current()", its prefixed names are only checked against all nodes of the schema.
+    ///
+    pub fn check(&self, path: &[(ModuleId, &str)], property: &Property) -> Vec<ExpressionError> {
+        let argument = property.argument().unwrap_or_default();
+        let module = property.source.module;
+        let error = |XPathError { kind, span }| ExpressionError {
+            kind,
+            module,
+            span: argument_span(&property.node, span.0, span.1 + 1 - span.0),
+        };
+
+        let expr = match parse_xpath(&argument) {
+            Ok(expr) => expr,
+            Err(err) => return vec![error(err)],
+        };
+
+        let mut chain = vec![];
+        let mut children = &self.schema.nodes;
+
+        for (module, name) in path {
+            match children
+                .iter()
+                .find(|node| node.module == *module && node.name == *name)
+            {
+                Some(node) => {
+                    chain.push(node);
+                    children = &node.children;
+                }
+                None => break,
+            }
+        }
+
+        let current = (chain.len() == path.len()).then(|| vec![chain]);
+        let mut walker = Walker {
+            checker: self,
+            module,
+            default: path.last().map_or(module, |(module, _)| *module),
+            current: current.clone(),
+            errors: vec![],
+        };
+
+        walker.expr(&expr, &current);
+        walker.errors.into_iter().map(error).collect()
+    }
+}
+
+/// Follows the location paths of a single expression through the schema
+struct Walker<'c, 's> {
+    checker: &'c ExpressionChecker<'c, 's>,
+
+    /// The module with the statement, which its prefixes are resolved in
+    module: ModuleId,
+
+    /// The module of unprefixed names, which is the module of the context node
+    default: ModuleId,
+
+    /// The context node of the expression, which is the current() node
+    current: Positions<'s>,
+    errors: Vec<XPathError>,
+}
+
+impl<'s> Walker<'_, 's> {
+    fn expr(&mut self, expr: &Expr, context: &Positions<'s>) {
+        match &expr.kind {
+            ExprKind::Binary { left, right, .. } => {
+                self.expr(left, context);
+                self.expr(right, context);
+            }
+            ExprKind::Negate(expr) => self.expr(expr, context),
+            ExprKind::Literal(_) | ExprKind::Number(_) | ExprKind::Variable(_) => {}
+            ExprKind::Function { arguments, .. } => {
+                for argument in arguments {
+                    self.expr(argument, context);
+                }
+            }
+            ExprKind::Filter { expr, predicates } => {
+                self.expr(expr, context);
+
+                for predicate in predicates {
+                    self.expr(predicate, &None);
+                }
+            }
+            ExprKind::Path { start, steps } => {
+                let mut positions = match start {
+                    PathStart::Context => context.clone(),
+                    PathStart::Root => Some(vec![vec![]]),
+                    PathStart::Expr(expr) => {
+                        self.expr(expr, context);
+
+                        match &expr.kind {
+                            ExprKind::Function { name, arguments }
+                                if name == "current" && arguments.is_empty() =>
+                            {
+                                self.current.clone()
+                            }
+                            _ => None,
+                        }
+                    }
+                };
+
+                for step in steps {
+                    positions = self.step(positions, step);
+
+                    for predicate in step.predicates.iter() {
+                        self.expr(predicate, &positions);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the nodes a step goes to, reporting its name if it isn't a schema node there
+    fn step(&mut self, positions: Positions<'s>, step: &Step) -> Positions<'s> {
+        let (prefix, name) = match &step.test {
+            NodeTest::Name { prefix, name } => (prefix.as_ref(), Some(name)),
+            NodeTest::Wildcard { prefix } => (prefix.as_ref(), None),
+            _ => (None, None),
+        };
+
+        let module = match prefix {
+            Some(prefix) => {
+                match resolve_prefix(self.checker.repository, self.module, Some(prefix)) {
+                    Some(module) => Some(module),
+                    None => {
+                        let prefix = prefix.clone();
+                        self.error(XPathErrorKind::UnknownPrefix { prefix }, step.span);
+                        return None;
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let unknown = |name: &str| XPathErrorKind::UnknownNode {
+            name: match prefix {
+                Some(prefix) => format!("{}:{}", prefix, name),
+                None => name.to_string(),
+            },
+        };
+
+        let chains = match positions {
+            Some(chains) => chains,
+            None => {
+                // Without a position in the schema, only prefixed names can be checked
+                if let (Some(module), Some(name)) = (module, name) {
+                    if !self.checker.known.contains(&(module, name.as_str())) {
+                        self.error(unknown(name), step.span);
+                    }
+                }
+
+                return None;
+            }
+        };
+
+        let mut next = vec![];
+
+        match (step.axis, &step.test) {
+            (Axis::Child, NodeTest::Name { name, .. }) => {
+                let module = module.unwrap_or(self.default);
+
+                for chain in chains.iter() {
+                    let mut chain = chain.clone();
+
+                    if find_child(self.children(&chain), module, name, &mut chain) {
+                        next.push(chain);
+                    }
+                }
+
+                if next.is_empty() && !chains.is_empty() {
+                    self.error(unknown(name), step.span);
+                    return None;
+                }
+            }
+            (Axis::Child, NodeTest::Wildcard { .. } | NodeTest::Node) => {
+                for chain in chains.iter() {
+                    collect_children(self.children(chain), module, chain, &mut next);
+                }
+            }
+            (Axis::Child, _) => {}
+            (Axis::Parent, NodeTest::Node) => {
+                for mut chain in chains {
+                    if chain.pop().is_some() {
+                        while chain.last().is_some_and(|node| {
+                            matches!(node.kind, SchemaNodeKind::Choice | SchemaNodeKind::Case)
+                        }) {
+                            chain.pop();
+                        }
+
+                        next.push(chain);
+                    }
+                }
+
+                // Siblings have the same parent, which only needs to be followed once
+                next.dedup_by(|a, b| {
+                    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| ptr::eq(*a, *b))
+                });
+            }
+            (Axis::Self_, NodeTest::Node) => next = chains,
+            _ => return self.step(None, step),
+        }
+
+        Some(next)
+    }
+
+    fn children(&self, chain: &[&'s SchemaNode]) -> &'s [SchemaNode] {
+        match chain.last() {
+            Some(node) => &node.children,
+            None => &self.checker.schema.nodes,
+        }
+    }
+
+    fn error(&mut self, kind: XPathErrorKind, span: Span) {
+        self.errors.push(XPathError { kind, span });
+    }
+}
+
+/// Finds a child data node, adding the choices and cases on the way and the node to the chain
+fn find_child<'s>(
+    children: &'s [SchemaNode],
+    module: ModuleId,
+    name: &str,
+    chain: &mut Vec<&'s SchemaNode>,
+) -> bool {
+    for child in children {
+        match child.kind {
+            SchemaNodeKind::Choice | SchemaNodeKind::Case => {
+                chain.push(child);
+
+                if find_child(&child.children, module, name, chain) {
+                    return true;
+                }
+
+                chain.pop();
+            }
+            _ if child.module == module && child.name == name => {
+                chain.push(child);
+                return true;
+            }
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Adds a chain for every child data node, of the given module if any, looking through choices
+/// and cases
+fn collect_children<'s>(
+    children: &'s [SchemaNode],
+    module: Option<ModuleId>,
+    chain: &[&'s SchemaNode],
+    chains: &mut Vec<Vec<&'s SchemaNode>>,
+) {
+    for child in children {
+        let mut child_chain = chain.to_vec();
+        child_chain.push(child);
+
+        match child.kind {
+            SchemaNodeKind::Choice | SchemaNodeKind::Case => {
+                collect_children(&child.children, module, &child_chain, chains)
+            }
+            _ if module.is_none_or(|module| child.module == module) => chains.push(child_chain),
+            _ => {}
+        }
+    }
+}
+
+fn collect_nodes<'a>(nodes: &'a [SchemaNode], known: &mut HashSet<(ModuleId, &'a str)>) {
+    for node in nodes {
+        known.insert((node.module, node.name.as_str()));
+        collect_nodes(&node.children, known);
+    }
+}
+
+/// Returns every location step in an expression, including those within predicates
+pub fn collect_steps<'a>(expr: &'a Expr, steps: &mut Vec<&'a Step>) {
+    match &expr.kind {
+        ExprKind::Binary { left, right, .. } => {
+            collect_steps(left, steps);
+            collect_steps(right, steps);
+        }
+        ExprKind::Negate(expr) => collect_steps(expr, steps),
+        ExprKind::Literal(_) | ExprKind::Number(_) | ExprKind::Variable(_) => {}
+        ExprKind::Function { arguments, .. } => {
+            for argument in arguments {
+                collect_steps(argument, steps);
+            }
+        }
+        ExprKind::Filter { expr, predicates } => {
+            collect_steps(expr, steps);
+
+            for predicate in predicates {
+                collect_steps(predicate, steps);
+            }
+        }
+        ExprKind::Path { start, steps: path } => {
+            if let PathStart::Expr(expr) = start {
+                collect_steps(expr, steps);
+            }
+
+            for step in path {
+                steps.push(step);
+
+                for predicate in step.predicates.iter() {
+                    collect_steps(predicate, steps);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the length in bytes of the NCName at the start of the text, or 0 if there is none
+fn name_length(text: &str) -> usize {
+    let mut chars = text.char_indices();
+
+    match chars.next() {
+        Some((_, c)) if c.is_alphabetic() || c == '_' => {}
+        _ => return 0,
+    }
+
+    chars
+        .find(|(_, c)| !(c.is_alphanumeric() || matches!(c, '.' | '-' | '_')))
+        .map_or(text.len(), |(i, _)| i)
+}
+
+/// Splits an expression into tokens, following the disambiguation rules of XPath section 3.7
+fn tokenize(text: &str) -> Result<Vec<(Token, Span)>, XPathError> {
+    let mut tokens: Vec<(Token, Span)> = vec![];
+    let mut position = 0;
+
+    while let Some(c) = text[position..].chars().next() {
+        let rest = &text[position..];
+
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+
+        // A name or "*" is an operator if it follows a token that can end an operand
+        let operator_position = tokens.last().is_some_and(|(token, _)| {
+            !matches!(
+                token,
+                Token::At
+                    | Token::ColonColon
+                    | Token::LeftParen
+                    | Token::LeftBracket
+                    | Token::Comma
+                    | Token::Slash
+                    | Token::DoubleSlash
+                    | Token::Pipe
+                    | Token::Plus
+                    | Token::Minus
+                    | Token::Equal
+                    | Token::NotEqual
+                    | Token::Less
+                    | Token::LessOrEqual
+                    | Token::Greater
+                    | Token::GreaterOrEqual
+                    | Token::Multiply
+                    | Token::OperatorName(_)
+            )
+        });
+
+        let next = rest[c.len_utf8()..].chars().next();
+
+        let (token, length) = match c {
+            '/' if next == Some('/') => (Token::DoubleSlash, 2),
+            '/' => (Token::Slash, 1),
+            '.' if next == Some('.') => (Token::DotDot, 2),
+            '.' if !next.is_some_and(|c| c.is_ascii_digit()) => (Token::Dot, 1),
+            '.' | '0'..='9' => {
+                let integer = rest.bytes().take_while(u8::is_ascii_digit).count();
+                let fraction = match rest[integer..].strip_prefix('.') {
+                    Some(fraction) => 1 + fraction.bytes().take_while(u8::is_ascii_digit).count(),
+                    None => 0,
+                };
+
+                let length = integer + fraction;
+                let number = rest[..length].parse().unwrap_or(f64::NAN);
+                (Token::Number(number), length)
+            }
+            '@' => (Token::At, 1),
+            ',' => (Token::Comma, 1),
+            ':' if next == Some(':') => (Token::ColonColon, 2),
+            '(' => (Token::LeftParen, 1),
+            ')' => (Token::RightParen, 1),
+            '[' => (Token::LeftBracket, 1),
+            ']' => (Token::RightBracket, 1),
+            '|' => (Token::Pipe, 1),
+            '+' => (Token::Plus, 1),
+            '-' => (Token::Minus, 1),
+            '=' => (Token::Equal, 1),
+            '!' if next == Some('=') => (Token::NotEqual, 2),
+            '<' if next == Some('=') => (Token::LessOrEqual, 2),
+            '<' => (Token::Less, 1),
+            '>' if next == Some('=') => (Token::GreaterOrEqual, 2),
+            '>' => (Token::Greater, 1),
+            '*' if operator_position => (Token::Multiply, 1),
+            '*' => (Token::Star, 1),
+            '"' | '\'' => match rest[1..].find(c) {
+                Some(end) => (Token::Literal(rest[1..end + 1].to_string()), end + 2),
+                None => {
+                    return Err(XPathError {
+                        kind: XPathErrorKind::UnterminatedLiteral,
+                        span: (position, text.len() - 1),
+                    })
+                }
+            },
+            '$' if name_length(&rest[1..]) > 0 => {
+                let length = 1 + qname_length(&rest[1..]);
+                (Token::Variable(rest[1..length].to_string()), length)
+            }
+            _ if name_length(rest) > 0 => {
+                let length = qname_length(rest);
+                let name = &rest[..length];
+
+                if operator_position && matches!(name, "and" | "or" | "div" | "mod") {
+                    (Token::OperatorName(name.to_string()), length)
+                } else {
+                    (Token::Name(name.to_string()), length)
+                }
+            }
+            c => {
+                return Err(XPathError {
+                    kind: XPathErrorKind::UnexpectedChar { c },
+                    span: (position, position + c.len_utf8() - 1),
+                })
+            }
+        };
+
+        tokens.push((token, (position, position + length - 1)));
+        position += length;
+    }
+
+    Ok(tokens)
+}
+
+/// Returns the length of the QName, or "prefix:*" name test, at the start of the text
+fn qname_length(text: &str) -> usize {
+    let prefix = name_length(text);
+    let rest = &text[prefix..];
+
+    if !rest.starts_with(':') || rest.starts_with("::") {
+        return prefix;
+    }
+
+    match &rest[1..] {
+        local if local.starts_with('*') => prefix + 2,
+        local if name_length(local) > 0 => prefix + 1 + name_length(local),
+        _ => prefix,
+    }
+}
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    position: usize,
+    end: usize,
+}
+
+type ParseResult<T> = Result<T, XPathError>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> ParseResult<(Token, Span)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        token.ok_or(XPathError {
+            kind: XPathErrorKind::UnexpectedEnd,
+            span: (self.end, self.end),
+        })
+    }
+
+    fn accept(&mut self, expected: &Token) -> bool {
+        let found = self.peek() == Some(expected);
+        self.position += found as usize;
+        found
+    }
+
+    fn expect(&mut self, expected: Token) -> ParseResult<()> {
+        match self.next()? {
+            (token, _) if token == expected => Ok(()),
+            (_, span) => Err(Self::unexpected(span)),
+        }
+    }
+
+    fn unexpected(span: Span) -> XPathError {
+        XPathError {
+            kind: XPathErrorKind::UnexpectedToken,
+            span,
+        }
+    }
+
+    /// The offset of the next token
+    fn start(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map_or(self.end, |(_, span)| span.0)
+    }
+
+    /// The span from an offset to the end of the last token
+    fn span_from(&self, start: usize) -> Span {
+        (start, self.tokens[self.position - 1].1 .1)
+    }
+
+    fn expr(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::and,
+            &[(Token::OperatorName("or".into()), BinaryOperator::Or)],
+        )
+    }
+
+    fn and(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::equality,
+            &[(Token::OperatorName("and".into()), BinaryOperator::And)],
+        )
+    }
+
+    fn equality(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::relational,
+            &[
+                (Token::Equal, BinaryOperator::Equal),
+                (Token::NotEqual, BinaryOperator::NotEqual),
+            ],
+        )
+    }
+
+    fn relational(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::additive,
+            &[
+                (Token::Less, BinaryOperator::Less),
+                (Token::LessOrEqual, BinaryOperator::LessOrEqual),
+                (Token::Greater, BinaryOperator::Greater),
+                (Token::GreaterOrEqual, BinaryOperator::GreaterOrEqual),
+            ],
+        )
+    }
+
+    fn additive(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::multiplicative,
+            &[
+                (Token::Plus, BinaryOperator::Add),
+                (Token::Minus, BinaryOperator::Subtract),
+            ],
+        )
+    }
+
+    fn multiplicative(&mut self) -> ParseResult<Expr> {
+        self.binary(
+            Self::unary,
+            &[
+                (Token::Multiply, BinaryOperator::Multiply),
+                (Token::OperatorName("div".into()), BinaryOperator::Divide),
+                (Token::OperatorName("mod".into()), BinaryOperator::Modulo),
+            ],
+        )
+    }
+
+    /// Parses a left associative chain of binary operations
+    fn binary(
+        &mut self,
+        operand: fn(&mut Self) -> ParseResult<Expr>,
+        operators: &[(Token, BinaryOperator)],
+    ) -> ParseResult<Expr> {
+        let start = self.start();
+        let mut left = operand(self)?;
+
+        loop {
+            let operator = operators
+                .iter()
+                .find(|(token, _)| self.peek() == Some(token))
+                .map(|(_, operator)| *operator);
+
+            let operator = match operator {
+                Some(operator) => operator,
+                None => return Ok(left),
+            };
+
+            self.position += 1;
+            let right = operand(self)?;
+
+            left = Expr {
+                kind: ExprKind::Binary {
+                    operator,
+                    left: Box::new(left),
+                    right: Box::new(right),
+                },
+                span: self.span_from(start),
+            };
+        }
+    }
+
+    fn unary(&mut self) -> ParseResult<Expr> {
+        let start = self.start();
+
+        if self.accept(&Token::Minus) {
+            let expr = self.unary()?;
+
+            return Ok(Expr {
+                kind: ExprKind::Negate(Box::new(expr)),
+                span: self.span_from(start),
+            });
+        }
+
+        self.binary(Self::path, &[(Token::Pipe, BinaryOperator::Union)])
+    }
+
+    /// PathExpr ::= LocationPath | FilterExpr | FilterExpr ('/' | '//') RelativeLocationPath
+    fn path(&mut self) -> ParseResult<Expr> {
+        let start = self.start();
+
+        let is_filter = match (self.peek(), self.peek_at(1)) {
+            (
+                Some(Token::Literal(_) | Token::Number(_) | Token::Variable(_) | Token::LeftParen),
+                _,
+            ) => true,
+            (Some(Token::Name(name)), Some(Token::LeftParen)) => !NODE_TYPES.contains(&&**name),
+            _ => false,
+        };
+
+        let (start_kind, mut steps) = if is_filter {
+            let mut expr = self.primary()?;
+            let predicates = self.predicates()?;
+
+            if !predicates.is_empty() {
+                expr = Expr {
+                    kind: ExprKind::Filter {
+                        expr: Box::new(expr),
+                        predicates,
+                    },
+                    span: self.span_from(start),
+                };
+            }
+
+            if !matches!(self.peek(), Some(Token::Slash | Token::DoubleSlash)) {
+                return Ok(expr);
+            }
+
+            (PathStart::Expr(Box::new(expr)), vec![])
+        } else if self.accept(&Token::Slash) {
+            // A lone "/" selects the root
+            if !matches!(
+                self.peek(),
+                Some(Token::Dot | Token::DotDot | Token::At | Token::Star | Token::Name(_))
+            ) {
+                return Ok(Expr {
+                    kind: ExprKind::Path {
+                        start: PathStart::Root,
+                        steps: vec![],
+                    },
+                    span: self.span_from(start),
+                });
+            }
+
+            (PathStart::Root, vec![self.step()?])
+        } else if self.accept(&Token::DoubleSlash) {
+            let span = self.span_from(start);
+            (
+                PathStart::Root,
+                vec![descendant_or_self(span), self.step()?],
+            )
+        } else {
+            (PathStart::Context, vec![self.step()?])
+        };
+
+        loop {
+            let start = self.start();
+
+            if self.accept(&Token::Slash) {
+                steps.push(self.step()?);
+            } else if self.accept(&Token::DoubleSlash) {
+                steps.push(descendant_or_self(self.span_from(start)));
+                steps.push(self.step()?);
+            } else {
+                break;
+            }
+        }
+
+        Ok(Expr {
+            kind: ExprKind::Path {
+                start: start_kind,
+                steps,
+            },
+            span: self.span_from(start),
+        })
+    }
+
+    /// Step ::= AxisSpecifier NodeTest Predicate* | '.' | '..'
+    fn step(&mut self) -> ParseResult<Step> {
+        let start = self.start();
+
+        let axis = match (self.peek(), self.peek_at(1)) {
+            (Some(Token::Dot), _) | (Some(Token::DotDot), _) => {
+                let axis = match self.next()?.0 {
+                    Token::Dot => Axis::Self_,
+                    _ => Axis::Parent,
+                };
+
+                return Ok(Step {
+                    axis,
+                    test: NodeTest::Node,
+                    predicates: vec![],
+                    span: self.span_from(start),
+                });
+            }
+            (Some(Token::At), _) => {
+                self.position += 1;
+                Axis::Attribute
+            }
+            (Some(Token::Name(name)), Some(Token::ColonColon)) => {
+                let name = name.clone();
+                let span = self.tokens[self.position].1;
+                self.position += 2;
+
+                Axis::from_name(&name).ok_or(XPathError {
+                    kind: XPathErrorKind::UnknownAxis { name },
+                    span,
+                })?
+            }
+            _ => Axis::Child,
+        };
+
+        let test = self.node_test()?;
+        let predicates = self.predicates()?;
+
+        Ok(Step {
+            axis,
+            test,
+            predicates,
+            span: self.span_from(start),
+        })
+    }
+
+    fn node_test(&mut self) -> ParseResult<NodeTest> {
+        let (token, span) = self.next()?;
+
+        let name = match token {
+            Token::Star => return Ok(NodeTest::Wildcard { prefix: None }),
+            Token::Name(name) => name,
+            _ => return Err(Self::unexpected(span)),
+        };
+
+        if let Some(prefix) = name.strip_suffix(":*") {
+            return Ok(NodeTest::Wildcard {
+                prefix: Some(prefix.to_string()),
+            });
+        }
+
+        if self.peek() != Some(&Token::LeftParen) {
+            return Ok(match name.split_once(':') {
+                Some((prefix, name)) => NodeTest::Name {
+                    prefix: Some(prefix.to_string()),
+                    name: name.to_string(),
+                },
+                None => NodeTest::Name { prefix: None, name },
+            });
+        }
+
+        self.position += 1;
+
+        let test = match name.as_str() {
+            "node" => NodeTest::Node,
+            "text" => NodeTest::Text,
+            "comment" => NodeTest::Comment,
+            "processing-instruction" => match self.peek().cloned() {
+                Some(Token::Literal(target)) => {
+                    self.position += 1;
+                    NodeTest::ProcessingInstruction(Some(target))
+                }
+                _ => NodeTest::ProcessingInstruction(None),
+            },
+            _ => return Err(Self::unexpected(span)),
+        };
+
+        self.expect(Token::RightParen)?;
+        Ok(test)
+    }
+
+    fn predicates(&mut self) -> ParseResult<Vec<Expr>> {
+        let mut predicates = vec![];
+
+        while self.accept(&Token::LeftBracket) {
+            predicates.push(self.expr()?);
+            self.expect(Token::RightBracket)?;
+        }
+
+        Ok(predicates)
+    }
+
+    /// PrimaryExpr ::= VariableReference | '(' Expr ')' | Literal | Number | FunctionCall
+    fn primary(&mut self) -> ParseResult<Expr> {
+        let (token, span) = self.next()?;
+
+        let kind = match token {
+            Token::Literal(text) => ExprKind::Literal(text),
+            Token::Number(number) => ExprKind::Number(number),
+            Token::Variable(name) => ExprKind::Variable(name),
+            Token::LeftParen => {
+                let expr = self.expr()?;
+                self.expect(Token::RightParen)?;
+                return Ok(expr);
+            }
+            Token::Name(name) => {
+                self.expect(Token::LeftParen)?;
+
+                let mut arguments = vec![];
+
+                if !self.accept(&Token::RightParen) {
+                    loop {
+                        arguments.push(self.expr()?);
+
+                        if self.accept(&Token::RightParen) {
+                            break;
+                        }
+
+                        self.expect(Token::Comma)?;
+                    }
+                }
+
+                let error = |kind| XPathError { kind, span };
+
+                match FUNCTIONS.iter().find(|(function, _, _)| *function == name) {
+                    None => return Err(error(XPathErrorKind::UnknownFunction { name })),
+                    Some((_, min, max)) if arguments.len() < *min || arguments.len() > *max => {
+                        return Err(error(XPathErrorKind::ArgumentCount { name }))
+                    }
+                    Some(_) => {}
+                }
+
+                ExprKind::Function { name, arguments }
+            }
+            _ => return Err(Self::unexpected(span)),
+        };
+
+        Ok(Expr {
+            kind,
+            span: self.span_from(span.0),
+        })
+    }
+}
+
+/// The step that "//" abbreviates
+fn descendant_or_self(span: Span) -> Step {
+    Step {
+        axis: Axis::DescendantOrSelf,
+        test: NodeTest::Node,
+        predicates: vec![],
+        span,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::path::PathBuf;
+
+    fn dedent(text: &str) -> String {
+        textwrap::dedent(text).trim_start().to_string()
+    }
+
+    #[test]
+    fn parsing() {
+        let cases = [
+            ("1 + 2 * 3", "(1 + (2 * 3))"),
+            ("a and b or not(c)", "((child::a and child::b) or not(child::c))"),
+            (
+                "../name = current()/../if:name",
+                "(parent::node()/child::name = current()/parent::node()/child::if:name)",
+            ),
+            (
+                "//interface[type = 'eth'][1]/@mtu",
+                "/descendant-or-self::node()/child::interface[(child::type = 'eth')][1]/attribute::mtu",
+            ),
+            ("/", "/"),
+            ("* * 2 div -x mod 3", "(((child::* * 2) div -child::x) mod 3)"),
+            ("a | ./b | p:*", "((child::a | self::node()/child::b) | child::p:*)"),
+            (
+                "count(ancestor::node()) > 0 and text() != \"it's\"",
+                "((count(ancestor::node()) > 0) and (child::text() != \"it's\"))",
+            ),
+            (
+                "derived-from-or-self(type, 'ianaift:ethernetCsmacd')",
+                "derived-from-or-self(child::type, 'ianaift:ethernetCsmacd')",
+            ),
+            ("$var[2]/x", "$var[2]/child::x"),
+        ];
+
+        for (text, expected) in cases {
+            let expr = parse_xpath(text).unwrap();
+            assert_eq!(expected, expr.to_string(), "{}", text);
+        }
+
+        let expr = parse_xpath("a[b = 1]/c").unwrap();
+        assert_eq!((0, 9), expr.span);
+
+        match &expr.kind {
+            ExprKind::Path { steps, .. } => {
+                assert_eq!((0, 7), steps[0].span);
+                assert_eq!((2, 6), steps[0].predicates[0].span);
+                assert_eq!((9, 9), steps[1].span);
+            }
+            kind => panic!("Expected a path, got {:?}", kind),
+        }
+
+        let error = |text| parse_xpath(text).unwrap_err();
+
+        assert_eq!(
+            XPathError {
+                kind: XPathErrorKind::UnexpectedEnd,
+                span: (4, 4)
+            },
+            error("a = ")
+        );
+        assert_eq!(
+            XPathError {
+                kind: XPathErrorKind::UnknownFunction {
+                    name: "foo".to_string()
+                },
+                span: (4, 6)
+            },
+            error("1 + foo()")
+        );
+        assert_eq!(
+            XPathErrorKind::ArgumentCount {
+                name: "current".to_string()
+            },
+            error("current(.)").kind
+        );
+        assert_eq!(
+            XPathErrorKind::UnknownAxis {
+                name: "up".to_string()
+            },
+            error("up::a").kind
+        );
+        assert_eq!(XPathErrorKind::UnterminatedLiteral, error("'abc").kind);
+        assert_eq!(XPathErrorKind::UnexpectedChar { c: '#' }, error("a#").kind);
+        assert_eq!(
+            XPathError {
+                kind: XPathErrorKind::UnexpectedToken,
+                span: (2, 2)
+            },
+            error("a b")
+        );
+    }
+
+    #[test]
+    fn prefixed_names() {
+        let mut repository = ModuleRepository::new(Vec::<PathBuf>::new());
+
+        repository.add_source(
+            "interfaces.yang",
+            dedent(
+                r#"
+                module interfaces {
+                    namespace "urn:interfaces";
+                    prefix if;
+
+                    container interfaces {
+                        list interface {
+                            key name;
+                            leaf name { type string; }
+                        }
+                    }
+                }
+                "#,
+            ),
+        );
+        repository.add_source(
+            "main.yang",
+            dedent(
+                r#"
+                module main {
+                    namespace "urn:main";
+                    prefix m;
+
+                    import interfaces { prefix if; }
+
+                    leaf interface {
+                        type string;
+                        must "/if:interfaces/if:interface[if:name = current()]";
+                        must "/if:interfaces/if:missing or /x:interfaces";
+                        must "../m:interface and if:*";
+                        must "/if:interfaces/if:name or /if:interface";
+                        must "descendant::if:name or ancestor::if:bogus";
+                        must "count(/if:interfaces";
+                    }
+                }
+                "#,
+            ),
+        );
+
+        let main = repository.load("main", None).unwrap();
+        let (schema, _) = Schema::compile(&repository);
+        let leaf = schema.node(&[(main, "interface")]).unwrap();
+        let checker = ExpressionChecker::new(&repository, &schema);
+
+        let errors: Vec<_> = leaf
+            .properties("must")
+            .flat_map(|property| checker.check(&[(main, "interface")], property))
+            .map(|error| (error.kind, error.span))
+            .collect();
+
+        assert_eq!(
+            vec![
+                (
+                    XPathErrorKind::UnknownNode {
+                        name: "if:missing".to_string()
+                    },
+                    (229, 238)
+                ),
+                (
+                    XPathErrorKind::UnknownPrefix {
+                        prefix: "x".to_string()
+                    },
+                    (244, 255)
+                ),
+                (
+                    XPathErrorKind::UnknownNode {
+                        name: "if:name".to_string()
+                    },
+                    (328, 334)
+                ),
+                (
+                    XPathErrorKind::UnknownNode {
+                        name: "if:interface".to_string()
+                    },
+                    (340, 351)
+                ),
+                (
+                    XPathErrorKind::UnknownNode {
+                        name: "if:bogus".to_string()
+                    },
+                    (392, 409)
+                ),
+                (XPathErrorKind::UnexpectedEnd, (447, 447)),
+            ],
+            errors
+        );
+    }
+}